@@ -29,10 +29,9 @@ impl<'a> Iterator for AnnotatedStringIterator<'a> {
             .annotated_string
             .annotations
             .iter()
-            .filter(|annotation| {
+            .rfind(|annotation| {
                 annotation.start <= self.current_idx && annotation.end > self.current_idx
             })
-            .last()
         {
             // 确定注释的结束位置，并确保不超过字符串长度
             let end_idx = min(annotation.end, self.annotated_string.string.len());
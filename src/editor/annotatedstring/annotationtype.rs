@@ -5,4 +5,28 @@ pub enum AnnotationType {
     Match,
     // 当前选定的匹配：如果用户按 Enter，将跳转到对应地方
     SelectedMatch,
+    // 行号栏
+    LineNumber,
+    // 行号栏中上次保存后修改过的行的标记
+    ModifiedLine,
+    // 选中的文本
+    Selection,
+    // 语法高亮：关键字
+    Keyword,
+    // 语法高亮：字符串和字符字面量
+    String,
+    // 语法高亮：数字
+    Number,
+    // 语法高亮：注释
+    Comment,
+    // 光标处的括号及与之配对的括号
+    MatchingBracket,
+    // 标尺：超出配置的列数限制的第一列
+    Ruler,
+    // 主光标之外的其他光标
+    Cursor,
+    // 代替折叠的行显示的摘要行
+    Fold,
+    // 光标所在单词在文档中的出现位置
+    WordOccurrence,
 }
\ No newline at end of file
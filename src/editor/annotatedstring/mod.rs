@@ -6,7 +6,7 @@ use std::{
 use super::ByteIdx;
 
 pub use annotationtype::AnnotationType;
-use annotation::Annotation;
+pub use annotation::Annotation;
 use annotatedstringpart::AnnotatedStringPart;
 use annotatedstringiterator::AnnotatedStringIterator;
 
@@ -46,6 +46,23 @@ impl AnnotatedString {
         });
     }
 
+    /// 在字符串开头插入内容，已有注释的索引随之后移
+    pub fn prepend(&mut self, prefix: &str) {
+        self.replace(0, 0, prefix);
+    }
+
+    /// 在字符串末尾追加内容
+    pub fn append(&mut self, suffix: &str) {
+        self.string.push_str(suffix);
+    }
+
+    /// 在字符串末尾追加带注释的内容
+    pub fn append_annotated(&mut self, suffix: &str, annotation_type: AnnotationType) {
+        let start = self.string.len();
+        self.string.push_str(suffix);
+        self.add_annotation(annotation_type, start, self.string.len());
+    }
+
     /// 清空对应字节索引左侧字符内容
     pub fn truncate_left_until(&mut self, until: ByteIdx) {
         self.replace(0, until, "");
@@ -0,0 +1,289 @@
+use std::{
+    fs::{self, read_dir, read_to_string},
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+/// 目录中的一项
+struct Entry {
+    name: String,
+    // 是否是目录。指向目录的符号链接也算作目录，失效的符号链接算作文件
+    is_dir: bool,
+}
+
+/// 浏览目录并选择要打开的文件。
+///
+/// 目录排在文件之前，各自按名称排序，第一项总是返回上一级的 `..`(根目录除外)。
+/// 被 `.gitignore` 忽略的项和 `.git` 目录不会列出。
+/// 进入子目录和返回上一级都按路径本身进行，不解析符号链接，
+/// 所以从符号链接进入的目录返回上一级时会回到链接所在的目录。
+pub struct DirectoryBrowser {
+    dir: PathBuf,
+    entries: Vec<Entry>,
+    // 当前选中项的序号
+    selected: usize,
+}
+
+impl DirectoryBrowser {
+    /// 列出目录中的项。
+    ///
+    /// # 返回值
+    /// 目录无法读取(如没有权限)时返回错误。
+    pub fn open(dir: &Path) -> Result<Self, Error> {
+        let mut entries: Vec<Entry> = read_dir(dir)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                // `metadata` 会跟随符号链接，无法读取时(失效的链接、没有权限)按文件处理
+                let is_dir = fs::metadata(entry.path()).is_ok_and(|metadata| metadata.is_dir());
+                Some(Entry { name, is_dir })
+            })
+            .collect();
+        let ignore = GitIgnore::for_dir(dir);
+        entries.retain(|entry| entry.name != ".git" && !ignore.is_ignored(&entry.name, entry.is_dir));
+        entries.sort_by(|left, right| right.is_dir.cmp(&left.is_dir).then_with(|| left.name.cmp(&right.name)));
+        if dir.parent().is_some() {
+            entries.insert(0, Entry { name: String::from(".."), is_dir: true });
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            entries,
+            selected: 0,
+        })
+    }
+
+    /// 正在浏览的目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 当前选中项的名称，目录以 `/` 结尾。目录为空时返回空字符串
+    pub fn selected_name(&self) -> String {
+        self.entries.get(self.selected).map_or_else(String::new, |entry| {
+            if entry.is_dir {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            }
+        })
+    }
+
+    /// 当前选中项的序号(从 1 开始)和项的总数
+    pub fn position(&self) -> (usize, usize) {
+        (self.selected.saturating_add(1), self.entries.len())
+    }
+
+    /// 选中下一项(`forward` 为 `true` 时)或上一项，到达一端后从另一端继续
+    pub fn select_next(&mut self, forward: bool) {
+        let len = self.entries.len();
+        let step = if forward { 1 } else { len.saturating_sub(1) };
+        self.selected = self.selected.saturating_add(step).checked_rem(len).unwrap_or(0);
+    }
+
+    /// 从当前选中项之后开始，选中下一个以指定字符开头的项(不区分大小写)
+    pub fn select_by_initial(&mut self, initial: char) {
+        let len = self.entries.len();
+        let found = (1..=len)
+            .filter_map(|offset| self.selected.saturating_add(offset).checked_rem(len))
+            .find(|index| {
+                self.entries.get(*index).is_some_and(|entry| {
+                    entry
+                        .name
+                        .chars()
+                        .next()
+                        .is_some_and(|first| first.to_lowercase().eq(initial.to_lowercase()))
+                })
+            });
+        if let Some(index) = found {
+            self.selected = index;
+        }
+    }
+
+    /// 返回上一级目录，选中刚离开的目录。已经在根目录时不做任何操作
+    ///
+    /// # 返回值
+    /// 上一级目录无法读取时返回错误，仍停留在当前目录。
+    pub fn go_up(&mut self) -> Result<(), Error> {
+        let Some(parent) = self.dir.parent() else {
+            return Ok(());
+        };
+        let left = self.dir.file_name().map(|name| name.to_string_lossy().into_owned());
+        let mut browser = Self::open(parent)?;
+        if let Some(index) = left.and_then(|left| browser.entries.iter().position(|entry| entry.name == left)) {
+            browser.selected = index;
+        }
+        *self = browser;
+        Ok(())
+    }
+
+    /// 打开当前选中的项：目录就进入该目录，文件就返回它的路径。
+    ///
+    /// # 返回值
+    /// 选中的是文件时返回 `Some(路径)`；进入了目录或目录为空时返回 `None`。
+    /// 目录无法读取时返回错误，仍停留在当前目录。
+    pub fn activate(&mut self) -> Result<Option<PathBuf>, Error> {
+        let Some(entry) = self.entries.get(self.selected) else {
+            return Ok(None);
+        };
+        if entry.name == ".." {
+            self.go_up()?;
+            return Ok(None);
+        }
+        let path = self.dir.join(&entry.name);
+        if entry.is_dir {
+            *self = Self::open(&path)?;
+            Ok(None)
+        } else {
+            Ok(Some(path))
+        }
+    }
+}
+
+/// `.gitignore` 中的一条规则
+struct IgnoreRule {
+    // 去掉开头的 `!`、`/` 和末尾的 `/` 之后的模式
+    pattern: String,
+    // 以 `!` 开头的规则重新包含之前被忽略的项
+    negated: bool,
+    // 以 `/` 结尾的规则只匹配目录
+    dir_only: bool,
+    // 模式中包含 `/` 时相对于 `.gitignore` 所在的目录匹配整个路径，否则只匹配名称
+    anchored: bool,
+    // 规则所在的 `.gitignore` 到被列出的目录的相对路径，以 `/` 结尾，同一目录时为空
+    prefix: String,
+}
+
+/// 被列出的目录及其上级目录(直到仓库的根目录)中 `.gitignore` 的规则。
+///
+/// 只支持常用的语法：`*`、`?`、`**`、开头的 `!` 和 `/`、末尾的 `/`。
+struct GitIgnore {
+    // 按优先级从低到高排列，后面的规则覆盖前面的规则
+    rules: Vec<IgnoreRule>,
+}
+
+impl GitIgnore {
+    fn for_dir(dir: &Path) -> Self {
+        let mut rules = Vec::new();
+        // 从被列出的目录向上查找，遇到包含 `.git` 的仓库根目录后停止。上级目录的规则优先级更低
+        let mut prefix = String::new();
+        for ancestor in dir.ancestors() {
+            if let Ok(contents) = read_to_string(ancestor.join(".gitignore")) {
+                let parsed: Vec<IgnoreRule> = contents
+                    .lines()
+                    .filter_map(|line| Self::parse_rule(line, &prefix))
+                    .collect();
+                rules.splice(0..0, parsed);
+            }
+            if ancestor.join(".git").exists() {
+                break;
+            }
+            let Some(name) = ancestor.file_name() else {
+                break;
+            };
+            prefix = format!("{}/{prefix}", name.to_string_lossy());
+        }
+        Self { rules }
+    }
+
+    fn parse_rule(line: &str, prefix: &str) -> Option<IgnoreRule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = line.strip_prefix('!').map_or((false, line), |rest| (true, rest));
+        let (dir_only, line) = line.strip_suffix('/').map_or((false, line), |rest| (true, rest));
+        let anchored = line.contains('/');
+        let pattern = line.strip_prefix('/').unwrap_or(line);
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(IgnoreRule {
+            pattern: pattern.to_string(),
+            negated,
+            dir_only,
+            anchored,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    /// 被列出的目录中的项是否被忽略，最后一条匹配的规则决定结果
+    fn is_ignored(&self, name: &str, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only)
+                    && if rule.anchored {
+                        glob_match(&rule.pattern, &format!("{}{name}", rule.prefix))
+                    } else {
+                        glob_match(&rule.pattern, name)
+                    }
+            })
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+/// 按 `.gitignore` 的规则匹配：`*` 和 `?` 不匹配 `/`，`**` 匹配任意内容
+fn glob_match(pattern: &str, text: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**") {
+        let rest = rest.strip_prefix('/').unwrap_or(rest);
+        return text
+            .char_indices()
+            .map(|(index, _)| index)
+            .chain([text.len()])
+            .any(|index| text.get(index..).is_some_and(|tail| glob_match(rest, tail)));
+    }
+    let mut pattern_chars = pattern.chars();
+    match pattern_chars.next() {
+        None => text.is_empty(),
+        Some('*') => {
+            let rest = pattern_chars.as_str();
+            text.char_indices()
+                .map(|(index, _)| index)
+                .chain([text.len()])
+                .take_while(|index| text.get(..*index).is_some_and(|skipped| !skipped.contains('/')))
+                .any(|index| text.get(index..).is_some_and(|tail| glob_match(rest, tail)))
+        }
+        Some(expected) => {
+            let mut text_chars = text.chars();
+            match text_chars.next() {
+                Some(actual) if (expected == '?' && actual != '/') || expected == actual => {
+                    glob_match(pattern_chars.as_str(), text_chars.as_str())
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_gitignore_wildcards() {
+        assert!(glob_match("*.o", "main.o"));
+        assert!(!glob_match("*.o", "main.rs"));
+        assert!(glob_match("target", "target"));
+        assert!(glob_match("?at", "cat"));
+        assert!(!glob_match("src/*.rs", "src/a/b.rs"));
+        assert!(glob_match("**/build", "a/b/build"));
+        assert!(glob_match("docs/**", "docs/a/b"));
+    }
+
+    #[test]
+    fn later_rules_override_earlier_ones() {
+        let ignore = GitIgnore {
+            rules: ["*.log", "!keep.log", "build/", "/src/gen"]
+                .iter()
+                .filter_map(|line| GitIgnore::parse_rule(line, "src/"))
+                .collect(),
+        };
+        assert!(ignore.is_ignored("debug.log", false));
+        assert!(!ignore.is_ignored("keep.log", false));
+        assert!(ignore.is_ignored("build", true));
+        assert!(!ignore.is_ignored("build", false));
+        assert!(ignore.is_ignored("gen", true));
+        assert!(!ignore.is_ignored("main.rs", false));
+    }
+}
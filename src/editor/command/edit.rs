@@ -1,32 +1,91 @@
 use crossterm::event::{
-    KeyCode::{Backspace, Char, Delete, Enter, Tab},
+    KeyCode::{BackTab, Backspace, Char, Delete, Down, Enter, Tab, Up},
     KeyEvent, KeyModifiers,
 };
 
 /// 编辑操作枚举
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Edit {
     Insert(char),
     InsertNewline,
     Delete,
     DeleteBackward,
+    DeleteWordBackward,
+    DeleteToLineEnd,
+    // 粘贴的文本(括号粘贴模式下终端一次性发送的内容)
+    Paste(String),
+    // 对选中的行整体添加或取消注释
+    ToggleBlockComment,
+    // 逐行添加或取消选中的行(没有选区时为当前行)的注释
+    ToggleComment,
+    // 复制当前行
+    DuplicateLine,
+    // 将下一行合并到当前行的末尾
+    JoinLine,
+    // 删除当前行(没有默认的按键，模式编辑中的 `dd` 使用)
+    DeleteLine,
+    // 将当前行与上一行交换
+    MoveLineUp,
+    // 将当前行与下一行交换
+    MoveLineDown,
+    // 有选区时增加选中的行的缩进，否则插入制表符
+    Indent,
+    // 减少选中的行(没有选区时为当前行)的缩进
+    Dedent,
 }
 
+/// 将按键转换为编辑命令。
+///
+/// 带 Alt 或 Ctrl 的字符键不会被当作文本插入：除了下面明确绑定的组合键外都返回 `Err`，
+/// 由 `Command::try_from` 继续尝试转换为 `Move`/`System` 命令，或者被忽略。
 impl TryFrom<KeyEvent> for Edit {
     type Error = String;
     fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
         match (event.code, event.modifiers) {
-            (Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+            // 只有不带修饰键(或只带 Shift)的可见字符才作为文本插入，
+            // 控制字符(如部分终端发送的 NUL、ESC)不能进入缓冲区
+            (Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT)
+                if !character.is_control() =>
+            {
                 Ok(Self::Insert(character))
             }
-            (Tab, KeyModifiers::NONE) => Ok(Self::Insert('\t')),
+            (Tab, KeyModifiers::NONE) => Ok(Self::Indent),
+            (BackTab, KeyModifiers::SHIFT | KeyModifiers::NONE) => Ok(Self::Dedent),
             (Enter, KeyModifiers::NONE) => Ok(Self::InsertNewline),
             (Backspace, KeyModifiers::NONE) => Ok(Self::DeleteBackward),
             (Delete, KeyModifiers::NONE) => Ok(Self::Delete),
+            (Char('w'), KeyModifiers::CONTROL) => Ok(Self::DeleteWordBackward),
+            (Char('k'), KeyModifiers::CONTROL) => Ok(Self::DeleteToLineEnd),
+            (Char('b'), KeyModifiers::CONTROL) => Ok(Self::ToggleBlockComment),
+            (Char('d'), KeyModifiers::CONTROL) => Ok(Self::DuplicateLine),
+            (Char('j'), KeyModifiers::CONTROL) => Ok(Self::JoinLine),
+            (Up, KeyModifiers::ALT) => Ok(Self::MoveLineUp),
+            (Down, KeyModifiers::ALT) => Ok(Self::MoveLineDown),
+            // 大多数终端把 Ctrl+/ 发送为 Ctrl+7
+            (Char('/' | '7'), KeyModifiers::CONTROL) => Ok(Self::ToggleComment),
             _ => Err(format!(
                 "Unsupported key code {:?} with modifiers {:?}",
                 event.code, event.modifiers
             )),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(character: char, modifiers: KeyModifiers) -> Result<Edit, String> {
+        Edit::try_from(KeyEvent::new(Char(character), modifiers))
+    }
+
+    #[test]
+    fn only_plain_and_shifted_characters_are_inserted() {
+        assert!(matches!(edit('a', KeyModifiers::NONE), Ok(Edit::Insert('a'))));
+        assert!(matches!(edit('A', KeyModifiers::SHIFT), Ok(Edit::Insert('A'))));
+        assert!(edit('a', KeyModifiers::ALT).is_err());
+        assert!(edit('a', KeyModifiers::CONTROL).is_err());
+        assert!(edit('A', KeyModifiers::SHIFT | KeyModifiers::ALT).is_err());
+        // 绑定了编辑命令的组合键也不是插入
+        assert!(matches!(edit('w', KeyModifiers::CONTROL), Ok(Edit::DeleteWordBackward)));
+    }
+}
@@ -0,0 +1,234 @@
+use crossterm::event::{
+    KeyCode::{self, BackTab, Backspace, Char, Delete, Down, End, Enter, Esc, Home, Insert, Left, PageDown, PageUp, Right, Tab, Up, F},
+    KeyEvent, KeyModifiers,
+};
+use toml::Table;
+
+use super::{Command, Edit, Move, System};
+
+/// 用户自定义的按键绑定。
+///
+/// 这里只保存配置文件中出现的绑定，查找不到的按键仍按内置的默认绑定转换，
+/// 因此空的按键映射与没有按键配置时的行为相同。
+#[derive(Clone, Default)]
+pub struct Keymap {
+    bindings: Vec<(KeyCode, KeyModifiers, Command)>,
+}
+
+impl Keymap {
+    pub const fn new() -> Self {
+        Self { bindings: Vec::new() }
+    }
+
+    /// 根据配置文件中的 `[keys]` 表构建按键映射，表的键为操作名，值为按键，例如 `save = "ctrl+w"`。
+    ///
+    /// # 返回值
+    /// 出现未知的操作名、无法识别的按键，或者同一个按键绑定了多个操作时，返回描述错误的信息。
+    pub fn from_table(table: &Table) -> Result<Self, String> {
+        let mut keymap = Self::new();
+        // 记录每个按键绑定的操作名，用于报告冲突
+        let mut names: Vec<&str> = Vec::new();
+        for (name, value) in table {
+            let command = Self::action(name).ok_or_else(|| format!("unknown action '{name}' in 'keys'"))?;
+            let spec = value
+                .as_str()
+                .ok_or_else(|| format!("key for '{name}' must be a string"))?;
+            let (code, modifiers) =
+                Self::parse_key(spec).ok_or_else(|| format!("invalid key '{spec}' for '{name}'"))?;
+            if let Some(index) = keymap
+                .bindings
+                .iter()
+                .position(|binding| binding.0 == code && binding.1 == modifiers)
+            {
+                let other = names.get(index).copied().unwrap_or_default();
+                return Err(format!("'{spec}' is bound to both '{other}' and '{name}'"));
+            }
+            keymap.bindings.push((code, modifiers, command));
+            names.push(name);
+        }
+        Ok(keymap)
+    }
+
+    /// 查找按键绑定的命令
+    pub fn get(&self, event: KeyEvent) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(code, modifiers, _)| *code == event.code && *modifiers == event.modifiers)
+            .map(|(_, _, command)| command.clone())
+    }
+
+    /// 将操作名转换为命令。只有不带参数的命令才能绑定到按键
+    fn action(name: &str) -> Option<Command> {
+        let command = match name {
+            "save" => Command::System(System::Save),
+            "quit" => Command::System(System::Quit),
+            "save_quit" => Command::System(System::SaveQuit),
+            "force_quit" => Command::System(System::ForceQuit),
+            "copy" => Command::System(System::Copy),
+            "dismiss" => Command::System(System::Dismiss),
+            "search" => Command::System(System::Search),
+            "replace" => Command::System(System::Replace),
+            "toggle_line_numbers" => Command::System(System::ToggleLineNumbers),
+            "go_to_line" => Command::System(System::GoToLine),
+            "split_line" => Command::System(System::SplitLine),
+            "join_lines" => Command::System(System::JoinLines),
+            "reload_config" => Command::System(System::ReloadConfig),
+            "toggle_line_preview" => Command::System(System::ToggleLinePreview),
+            "reload" => Command::System(System::Reload),
+            "reload_with_encoding" => Command::System(System::ReloadWithEncoding),
+            "open" => Command::System(System::Open),
+            "word_count" => Command::System(System::WordCount),
+            "open_file" => Command::System(System::OpenFile),
+            "recent_files" => Command::System(System::RecentFiles),
+            "browse" => Command::System(System::Browse),
+            "find_file" => Command::System(System::FindFile),
+            "next_buffer" => Command::System(System::NextBuffer),
+            "prev_buffer" => Command::System(System::PrevBuffer),
+            "toggle_split" => Command::System(System::ToggleSplit),
+            "focus_other_pane" => Command::System(System::FocusOtherPane),
+            "toggle_overtype" => Command::System(System::ToggleOvertype),
+            "toggle_scrollbar" => Command::System(System::ToggleScrollbar),
+            "toggle_whitespace" => Command::System(System::ToggleWhitespace),
+            "start_record" => Command::System(System::StartRecord),
+            "stop_record" => Command::System(System::StopRecord),
+            "play_macro" => Command::System(System::PlayMacro),
+            "add_cursor_below" => Command::System(System::AddCursorBelow),
+            "add_cursor_at_next_match" => Command::System(System::AddCursorAtNextMatch),
+            "toggle_fold" => Command::System(System::ToggleFold),
+            "sort_lines" => Command::System(System::SortLines),
+            "reverse_lines" => Command::System(System::ReverseLines),
+            "toggle_occurrences" => Command::System(System::ToggleOccurrences),
+            "jump_to_matching_line" => Command::System(System::JumpToMatchingLine),
+            "insert_newline" => Command::Edit(Edit::InsertNewline),
+            "delete" => Command::Edit(Edit::Delete),
+            "delete_backward" => Command::Edit(Edit::DeleteBackward),
+            "delete_word_backward" => Command::Edit(Edit::DeleteWordBackward),
+            "delete_to_line_end" => Command::Edit(Edit::DeleteToLineEnd),
+            "toggle_block_comment" => Command::Edit(Edit::ToggleBlockComment),
+            "toggle_comment" => Command::Edit(Edit::ToggleComment),
+            "duplicate_line" => Command::Edit(Edit::DuplicateLine),
+            "join_line" => Command::Edit(Edit::JoinLine),
+            "delete_line" => Command::Edit(Edit::DeleteLine),
+            "move_line_up" => Command::Edit(Edit::MoveLineUp),
+            "move_line_down" => Command::Edit(Edit::MoveLineDown),
+            "indent" => Command::Edit(Edit::Indent),
+            "dedent" => Command::Edit(Edit::Dedent),
+            "page_up" => Command::Move(Move::PageUp),
+            "page_down" => Command::Move(Move::PageDown),
+            "page_left" => Command::Move(Move::PageLeft),
+            "page_right" => Command::Move(Move::PageRight),
+            "start_of_line" => Command::Move(Move::StartOfLine),
+            "end_of_line" => Command::Move(Move::EndOfLine),
+            "up" => Command::Move(Move::Up),
+            "down" => Command::Move(Move::Down),
+            "left" => Command::Move(Move::Left),
+            "right" => Command::Move(Move::Right),
+            "word_forward" => Command::Move(Move::WordForward),
+            "word_backward" => Command::Move(Move::WordBackward),
+            "match_bracket" => Command::Move(Move::MatchBracket),
+            "document_start" => Command::Move(Move::DocumentStart),
+            "document_end" => Command::Move(Move::DocumentEnd),
+            _ => return None,
+        };
+        Some(command)
+    }
+
+    /// 解析按键，格式为用 `+` 连接的修饰键(`ctrl`、`alt`、`shift`)和按键名，例如 `ctrl+w`、`alt+pageup`、`f5`。
+    ///
+    /// 修饰键和按键名不区分大小写；按键为单个字符且带 Ctrl 或 Alt 时按小写字母处理，与终端发送的按键一致。
+    fn parse_key(spec: &str) -> Option<(KeyCode, KeyModifiers)> {
+        let mut parts: Vec<&str> = spec.split('+').collect();
+        // 按键本身是 `+` 时，最后两个部分都是空字符串
+        let key = if spec.ends_with("++") || spec == "+" {
+            parts.truncate(parts.len().saturating_sub(2));
+            "+"
+        } else {
+            parts.pop()?
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            modifiers |= match part.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+        let mut characters = key.chars();
+        let code = match (characters.next(), characters.next()) {
+            (Some(character), None) => {
+                if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                    Char(character.to_ascii_lowercase())
+                } else {
+                    Char(character)
+                }
+            }
+            _ => match key.to_lowercase().as_str() {
+                "up" => Up,
+                "down" => Down,
+                "left" => Left,
+                "right" => Right,
+                "home" => Home,
+                "end" => End,
+                "pageup" => PageUp,
+                "pagedown" => PageDown,
+                "tab" => Tab,
+                "backtab" => BackTab,
+                "enter" => Enter,
+                "esc" => Esc,
+                "backspace" => Backspace,
+                "delete" => Delete,
+                "insert" => Insert,
+                "space" => Char(' '),
+                name => F(name.strip_prefix('f')?.parse().ok().filter(|number| (1..=12).contains(number))?),
+            },
+        };
+        Some((code, modifiers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap(toml: &str) -> Result<Keymap, String> {
+        Keymap::from_table(&toml.parse::<Table>().unwrap())
+    }
+
+    #[test]
+    fn rebound_key_is_found_by_get() {
+        let keymap = keymap("save = \"ctrl+w\"\ndocument_end = \"alt+pagedown\"").unwrap();
+        let event = KeyEvent::new(Char('w'), KeyModifiers::CONTROL);
+        assert!(matches!(keymap.get(event), Some(Command::System(System::Save))));
+        let event = KeyEvent::new(PageDown, KeyModifiers::ALT);
+        assert!(matches!(keymap.get(event), Some(Command::Move(Move::DocumentEnd))));
+        // 没有绑定的按键交给默认绑定处理
+        assert!(keymap.get(KeyEvent::new(Char('s'), KeyModifiers::CONTROL)).is_none());
+    }
+
+    #[test]
+    fn invalid_actions_keys_and_conflicts_are_rejected() {
+        assert_eq!(keymap("explode = \"ctrl+e\"").err().unwrap(), "unknown action 'explode' in 'keys'");
+        assert_eq!(keymap("save = \"hyper+s\"").err().unwrap(), "invalid key 'hyper+s' for 'save'");
+        assert_eq!(keymap("save = 1").err().unwrap(), "key for 'save' must be a string");
+        let err = keymap("save = \"ctrl+w\"\nquit = \"ctrl+w\"").err().unwrap();
+        assert!(err.contains("'ctrl+w' is bound to both"), "{err}");
+        assert!(err.contains("'save'") && err.contains("'quit'"), "{err}");
+        // 大小写不同的写法也是同一个按键
+        assert!(keymap("save = \"ctrl+w\"\nquit = \"Ctrl+W\"").is_err());
+    }
+
+    #[test]
+    fn parse_key_special_cases() {
+        assert_eq!(Keymap::parse_key("ctrl++"), Some((Char('+'), KeyModifiers::CONTROL)));
+        assert_eq!(Keymap::parse_key("+"), Some((Char('+'), KeyModifiers::NONE)));
+        // 带 Ctrl 的字符按小写处理，与终端发送的按键一致
+        assert_eq!(Keymap::parse_key("ctrl+W"), Some((Char('w'), KeyModifiers::CONTROL)));
+        assert_eq!(Keymap::parse_key("W"), Some((Char('W'), KeyModifiers::NONE)));
+        assert_eq!(Keymap::parse_key("Shift+Alt+Up"), Some((Up, KeyModifiers::SHIFT | KeyModifiers::ALT)));
+        assert_eq!(Keymap::parse_key("f12"), Some((F(12), KeyModifiers::NONE)));
+        assert_eq!(Keymap::parse_key("f13"), None);
+        assert_eq!(Keymap::parse_key("f0"), None);
+        assert_eq!(Keymap::parse_key("ctrl+"), None);
+    }
+}
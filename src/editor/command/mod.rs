@@ -1,19 +1,29 @@
 use crate::prelude::*;
-use crossterm::event::Event;
-use std::convert::TryFrom;
+use crossterm::event::{Event, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::{
+    convert::TryFrom,
+    sync::{PoisonError, RwLock},
+};
 
 pub use edit::Edit;
+pub use keymap::Keymap;
 pub use movecommand::Move;
 pub use system::System;
 
 mod edit;
+mod keymap;
 mod movecommand;
 mod system;
 
+// 当前使用的自定义按键绑定，转换按键时优先于默认绑定
+static KEYMAP: RwLock<Keymap> = RwLock::new(Keymap::new());
+
 /// 操作命令枚举
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Command {
     Move(Move),
+    // 按住 Shift 移动光标以扩展选区
+    Select(Move),
     Edit(Edit),
     System(System),
 }
@@ -25,11 +35,21 @@ impl TryFrom<Event> for Command {
     fn try_from(event: Event) -> Result<Self, Self::Error> {
         match event {
             Event::Key(key_event) => {
+                // 优先使用自定义的按键绑定
+                if let Some(command) = KEYMAP
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .get(key_event)
+                {
+                    return Ok(command);
+                }
                 // 尝试将 key_event 转换为 Edit 命令枚举。如果成功，则将其包装到 Command::Edit 中。
                 Edit::try_from(key_event)
                     .map(Command::Edit)
                     // 上一个转换失败，就转换成 Move
                     .or_else(|_| Move::try_from(key_event).map(Command::Move))
+                    // 上一个转换失败，就判断是不是按住 Shift 的移动(选择)
+                    .or_else(|_| Self::try_select_from(key_event))
                     // 上一个转换失败，就转换成 System
                     .or_else(|_| System::try_from(key_event).map(Command::System))
                     // 都不行就格式化信息返回Err
@@ -41,7 +61,66 @@ impl TryFrom<Event> for Command {
                     width: width_u16 as usize,
                 })))
             }
+            Event::Paste(text) => Ok(Self::Edit(Edit::Paste(text))),
+            // 只处理鼠标左键按下，其他鼠标事件(移动、滚轮等)都忽略
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                ..
+            }) => Ok(Self::System(System::MouseClick(Position {
+                row: usize::from(row),
+                col: usize::from(column),
+            }))),
             _ => Err(format!("Event not supported: {event:?}")),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Command {
+    /// 设置自定义的按键绑定，替换之前设置的绑定
+    pub fn set_keymap(keymap: Keymap) {
+        *KEYMAP.write().unwrap_or_else(PoisonError::into_inner) = keymap;
+    }
+
+    /// 将按住 Shift 的移动按键转换为选择命令
+    fn try_select_from(key_event: KeyEvent) -> Result<Self, String> {
+        if key_event.modifiers == KeyModifiers::SHIFT {
+            Move::try_from(KeyEvent {
+                modifiers: KeyModifiers::NONE,
+                ..key_event
+            })
+            .map(Command::Select)
+        } else {
+            Err(format!("Unsupported selection key: {key_event:?}"))
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyCode::{self, Char, Left, F};
+
+    fn command(code: KeyCode, modifiers: KeyModifiers) -> Result<Command, String> {
+        Command::try_from(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+
+    #[test]
+    fn character_keys_map_to_expected_commands() {
+        assert!(matches!(command(Char('a'), KeyModifiers::NONE), Ok(Command::Edit(Edit::Insert('a')))));
+        assert!(matches!(command(Char('A'), KeyModifiers::SHIFT), Ok(Command::Edit(Edit::Insert('A')))));
+        assert!(matches!(command(Char('h'), KeyModifiers::ALT), Ok(Command::System(System::ToggleOccurrences))));
+        assert!(matches!(command(Char('a'), KeyModifiers::CONTROL), Ok(Command::System(System::OpenFile))));
+        assert!(matches!(command(Left, KeyModifiers::SHIFT), Ok(Command::Select(Move::Left))));
+    }
+
+    #[test]
+    fn unsupported_keys_are_rejected() {
+        // 没有绑定的 Alt 组合键不能作为文本插入
+        assert!(command(Char('a'), KeyModifiers::ALT).is_err());
+        assert!(command(Char('a'), KeyModifiers::CONTROL | KeyModifiers::ALT).is_err());
+        assert!(command(Char('\0'), KeyModifiers::NONE).is_err());
+        assert!(command(F(12), KeyModifiers::NONE).is_err());
+        assert!(command(F(2), KeyModifiers::SHIFT).is_err());
+    }
+}
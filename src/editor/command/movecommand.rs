@@ -1,5 +1,5 @@
 use crossterm::event::{
-    KeyCode::{Down, End, Home, Left, PageDown, PageUp, Right, Up},
+    KeyCode::{Char, Down, End, Home, Left, PageDown, PageUp, Right, Up},
     KeyEvent, KeyModifiers,
 };
 
@@ -8,12 +8,21 @@ use crossterm::event::{
 pub enum Move {
     PageUp,
     PageDown,
+    // 水平翻页：向左/向右移动一个视图宽度
+    PageLeft,
+    PageRight,
     StartOfLine,
     EndOfLine,
     Up,
     Left,
     Right,
     Down,
+    WordForward,
+    WordBackward,
+    // 跳转到与光标处括号配对的括号
+    MatchBracket,
+    DocumentStart,
+    DocumentEnd,
 }
 
 impl TryFrom<KeyEvent> for Move {
@@ -34,6 +43,22 @@ impl TryFrom<KeyEvent> for Move {
                 End => Ok(Self::EndOfLine),
                 _ => Err(format!("Unsupported code: {code:?}")),
             }
+        } else if modifiers == KeyModifiers::CONTROL {
+            match code {
+                Right => Ok(Self::WordForward),
+                Left => Ok(Self::WordBackward),
+                Home => Ok(Self::DocumentStart),
+                End => Ok(Self::DocumentEnd),
+                // 大多数终端把 Ctrl+] 发送为 Ctrl+5
+                Char(']' | '5') => Ok(Self::MatchBracket),
+                _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
+            }
+        } else if modifiers == KeyModifiers::ALT {
+            match code {
+                PageUp => Ok(Self::PageLeft),
+                PageDown => Ok(Self::PageRight),
+                _ => Err(format!("Unsupported ALT+{code:?} combination")),
+            }
         } else {
             Err(format!(
                 "Unsupported key code {code:?} or modifier {modifiers:?}"
@@ -1,6 +1,6 @@
 use crate::prelude::*;
 use crossterm::event::{
-    KeyCode::{self, Char},
+    KeyCode::{Char, Esc, Insert, PageDown, PageUp, F},
     KeyEvent, KeyModifiers,
 };
 
@@ -10,8 +10,69 @@ pub enum System {
     Save,
     Resize(Size),
     Quit,
+    // 保存当前缓冲区后退出
+    SaveQuit,
+    // 不保存，直接退出
+    ForceQuit,
+    // 复制选中的文本
+    Copy,
     Dismiss,
-    Search
+    Search,
+    Replace,
+    ToggleLineNumbers,
+    GoToLine,
+    SplitLine,
+    JoinLines,
+    ReloadConfig,
+    ToggleLinePreview,
+    // 从磁盘重新读取当前文件
+    Reload,
+    // 按输入的编码重新读取当前文件
+    ReloadWithEncoding,
+    // 在当前缓冲区中打开文件
+    Open,
+    // 在消息栏中显示单词数和字符数
+    WordCount,
+    // 在新的缓冲区中打开文件
+    OpenFile,
+    // 列出最近打开的文件
+    RecentFiles,
+    // 浏览目录，选择要打开的文件
+    Browse,
+    // 在项目目录树中模糊查找文件
+    FindFile,
+    // 切换到下一个/上一个缓冲区
+    NextBuffer,
+    PrevBuffer,
+    // 左右分屏显示两个缓冲区，再次执行时取消分屏
+    ToggleSplit,
+    // 分屏时切换到另一个窗格
+    FocusOtherPane,
+    // 切换插入模式和改写模式
+    ToggleOvertype,
+    // 切换是否显示滚动条
+    ToggleScrollbar,
+    // 切换是否显示空白字符
+    ToggleWhitespace,
+    // 开始和停止录制宏
+    StartRecord,
+    StopRecord,
+    // 回放最近录制的宏，可以指定重复次数
+    PlayMacro,
+    // 在下一行、在下一个匹配项处添加光标
+    AddCursorBelow,
+    AddCursorAtNextMatch,
+    // 折叠或展开当前行之后缩进更深的代码块
+    ToggleFold,
+    // 排序选中的行、将选中的行倒序排列
+    SortLines,
+    ReverseLines,
+    // 切换是否高亮光标所在单词的出现位置
+    ToggleOccurrences,
+    // 跳转到下一个包含输入内容的行
+    JumpToMatchingLine,
+    // 鼠标左键点击，位置为终端上的行列
+    MouseClick(Position),
 }
 
 impl TryFrom<KeyEvent> for System {
@@ -24,11 +85,52 @@ impl TryFrom<KeyEvent> for System {
             match code {
                 Char('q') => Ok(Self::Quit),
                 Char('s') => Ok(Self::Save),
+                Char('x') => Ok(Self::SaveQuit),
+                Char('c') => Ok(Self::Copy),
                 Char('f') => Ok(Self::Search),
+                Char('r') => Ok(Self::Replace),
+                Char('l') => Ok(Self::ToggleLineNumbers),
+                Char('g') => Ok(Self::GoToLine),
+                Char('t') => Ok(Self::SplitLine),
+                Char('e') => Ok(Self::JoinLines),
+                Char('p') => Ok(Self::FindFile),
+                Char('o') => Ok(Self::Open),
+                Char('u') => Ok(Self::Reload),
+                Char('n') => Ok(Self::WordCount),
+                Char('a') => Ok(Self::OpenFile),
+                PageDown => Ok(Self::NextBuffer),
+                PageUp => Ok(Self::PrevBuffer),
+                Char('v') => Ok(Self::ToggleSplit),
                 _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
             }
-        } else if modifiers == KeyModifiers::NONE && matches!(code, KeyCode::Esc) {
-            Ok(Self::Dismiss)
+        } else if modifiers == KeyModifiers::ALT {
+            match code {
+                Char('p') => Ok(Self::ToggleLinePreview),
+                Char('s') => Ok(Self::ToggleScrollbar),
+                Char('w') => Ok(Self::ToggleWhitespace),
+                Char('e') => Ok(Self::ReloadWithEncoding),
+                Char('c') => Ok(Self::AddCursorBelow),
+                Char('n') => Ok(Self::AddCursorAtNextMatch),
+                Char('f') => Ok(Self::ToggleFold),
+                Char('o') => Ok(Self::SortLines),
+                Char('r') => Ok(Self::ReverseLines),
+                Char('h') => Ok(Self::ToggleOccurrences),
+                Char('j') => Ok(Self::JumpToMatchingLine),
+                _ => Err(format!("Unsupported ALT+{code:?} combination")),
+            }
+        } else if modifiers == KeyModifiers::NONE {
+            match code {
+                Esc => Ok(Self::Dismiss),
+                F(2) => Ok(Self::RecentFiles),
+                F(3) => Ok(Self::Browse),
+                F(5) => Ok(Self::ReloadConfig),
+                F(6) => Ok(Self::FocusOtherPane),
+                F(7) => Ok(Self::StartRecord),
+                F(8) => Ok(Self::StopRecord),
+                F(9) => Ok(Self::PlayMacro),
+                Insert => Ok(Self::ToggleOvertype),
+                _ => Err(format!("Unsupported code: {code:?}")),
+            }
         } else {
             Err(format!(
                 "Unsupported key code {code:?} or modifier {modifiers:?}"
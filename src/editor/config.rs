@@ -0,0 +1,250 @@
+use std::{
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+use super::{command::Keymap, terminal::Theme};
+
+/// 编辑器配置
+// clippy::struct_excessive_bools: 每个标志对应一个独立的配置项
+#[allow(clippy::struct_excessive_bools)]
+pub struct Config {
+    // 状态栏中显示的位置是否从 1 开始计数(否则从 0 开始)
+    pub one_based_position: bool,
+    // 状态栏是否占用两行：第一行显示文件信息，第二行显示语言、编码和光标位置
+    pub two_line_status: bool,
+    // 无法识别文件编码时默认使用的编码
+    pub default_encoding: String,
+    // 在文档末尾按 Enter 时是否插入新的空行
+    pub newline_at_eof: bool,
+    // 换行时是否自动沿用当前行的缩进
+    pub auto_indent: bool,
+    // 输入开括号或引号时是否自动插入对应的闭括号或引号
+    pub auto_pair: bool,
+    // 加载文件时是否读取 modeline(如 `vim: set ts=4:`)中的设置
+    pub modelines: bool,
+    // 制表符宽度(列数)
+    pub tab_width: usize,
+    // 按 Tab 键时是否插入空格而不是制表符
+    pub expand_tabs: bool,
+    // 加载混用了制表符和空格缩进的文件时，是否按 `expand_tabs` 统一行首的缩进
+    pub normalize_indentation: bool,
+    // 排序选中的行时是否不区分大小写
+    pub sort_ignore_case: bool,
+    // 整体注释选中行时使用的注释符号
+    pub comment_token: String,
+    // 是否在一段时间没有操作后自动保存
+    pub auto_save: bool,
+    // 自动保存前需要等待的无操作时间(秒)
+    pub auto_save_delay: usize,
+    // 保存时是否删除行尾的空格和制表符
+    pub trim_trailing_whitespace: bool,
+    // 保存时是否删除文档末尾的空白行，并让文件以一个换行符结尾
+    pub trim_trailing_blank_lines: bool,
+    // 第一次保存已有文件前，是否将原文件备份为 `文件名~`
+    pub backup: bool,
+    // 是否将超出窗口宽度的行折行显示
+    pub wrap: bool,
+    // 是否在状态栏中显示单词数和字符数
+    pub show_word_count: bool,
+    // 是否在左侧显示行号
+    pub show_line_numbers: bool,
+    // 是否在右侧显示滚动条
+    pub show_scrollbar: bool,
+    // 是否将空格显示为 `·`、制表符显示为 `→`
+    pub render_whitespace: bool,
+    // 是否高亮光标所在单词在文档中的其他出现位置
+    pub highlight_occurrences: bool,
+    // 行的列数限制，超出限制的第一列显示为标尺。为 None 时不显示
+    pub ruler_column: Option<usize>,
+    // 是否启用类似 vim 的模式编辑(普通模式和插入模式)
+    pub modal_editing: bool,
+    // 复制时是否通过 OSC 52 序列写入终端所在机器的剪贴板。不支持该序列的终端可能显示乱码，所以默认关闭
+    pub osc52_clipboard: bool,
+    // 有未保存的修改时，需要连续按几次 Ctrl-Q 才能退出
+    pub quit_times: usize,
+    // 标注使用的颜色主题，先选择内置主题 `theme`，再用 `[colors]` 表覆盖其中的颜色
+    pub theme: Theme,
+    // `[keys]` 表中自定义的按键绑定，没有绑定的按键使用默认绑定
+    pub keymap: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            one_based_position: true,
+            two_line_status: false,
+            default_encoding: String::from("windows-1252"),
+            newline_at_eof: true,
+            auto_indent: true,
+            auto_pair: false,
+            modelines: true,
+            tab_width: 4,
+            expand_tabs: false,
+            normalize_indentation: false,
+            sort_ignore_case: false,
+            comment_token: String::from("//"),
+            auto_save: false,
+            auto_save_delay: 30,
+            trim_trailing_whitespace: false,
+            trim_trailing_blank_lines: false,
+            backup: false,
+            wrap: false,
+            show_word_count: false,
+            show_line_numbers: false,
+            show_scrollbar: false,
+            render_whitespace: false,
+            highlight_occurrences: false,
+            ruler_column: None,
+            modal_editing: false,
+            osc52_clipboard: false,
+            quit_times: 3,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl Config {
+    /// 获取配置文件的路径。
+    ///
+    /// 依次使用环境变量 `HECTO_CONFIG`、`$XDG_CONFIG_HOME/hecto/config.toml`
+    /// 和 `$HOME/.config/hecto/config.toml`，都无法确定时返回 `None`。
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("HECTO_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|config_dir| config_dir.join("hecto").join("config.toml"))
+    }
+
+    /// 从 TOML 文件中加载配置，文件中没有出现的配置项使用默认值。
+    ///
+    /// # 返回值
+    /// 文件无法读取、不是合法的 TOML，或者包含未知的配置项、类型错误的值时，返回描述错误的信息。
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = read_to_string(path).map_err(|err| err.to_string())?;
+        let table = contents
+            .parse::<Table>()
+            .map_err(|err| err.message().to_string())?;
+        Self::from_table(&table)
+    }
+
+    /// 根据解析出的 TOML 表构建配置
+    fn from_table(table: &Table) -> Result<Self, String> {
+        let mut config = Self::default();
+        // 颜色需要在选择主题之后再覆盖，与它们在文件中出现的顺序无关
+        let mut colors = None;
+        for (key, value) in table {
+            match key.as_str() {
+                "one_based_position" => config.one_based_position = Self::bool_value(key, value)?,
+                "two_line_status" => config.two_line_status = Self::bool_value(key, value)?,
+                "default_encoding" => config.default_encoding = Self::string_value(key, value)?,
+                "newline_at_eof" => config.newline_at_eof = Self::bool_value(key, value)?,
+                "auto_indent" => config.auto_indent = Self::bool_value(key, value)?,
+                "auto_pair" => config.auto_pair = Self::bool_value(key, value)?,
+                "modelines" => config.modelines = Self::bool_value(key, value)?,
+                "tab_width" => config.tab_width = Self::positive_value(key, value)?,
+                "expand_tabs" => config.expand_tabs = Self::bool_value(key, value)?,
+                "normalize_indentation" => config.normalize_indentation = Self::bool_value(key, value)?,
+                "sort_ignore_case" => config.sort_ignore_case = Self::bool_value(key, value)?,
+                "comment_token" => config.comment_token = Self::string_value(key, value)?,
+                "auto_save" => config.auto_save = Self::bool_value(key, value)?,
+                "auto_save_delay" => config.auto_save_delay = Self::positive_value(key, value)?,
+                "trim_trailing_whitespace" => {
+                    config.trim_trailing_whitespace = Self::bool_value(key, value)?;
+                }
+                "trim_trailing_blank_lines" => {
+                    config.trim_trailing_blank_lines = Self::bool_value(key, value)?;
+                }
+                "backup" => config.backup = Self::bool_value(key, value)?,
+                "wrap" => config.wrap = Self::bool_value(key, value)?,
+                "show_word_count" => config.show_word_count = Self::bool_value(key, value)?,
+                "show_line_numbers" => config.show_line_numbers = Self::bool_value(key, value)?,
+                "show_scrollbar" => config.show_scrollbar = Self::bool_value(key, value)?,
+                "render_whitespace" => config.render_whitespace = Self::bool_value(key, value)?,
+                "highlight_occurrences" => config.highlight_occurrences = Self::bool_value(key, value)?,
+                "ruler_column" => config.ruler_column = Some(Self::positive_value(key, value)?),
+                "modal_editing" => config.modal_editing = Self::bool_value(key, value)?,
+                "osc52_clipboard" => config.osc52_clipboard = Self::bool_value(key, value)?,
+                "quit_times" => config.quit_times = Self::positive_value(key, value)?,
+                "theme" => {
+                    let name = Self::string_value(key, value)?;
+                    config.theme = Theme::by_name(&name).ok_or_else(|| format!("unknown theme '{name}'"))?;
+                }
+                "colors" => {
+                    colors = Some(value.as_table().ok_or_else(|| format!("'{key}' must be a table"))?);
+                }
+                "keys" => {
+                    let keys = value.as_table().ok_or_else(|| format!("'{key}' must be a table"))?;
+                    config.keymap = Keymap::from_table(keys)?;
+                }
+                _ => return Err(format!("unknown setting '{key}'")),
+            }
+        }
+        for (name, value) in colors.into_iter().flatten() {
+            config.theme.set_color(name, value)?;
+        }
+        Ok(config)
+    }
+
+    fn bool_value(key: &str, value: &Value) -> Result<bool, String> {
+        value
+            .as_bool()
+            .ok_or_else(|| format!("'{key}' must be true or false"))
+    }
+
+    fn string_value(key: &str, value: &Value) -> Result<String, String> {
+        value
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| format!("'{key}' must be a string"))
+    }
+
+    fn positive_value(key: &str, value: &Value) -> Result<usize, String> {
+        value
+            .as_integer()
+            .and_then(|integer| usize::try_from(integer).ok())
+            .filter(|integer| *integer > 0)
+            .ok_or_else(|| format!("'{key}' must be a positive integer"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, process};
+
+    /// 在临时目录中写入配置文件，返回它的路径
+    fn config_file(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("hecto-test-{}-{name}.toml", process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn load_overrides_only_settings_in_file() {
+        let path = config_file("valid", "one_based_position = false\n");
+        let config = Config::load(&path).unwrap();
+        assert!(!config.one_based_position);
+        assert_eq!(config.default_encoding, Config::default().default_encoding);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_invalid_and_missing_files() {
+        let path = config_file("invalid", "one_based_position = \n");
+        assert!(Config::load(&path).is_err());
+        fs::write(&path, "one_based_position = 1\n").unwrap();
+        assert_eq!(Config::load(&path).err().unwrap(), "'one_based_position' must be true or false");
+        fs::write(&path, "no_such_setting = true\n").unwrap();
+        assert_eq!(Config::load(&path).err().unwrap(), "unknown setting 'no_such_setting'");
+        fs::remove_file(&path).unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+}
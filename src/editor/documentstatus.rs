@@ -1,11 +1,43 @@
+use std::fmt::Write;
+
 use crate::prelude::*;
 
+use super::Mode;
+
+// clippy::struct_excessive_bools: 这些标志分别对应状态栏中互相独立的指示符
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Default, Eq, PartialEq, Debug)]
 pub struct DocumentStatus {
     pub total_lines: usize,
     pub current_line_index: LineIdx,
+    // 光标在当前行中的字素索引
+    pub current_grapheme_index: GraphemeIdx,
+    // 光标在当前行中的显示列(制表符和宽字符会占用多列)
+    pub current_column: ColIdx,
     pub is_modified: bool,
+    // 缓冲区是否只读
+    pub is_read_only: bool,
+    // 视图水平滚动的列数
+    pub horizontal_offset: ColIdx,
+    // 单词数和字符数，不在状态栏中显示时为 None
+    pub word_and_char_count: Option<(usize, usize)>,
+    // 打开了多个缓冲区时，当前缓冲区的序号(从 1 开始)和缓冲区总数
+    pub buffer_position: Option<(usize, usize)>,
+    // 启用模式编辑时编辑器所处的模式
+    pub mode: Option<Mode>,
+    // 是否正在录制宏
+    pub recording: bool,
+    // 是否处于改写模式
+    pub overwrite: bool,
+    // 是否显示空白字符
+    pub render_whitespace: bool,
     pub file_name: String,
+    // 换行符类型(LF/CRLF)
+    pub line_ending: String,
+    // 文件编码
+    pub encoding: String,
+    // 根据扩展名识别的语言名称
+    pub language: String,
 }
 
 impl DocumentStatus {
@@ -18,17 +50,180 @@ impl DocumentStatus {
         }
     }
 
+    // 只读标志
+    pub fn read_only_indicator_to_string(&self) -> String {
+        if self.is_read_only {
+            String::from(" [RO]")
+        } else {
+            String::new()
+        }
+    }
+
+    // 缓冲区序号，如 `[2/4] `，只有一个缓冲区时为空
+    pub fn buffer_position_to_string(&self) -> String {
+        self.buffer_position
+            .map_or_else(String::new, |(current, total)| format!("[{current}/{total}] "))
+    }
+
+    // 模式名称，如 `[NORMAL] `，没有启用模式编辑时为空
+    pub fn mode_to_string(&self) -> String {
+        self.mode
+            .map_or_else(String::new, |mode| format!("[{}] ", mode.name()))
+    }
+
+    // 录制宏时的标志 `[REC] `，没有录制时为空
+    pub fn recording_indicator_to_string(&self) -> String {
+        if self.recording {
+            String::from("[REC] ")
+        } else {
+            String::new()
+        }
+    }
+
+    // 插入/改写模式展示
+    pub fn overwrite_indicator_to_string(&self) -> String {
+        if self.overwrite {
+            String::from("OVR")
+        } else {
+            String::from("INS")
+        }
+    }
+
+    // 显示空白字符时的标志，如 `WS `，不显示时为空
+    pub fn whitespace_indicator_to_string(&self) -> String {
+        if self.render_whitespace {
+            String::from("WS ")
+        } else {
+            String::new()
+        }
+    }
+
     // 总行数展示
     pub fn line_count_to_string(&self) -> String {
         format!("{} lines", self.total_lines)
     }
 
-    // 当前光标/操作位置展示
-    pub fn position_indicator_to_string(&self) -> String {
+    // 当前光标/操作位置展示，如 `Ln 12, Col 5`
+    // `one_based` 决定行号和列号从 1 还是从 0 开始计数
+    // 字素索引和显示列不同时(如行中有制表符)，列号显示为 `字素-显示列`
+    pub fn position_indicator_to_string(&self, one_based: bool) -> String {
+        let offset = usize::from(one_based);
+        let line = self.current_line_index.saturating_add(offset);
+        let grapheme = self.current_grapheme_index.saturating_add(offset);
+        let column = self.current_column.saturating_add(offset);
+        if grapheme == column {
+            format!("Ln {line}, Col {column}")
+        } else {
+            format!("Ln {line}, Col {grapheme}-{column}")
+        }
+    }
+
+    // 水平滚动的列数展示，没有水平滚动时为空，避免干扰
+    pub fn horizontal_offset_to_string(&self) -> String {
+        if self.horizontal_offset > 0 {
+            format!("Col-offset {} ", self.horizontal_offset)
+        } else {
+            String::new()
+        }
+    }
+
+    // 单词数和字符数展示，不显示时为空
+    pub fn word_count_to_string(&self) -> String {
+        self.word_and_char_count
+            .map_or_else(String::new, |(words, chars)| format!("{words} words {chars} chars "))
+    }
+
+    // 光标所在行在文档中的百分比位置
+    pub fn percentage_to_string(&self) -> String {
+        let current_line = self.current_line_index.saturating_add(1);
+        let percentage = current_line
+            .saturating_mul(100)
+            .checked_div(self.total_lines)
+            .map_or(100, |percentage| percentage.min(100));
+        format!("{percentage}%")
+    }
+
+    /// 将状态格式化为单行 JSON，行号和列号从 1 开始计数，列号是字素索引。例如：
+    ///
+    /// `{"file":"main.rs","lines":120,"line":3,"column":5,"modified":false,"encoding":"UTF-8"}`
+    pub fn to_json(&self) -> String {
         format!(
-            "{}/{}",
+            r#"{{"file":{},"lines":{},"line":{},"column":{},"modified":{},"encoding":{}}}"#,
+            json_string(&self.file_name),
+            self.total_lines,
             self.current_line_index.saturating_add(1),
-            self.total_lines
+            self.current_grapheme_index.saturating_add(1),
+            self.is_modified,
+            json_string(&self.encoding),
         )
     }
-}
\ No newline at end of file
+}
+
+/// 将字符串转义为 JSON 字符串字面量(包括两侧的引号)
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from('"');
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            control if control.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", u32::from(control));
+            }
+            other => escaped.push(other),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_indicator_counts_from_one_or_zero() {
+        let status = DocumentStatus {
+            current_line_index: 2,
+            current_grapheme_index: 4,
+            current_column: 4,
+            ..DocumentStatus::default()
+        };
+        assert_eq!(status.position_indicator_to_string(true), "Ln 3, Col 5");
+        assert_eq!(status.position_indicator_to_string(false), "Ln 2, Col 4");
+        // 行首的位置在从 0 开始计数时显示为 0
+        let status = DocumentStatus::default();
+        assert_eq!(status.position_indicator_to_string(true), "Ln 1, Col 1");
+        assert_eq!(status.position_indicator_to_string(false), "Ln 0, Col 0");
+    }
+
+    #[test]
+    fn position_indicator_shows_display_column_after_tabs() {
+        let status = DocumentStatus {
+            current_grapheme_index: 1,
+            current_column: 4,
+            ..DocumentStatus::default()
+        };
+        assert_eq!(status.position_indicator_to_string(true), "Ln 1, Col 2-5");
+        assert_eq!(status.position_indicator_to_string(false), "Ln 0, Col 1-4");
+    }
+
+    #[test]
+    fn to_json_escapes_file_name() {
+        let status = DocumentStatus {
+            file_name: String::from("a\"b\\c\nd\u{1}.rs"),
+            total_lines: 12,
+            current_line_index: 2,
+            current_grapheme_index: 4,
+            is_modified: true,
+            encoding: String::from("UTF-8"),
+            ..DocumentStatus::default()
+        };
+        assert_eq!(
+            status.to_json(),
+            r#"{"file":"a\"b\\c\nd\u0001.rs","lines":12,"line":3,"column":5,"modified":true,"encoding":"UTF-8"}"#
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    fs::read_dir,
+    path::{Path, PathBuf},
+};
+
+// 遍历目录的最大深度，当前目录中的文件深度为 1
+const MAX_DEPTH: usize = 12;
+// 最多收集的文件数，避免在很大的目录(如主目录)中遍历太久
+const MAX_FILES: usize = 50_000;
+// 遍历时跳过的目录
+const SKIPPED_DIRS: [&str; 2] = [".git", "target"];
+
+// 匹配的字符位于路径开头或分隔符之后时的加分
+const BOUNDARY_BONUS: usize = 8;
+// 匹配的字符紧跟在上一个匹配的字符之后时的加分
+const CONSECUTIVE_BONUS: usize = 5;
+// 匹配的字符位于文件名(最后一个 `/` 之后)中时的加分
+const FILE_NAME_BONUS: usize = 2;
+
+/// 在项目目录树中按模糊匹配查找文件。
+///
+/// 创建时遍历一次目录树并记住所有文件的相对路径，之后的查找只在这些路径中进行，
+/// 所以创建之后新增或删除的文件不会反映在结果中。
+pub struct Finder {
+    // 相对于遍历根目录的路径，使用 `/` 分隔，按遍历顺序排列
+    paths: Vec<String>,
+    // 文件数达到上限，遍历提前结束
+    truncated: bool,
+}
+
+impl Finder {
+    /// 遍历目录树，收集其中的文件。
+    ///
+    /// 跳过 `.git` 和 `target` 目录，不进入指向目录的符号链接(避免循环)，
+    /// 无法读取的目录和名称不是有效 UTF-8 的项会被忽略。
+    pub fn scan(root: &Path) -> Self {
+        let mut paths = Vec::new();
+        let mut truncated = false;
+        // 待遍历的目录：(完整路径, 相对路径前缀, 深度)。逆序入栈，使遍历顺序与名称顺序一致
+        let mut pending: Vec<(PathBuf, String, usize)> = vec![(root.to_path_buf(), String::new(), 1)];
+        'walk: while let Some((dir, prefix, depth)) = pending.pop() {
+            let Ok(entries) = read_dir(&dir) else {
+                continue;
+            };
+            let mut entries: Vec<(String, bool)> = entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| {
+                    let name = entry.file_name().into_string().ok()?;
+                    // `file_type` 不跟随符号链接，指向文件的符号链接按文件处理
+                    let file_type = entry.file_type().ok()?;
+                    let is_dir = file_type.is_dir();
+                    if file_type.is_symlink() && !entry.path().is_file() {
+                        return None;
+                    }
+                    Some((name, is_dir))
+                })
+                .collect();
+            entries.sort();
+            let mut subdirs = Vec::new();
+            for (name, is_dir) in entries {
+                let path = format!("{prefix}{name}");
+                if is_dir {
+                    if depth < MAX_DEPTH && !SKIPPED_DIRS.contains(&name.as_str()) {
+                        subdirs.push((dir.join(&name), format!("{path}/"), depth.saturating_add(1)));
+                    }
+                } else {
+                    if paths.len() >= MAX_FILES {
+                        truncated = true;
+                        break 'walk;
+                    }
+                    paths.push(path);
+                }
+            }
+            pending.extend(subdirs.into_iter().rev());
+        }
+        Self { paths, truncated }
+    }
+
+    /// 收集到的文件数
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// 文件数是否达到上限，有文件没有被收集
+    pub const fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// 查找与输入内容匹配的路径，按匹配程度从高到低最多返回 `limit` 个。
+    ///
+    /// 输入内容中的字符按顺序出现在路径中(不区分大小写)即为匹配。
+    /// 匹配程度相同时较短的路径排在前面。输入内容为空时按遍历顺序返回前 `limit` 个路径。
+    pub fn matches(&self, query: &str, limit: usize) -> Vec<&str> {
+        if query.is_empty() {
+            return self.paths.iter().take(limit).map(String::as_str).collect();
+        }
+        let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+        let mut scored: Vec<(usize, &str)> = self
+            .paths
+            .iter()
+            .filter_map(|path| score(&query, path).map(|score| (score, path.as_str())))
+            .collect();
+        scored.sort_by(|left, right| {
+            right
+                .0
+                .cmp(&left.0)
+                .then_with(|| left.1.len().cmp(&right.1.len()))
+                .then_with(|| left.1.cmp(right.1))
+        });
+        scored.into_iter().take(limit).map(|(_, path)| path).collect()
+    }
+}
+
+/// 计算路径与输入内容(已转换为小写)的匹配程度，不匹配时返回 `None`。
+///
+/// 从左到右依次为输入内容中的每个字符找到路径中最早出现的位置，
+/// 每个匹配的字符得 1 分，位于单词开头、紧跟上一个匹配或位于文件名中时额外加分。
+fn score(query: &[char], path: &str) -> Option<usize> {
+    let file_name_start = path.rfind('/').map_or(0, |index| index.saturating_add(1));
+    let mut query_chars = query.iter().peekable();
+    let mut total: usize = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for (index, character) in path.char_indices() {
+        let Some(expected) = query_chars.peek() else {
+            break;
+        };
+        let matched = character.to_lowercase().eq([**expected]);
+        if matched {
+            query_chars.next();
+            total = total.saturating_add(1);
+            if previous.is_none_or(|previous| matches!(previous, '/' | '_' | '-' | '.' | ' ')) {
+                total = total.saturating_add(BOUNDARY_BONUS);
+            }
+            if previous_matched {
+                total = total.saturating_add(CONSECUTIVE_BONUS);
+            }
+            if index >= file_name_start {
+                total = total.saturating_add(FILE_NAME_BONUS);
+            }
+        }
+        previous = Some(character);
+        previous_matched = matched;
+    }
+    query_chars.peek().is_none().then_some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finder(paths: &[&str]) -> Finder {
+        Finder {
+            paths: paths.iter().map(|path| (*path).to_string()).collect(),
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn query_must_appear_in_order() {
+        let query: Vec<char> = "mdr".chars().collect();
+        assert!(score(&query, "src/editor/mod.rs").is_some());
+        let query: Vec<char> = "rdm".chars().collect();
+        assert!(score(&query, "src/main.rs").is_none());
+    }
+
+    #[test]
+    fn matches_are_case_insensitive_and_ranked() {
+        let finder = finder(&["src/editor/mod.rs", "src/main.rs", "README.md", "docs/Makefile"]);
+        assert_eq!(finder.matches("main", 10), vec!["src/main.rs"]);
+        assert_eq!(finder.matches("MAKE", 10), vec!["docs/Makefile"]);
+        // 匹配文件名开头和连续字符的路径排在前面
+        assert_eq!(finder.matches("mo", 10).first(), Some(&"src/editor/mod.rs"));
+        assert_eq!(finder.matches("", 2), vec!["src/editor/mod.rs", "src/main.rs"]);
+        assert!(finder.matches("xyz", 10).is_empty());
+    }
+
+    #[test]
+    fn results_are_limited() {
+        let finder = finder(&["a1", "a2", "a3"]);
+        assert_eq!(finder.matches("a", 2).len(), 2);
+    }
+}
@@ -0,0 +1,57 @@
+// 最多保留的历史记录条数，超过后丢弃最早的记录
+const MAX_ENTRIES: usize = 50;
+
+/// 输入历史，例如搜索过的内容。
+///
+/// 浏览历史时从最新的一条开始向前翻，翻过最新的一条后回到空白输入。
+#[derive(Default)]
+pub struct History {
+    // 历史记录，最新的记录在最后
+    entries: Vec<String>,
+    // 当前浏览到的记录，为 None 时没有在浏览历史
+    position: Option<usize>,
+}
+
+impl History {
+    /// 添加一条记录，并结束浏览。空白内容和与上一条相同的内容不会被记录
+    pub fn push(&mut self, entry: &str) {
+        self.position = None;
+        if entry.is_empty() || self.entries.last().is_some_and(|last| last == entry) {
+            return;
+        }
+        self.entries.push(entry.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    /// 结束浏览，下次从最新的一条开始
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    /// 获取上一条(更早的)记录，已经位于最早的一条时停留在该条，没有记录时返回 `None`
+    pub fn previous(&mut self) -> Option<&str> {
+        let position = match self.position {
+            Some(position) => position.saturating_sub(1),
+            None => self.entries.len().checked_sub(1)?,
+        };
+        self.position = Some(position);
+        self.entries.get(position).map(String::as_str)
+    }
+
+    /// 获取下一条(更新的)记录。
+    ///
+    /// # 返回值
+    /// 翻过最新的一条后结束浏览并返回空字符串，没有在浏览历史时返回 `None`。
+    pub fn next(&mut self) -> Option<&str> {
+        let position = self.position?.saturating_add(1);
+        if position < self.entries.len() {
+            self.position = Some(position);
+            self.entries.get(position).map(String::as_str)
+        } else {
+            self.position = None;
+            Some("")
+        }
+    }
+}
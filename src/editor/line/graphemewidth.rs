@@ -1,7 +1,11 @@
-#[derive(Copy, Clone, Debug)]
+use crate::prelude::*;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum GraphemeWidth {
     Half,
     Full,
+    // 制表符，宽度取决于它所在的列(到下一个制表位为止)
+    Tab(ColIdx),
 }
 
 impl From<GraphemeWidth> for usize {
@@ -9,6 +13,7 @@ impl From<GraphemeWidth> for usize {
         match val {
             GraphemeWidth::Half => 1,
             GraphemeWidth::Full => 2,
+            GraphemeWidth::Tab(width) => width,
         }
     }
-}
\ No newline at end of file
+}
@@ -6,61 +6,203 @@ use unicode_width::UnicodeWidthStr;
 use graphemewidth::GraphemeWidth;
 use textfragment::TextFragment;
 
-use super::{AnnotatedString, AnnotationType};
+use super::{AnnotatedString, Annotation, AnnotationType};
 
 mod graphemewidth;
 mod textfragment;
 
-#[derive(Default, Clone)]
+/// 默认的制表符宽度(列数)
+pub const DEFAULT_TAB_WIDTH: ColIdx = 4;
+
+/// 文档中的一行。
+///
+/// `string` 按原样保存文件内容(包括制表符)，`fragments` 只描述如何渲染：
+/// 制表符被展开为到下一个制表位为止的空格，所以保存文件时不会改变其中的制表符。
+#[derive(Clone)]
 pub struct Line {
     fragments: Vec<TextFragment>,
     string: String,
+    // 制表符宽度，制表符会被渲染到下一个该宽度整数倍的列
+    tab_width: ColIdx,
+}
+
+impl Default for Line {
+    fn default() -> Self {
+        Self::new("", DEFAULT_TAB_WIDTH)
+    }
 }
 
 impl Line {
     pub fn from(line_str: &str) -> Self {
+        Self::new(line_str, DEFAULT_TAB_WIDTH)
+    }
+
+    /// 使用指定的制表符宽度创建行
+    pub fn new(line_str: &str, tab_width: ColIdx) -> Self {
         debug_assert!(line_str.is_empty() || line_str.lines().count() == 1);
-        let fragments = Self::str_to_fragments(line_str);
-        Self { 
+        let tab_width = tab_width.max(1);
+        let fragments = Self::str_to_fragments(line_str, tab_width);
+        Self {
             fragments,
-            string: String::from(line_str)
+            string: String::from(line_str),
+            tab_width,
         }
     }
 
-    fn str_to_fragments(line_str: &str) -> Vec<TextFragment> {
+    fn str_to_fragments(line_str: &str, tab_width: ColIdx) -> Vec<TextFragment> {
+        let mut fragments = Self::segment(line_str, 0);
+        Self::layout_tabs(&mut fragments, 0, tab_width);
+        fragments
+    }
+
+    /// 将字符串拆分为字素片段。
+    ///
+    /// # 参数
+    /// - `text`: 要拆分的字符串。
+    /// - `offset`: `text` 在整行中的起始字节索引，会加到每个片段的 `start` 上。
+    fn segment(text: &str, offset: ByteIdx) -> Vec<TextFragment> {
         // 使用 `.graphemes(true)` 将字符串拆分成字素（grapheme clusters）
         // 字素是人类可感知的字符单位，可能由多个 Unicode 码点组成
-        line_str
-            .grapheme_indices(true)
-            .map(|(byte_idx, grapheme)| {
-                let (replacement, rendered_width) = Self::get_replacement_character(grapheme)
-                    .map_or_else(
-                        // 如果转换的函数返回None就进行处理
-                        || {
-                            let unicode_width = grapheme.width();
-                            let rendered_width = match unicode_width {
-                                0 | 1 => GraphemeWidth::Half,
-                                _ => GraphemeWidth::Full,
-                            };
-                            (None, rendered_width)
-                        }, 
-                        // Some(x)有值就直接用
-                        |replacement| (Some(replacement), GraphemeWidth::Half),
-                    );
-
-                TextFragment {
-                    grapheme: grapheme.to_string(),
-                    rendered_width,
-                    replacement,
-                    start: byte_idx,
-                }
-            })
+        text.grapheme_indices(true)
+            .map(|(byte_idx, grapheme)| Self::fragment(grapheme, offset.saturating_add(byte_idx)))
             .collect()
     }
 
+    /// 为单个字素创建片段。
+    ///
+    /// 制表符的宽度和片段所在的列由 `layout_tabs` 计算，这里只先占位。
+    fn fragment(grapheme: &str, start: ByteIdx) -> TextFragment {
+        let (replacement, rendered_width) = if grapheme == "\t" {
+            // tab制表符渲染为空格，一直填充到下一个制表位
+            (Some(' '), GraphemeWidth::Tab(1))
+        } else {
+            Self::get_replacement_character(grapheme).map_or_else(
+                // 如果转换的函数返回None就进行处理
+                || {
+                    let unicode_width = grapheme.width();
+                    let rendered_width = match unicode_width {
+                        0 | 1 => GraphemeWidth::Half,
+                        _ => GraphemeWidth::Full,
+                    };
+                    (None, rendered_width)
+                },
+                // Some(x)有值就直接用
+                |replacement| (Some(replacement), GraphemeWidth::Half),
+            )
+        };
+
+        TextFragment {
+            grapheme: grapheme.to_string(),
+            rendered_width,
+            replacement,
+            start,
+            column: 0,
+        }
+    }
+
+    /// 从左到右计算各片段所在的列，以及制表符的宽度(到下一个制表位为止)
+    ///
+    /// # 参数
+    /// - `fragments`: 要计算的片段。
+    /// - `start_column`: 第一个片段开始的列。
+    /// - `tab_width`: 制表符宽度。
+    fn layout_tabs(fragments: &mut [TextFragment], start_column: ColIdx, tab_width: ColIdx) {
+        let mut column = start_column;
+        for fragment in fragments {
+            column = Self::layout_fragment(fragment, column, tab_width);
+        }
+    }
+
+    /// 将片段放在指定的列上，制表符重新计算宽度，返回片段之后的列
+    fn layout_fragment(fragment: &mut TextFragment, column: ColIdx, tab_width: ColIdx) -> ColIdx {
+        fragment.column = column;
+        if let GraphemeWidth::Tab(_) = fragment.rendered_width {
+            let offset = column.checked_rem(tab_width).unwrap_or(0);
+            fragment.rendered_width = GraphemeWidth::Tab(tab_width.saturating_sub(offset));
+        }
+        column.saturating_add(fragment.rendered_width.into())
+    }
+
     /// 重新构建 fragment
     fn rebuild_fragments(&mut self) {
-        self.fragments = Self::str_to_fragments(&self.string);
+        self.fragments = Self::str_to_fragments(&self.string, self.tab_width);
+    }
+
+    /// 用 `replacement` 替换 `graphemes` 范围内的字素，并增量更新 fragment。
+    ///
+    /// 从修改点前一个字素(插入组合字符等情况会与它合并)开始重新拆分，
+    /// 直到新的字素边界与修改前的边界重合为止，之后的片段只需平移字节索引。
+    /// 列从修改点之前的片段继续计算，之后的片段回到修改前的列时，
+    /// 后面的制表符宽度和列都不会再变化，不再重新计算。
+    fn replace_graphemes(&mut self, graphemes: Range<GraphemeIdx>, replacement: &str) {
+        let count = self.grapheme_count();
+        let graphemes = min(graphemes.start, count)..min(graphemes.end, count);
+        let byte_range = self.grapheme_idx_to_byte_idx_or_end(graphemes.start)
+            ..self.grapheme_idx_to_byte_idx_or_end(graphemes.end);
+        let first = graphemes.start.saturating_sub(1);
+        let window_start = self.grapheme_idx_to_byte_idx_or_end(first);
+        let edit_end = byte_range.start.saturating_add(replacement.len());
+
+        let removed = byte_range.len();
+        let added = replacement.len();
+        self.string.replace_range(byte_range, replacement);
+        let shift = |byte_idx: ByteIdx| byte_idx.saturating_add(added).saturating_sub(removed);
+
+        let mut new_fragments = Vec::new();
+        // 第一个保持不变的旧片段
+        let mut old_end = graphemes.end;
+        let mut resynced = false;
+        let rest = self.string.get(window_start..).unwrap_or_default();
+        for (byte_idx, grapheme) in rest.grapheme_indices(true) {
+            let start = window_start.saturating_add(byte_idx);
+            if start >= edit_end {
+                while self
+                    .fragments
+                    .get(old_end)
+                    .is_some_and(|fragment| shift(fragment.start) < start)
+                {
+                    old_end = old_end.saturating_add(1);
+                }
+                if self
+                    .fragments
+                    .get(old_end)
+                    .is_some_and(|fragment| shift(fragment.start) == start)
+                {
+                    resynced = true;
+                    break;
+                }
+            }
+            new_fragments.push(Self::fragment(grapheme, start));
+        }
+        // 没有与旧边界重合时，一直拆分到了行尾
+        if !resynced {
+            old_end = count;
+        }
+
+        let tab_width = self.tab_width;
+        let mut column = self.width_until(first);
+        for fragment in &mut new_fragments {
+            column = Self::layout_fragment(fragment, column, tab_width);
+        }
+        let mut in_layout = true;
+        for fragment in self.fragments.iter_mut().skip(old_end) {
+            fragment.start = shift(fragment.start);
+            in_layout = in_layout && fragment.column != column;
+            if in_layout {
+                column = Self::layout_fragment(fragment, column, tab_width);
+            }
+        }
+        self.fragments.splice(first..old_end, new_fragments);
+    }
+
+    /// 设置制表符宽度，并重新计算各字素的渲染宽度
+    pub fn set_tab_width(&mut self, tab_width: ColIdx) {
+        let tab_width = tab_width.max(1);
+        if self.tab_width != tab_width {
+            self.tab_width = tab_width;
+            // 字素的拆分不受制表符宽度影响，只需重新计算制表符的宽度
+            Self::layout_tabs(&mut self.fragments, 0, tab_width);
+        }
     }
 
     /// 处理替换字符
@@ -69,8 +211,6 @@ impl Line {
         match for_str {
             // 空格不用替换
             " " => None,
-            // tab制表符换成空格
-            "\t" => Some(' '),
             // 可见空白字符（如全角空格）替换为特殊字符 '␣'
             _ if width > 0 && for_str.trim().is_empty() => Some('␣'),
             // 不可见字符（如零宽字符）替换为特殊字符 '▯'
@@ -90,7 +230,7 @@ impl Line {
 
     /// 根据列索引获取可展示的内容
     pub fn get_visible_graphemes(&self, range: Range<ColIdx>) -> String {
-        self.get_annotated_visible_substr(range, None, None).to_string()
+        self.get_annotated_visible_substr(range, &[], None, None, None, false).to_string()
     }
 
     /// 获取给定列索引范围内的带注释字符串。
@@ -100,16 +240,22 @@ impl Line {
     ///
     /// # 参数
     /// - `range`: 获取带注释字符串的列索引范围。
+    /// - `highlights`: 语法高亮的注释，显示在选区和搜索匹配之下。
     /// - `query`: 要高亮显示在带注释字符串中的查询字符串。
     /// - `selected_match`: 要高亮显示在带注释字符串中的选定匹配项。仅在查询字符串不为空时应用。
+    /// - `selection`: 该行中被选中的字素索引范围。
+    /// - `render_whitespace`: 是否将空格显示为 `·`、制表符显示为 `→`。只影响显示，不改变行的内容。
     ///
     /// # 返回值
     /// 返回一个带注释的字符串 (`AnnotatedString`)。
     pub fn get_annotated_visible_substr(
         &self,
         range: Range<ColIdx>,
+        highlights: &[Annotation],
         query: Option<&str>,
         selected_match: Option<GraphemeIdx>,
+        selection: Option<Range<GraphemeIdx>>,
+        render_whitespace: bool,
     ) -> AnnotatedString {
         // 如果起始列索引大于或等于结束列索引，则返回默认的空带注释字符串
         if range.start >= range.end {
@@ -119,6 +265,20 @@ impl Line {
         // 创建一个新的带注释字符串
         let mut result = AnnotatedString::from(&self.string);
 
+        // 语法高亮最先添加，选区和搜索匹配的注释会显示在它之上
+        for annotation in highlights {
+            result.add_annotation(annotation.annotation_type, annotation.start, annotation.end);
+        }
+
+        // 先添加选区注释，这样搜索匹配的注释可以显示在选区之上
+        if let Some(selection) = selection {
+            let start = self.grapheme_idx_to_byte_idx_or_end(selection.start);
+            let end = self.grapheme_idx_to_byte_idx_or_end(selection.end);
+            if start < end {
+                result.add_annotation(AnnotationType::Selection, start, end);
+            }
+        }
+
         // 根据搜索结果对字符串进行注释
         if let Some(query) = query {
             if !query.is_empty() {
@@ -165,7 +325,13 @@ impl Line {
 
             // 如果片段部分可见（右边缘超出范围），则用省略号替换
             if fragment_start < range.end && fragment_end > range.end {
-                result.replace(fragment.start, self.string.len(), "⋯");
+                let visible_width = range.end.saturating_sub(fragment_start);
+                // 右边缘截断时制表符的开头仍然可见，可以显示箭头
+                result.replace(
+                    fragment.start,
+                    self.string.len(),
+                    &Self::clipped_replacement(fragment, visible_width, render_whitespace),
+                );
                 continue;
             } else if fragment_start == range.end {
                 // 如果正好到达可见范围的末尾，则截断右侧
@@ -179,20 +345,21 @@ impl Line {
                 break; // 剩余片段都不可见，结束处理
             } else if fragment_start < range.start && fragment_end > range.start {
                 // 如果片段与可见范围的起始位置重叠，则移除左侧并添加省略号
+                let visible_width = fragment_end.saturating_sub(range.start);
                 result.replace(
                     0,
                     fragment.start.saturating_add(fragment.grapheme.len()),
-                    "⋯",
+                    &Self::clipped_replacement(fragment, visible_width, false),
                 );
                 break; // 剩余片段都不可见，结束处理
             }
 
             // 如果片段完全在可见范围内，则根据需要应用替换字符
             if fragment_start >= range.start && fragment_end <= range.end {
-                if let Some(replacement) = fragment.replacement {
+                if let Some(replacement) = Self::rendered_replacement(fragment, render_whitespace) {
                     let start_byte_idx = fragment.start;
                     let end_byte_idx = start_byte_idx.saturating_add(fragment.grapheme.len());
-                    result.replace(start_byte_idx, end_byte_idx, &replacement.to_string());
+                    result.replace(start_byte_idx, end_byte_idx, &replacement);
                 }
             }
         }
@@ -200,24 +367,218 @@ impl Line {
         result
     }
 
+    /// 获取完全可见的片段的显示内容，不需要替换时返回 `None`。
+    ///
+    /// 替换字符按渲染宽度重复，使制表符占满到下一个制表位的所有列。
+    /// 显示空白字符时，空格显示为 `·`，制表符显示为 `→` 后跟空格。
+    fn rendered_replacement(fragment: &TextFragment, render_whitespace: bool) -> Option<String> {
+        let width = usize::from(fragment.rendered_width);
+        if render_whitespace {
+            match fragment.grapheme.as_str() {
+                " " => return Some(String::from("·")),
+                "\t" => return Some(Self::tab_marker(width)),
+                _ => {}
+            }
+        }
+        fragment
+            .replacement
+            .map(|replacement| replacement.to_string().repeat(width))
+    }
+
+    /// 显示空白字符时制表符的显示内容：占满 `width` 列，第一列是箭头
+    fn tab_marker(width: ColIdx) -> String {
+        format!("→{}", " ".repeat(width.saturating_sub(1)))
+    }
+
+    /// 获取部分可见的片段的显示内容。
+    ///
+    /// 制表符本身显示为空白，被截断时用空格填满可见的列，这样后面的内容仍然对齐；
+    /// `show_tab_marker` 为 `true` 时(显示空白字符且制表符的开头可见)第一列显示箭头。
+    /// 其他字素无法只显示一部分，用省略号代替。
+    fn clipped_replacement(fragment: &TextFragment, visible_width: ColIdx, show_tab_marker: bool) -> String {
+        if matches!(fragment.rendered_width, GraphemeWidth::Tab(_)) {
+            if show_tab_marker {
+                Self::tab_marker(visible_width)
+            } else {
+                " ".repeat(visible_width)
+            }
+        } else {
+            String::from("⋯")
+        }
+    }
+
+    /// 判断指定位置的字素是否属于单词(字母、数字或下划线)
+    fn is_word_grapheme(&self, at: GraphemeIdx) -> bool {
+        self.fragments.get(at).is_some_and(|fragment| {
+            fragment
+                .grapheme
+                .chars()
+                .next()
+                .is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+        })
+    }
+
+    /// 查找下一个单词的开头。
+    ///
+    /// 从 `from` 开始先跳过当前单词，再跳过空白和标点，停在下一个单词的第一个字素上。
+    /// 后面没有单词时返回行尾。
+    pub fn next_word_start(&self, from: GraphemeIdx) -> GraphemeIdx {
+        let count = self.grapheme_count();
+        let mut index = min(from, count);
+        while index < count && self.is_word_grapheme(index) {
+            index = index.saturating_add(1);
+        }
+        while index < count && !self.is_word_grapheme(index) {
+            index = index.saturating_add(1);
+        }
+        index
+    }
+
+    /// 查找上一个单词的开头。
+    ///
+    /// 从 `from` 开始向左先跳过空白和标点，再跳过单词，停在该单词的第一个字素上。
+    /// 前面没有单词时返回行首。
+    pub fn prev_word_start(&self, from: GraphemeIdx) -> GraphemeIdx {
+        let mut index = min(from, self.grapheme_count());
+        while index > 0 && !self.is_word_grapheme(index.saturating_sub(1)) {
+            index = index.saturating_sub(1);
+        }
+        while index > 0 && self.is_word_grapheme(index.saturating_sub(1)) {
+            index = index.saturating_sub(1);
+        }
+        index
+    }
+
+    /// 获取指定位置的字素所在单词的字素索引范围，该字素不属于单词(如空白或标点)时返回 `None`
+    pub fn word_at(&self, at: GraphemeIdx) -> Option<Range<GraphemeIdx>> {
+        if !self.is_word_grapheme(at) {
+            return None;
+        }
+        let start = self.prev_word_start(at.saturating_add(1));
+        let mut end = at;
+        while self.is_word_grapheme(end) {
+            end = end.saturating_add(1);
+        }
+        Some(start..end)
+    }
+
+    /// 查找整行中作为完整单词出现的 `word`，返回每个匹配项的字节索引范围。
+    ///
+    /// 前后紧挨着单词字素的匹配项(如 `foobar` 中的 `foo`)不算在内。
+    pub fn find_words(&self, word: &str) -> Vec<Range<ByteIdx>> {
+        if word.is_empty() {
+            return Vec::new();
+        }
+        let grapheme_count = word.graphemes(true).count();
+        self.find_all(word, 0..self.string.len())
+            .into_iter()
+            .filter(|(_, grapheme_idx)| {
+                let before = grapheme_idx.checked_sub(1).is_some_and(|index| self.is_word_grapheme(index));
+                !before && !self.is_word_grapheme(grapheme_idx.saturating_add(grapheme_count))
+            })
+            .map(|(byte_idx, _)| byte_idx..byte_idx.saturating_add(word.len()))
+            .collect()
+    }
+
     /// 内容长度
     pub fn grapheme_count(&self) -> GraphemeIdx {
         self.fragments.len()
     }
 
-    /// 计算宽度
+    /// 计算到指定字素为止的总宽度，超出行尾时为整行的宽度
     pub fn width_until(&self, grapheme_index: GraphemeIdx) -> ColIdx {
-        // 计算到指定字素为止的总宽度
+        self.fragments.get(grapheme_index).map_or_else(
+            || {
+                self.fragments.last().map_or(0, |fragment| {
+                    fragment.column.saturating_add(fragment.rendered_width.into())
+                })
+            },
+            |fragment| fragment.column,
+        )
+    }
+
+    /// 获取显示在指定列上的字素索引。
+    ///
+    /// 列落在宽字符或制表符的中间时返回该字素的索引；超出行宽时返回行尾。
+    pub fn grapheme_idx_at_column(&self, column: ColIdx) -> GraphemeIdx {
+        let mut fragment_end: ColIdx = 0;
         self.fragments
             .iter()
-            .take(grapheme_index)
-            .map(|fragment| {
-                match fragment.rendered_width {
-                    GraphemeWidth::Half => 1,
-                    GraphemeWidth::Full => 2
-                }
+            .position(|fragment| {
+                fragment_end = fragment_end.saturating_add(fragment.rendered_width.into());
+                column < fragment_end
             })
-            .sum()
+            .unwrap_or(self.grapheme_count())
+    }
+
+    /// 获取最接近指定列的字素边界所在的字素索引。
+    ///
+    /// 列落在宽字符或制表符的中间时，取离它更近的一侧，距离相同时取左侧；超出行宽时返回行尾。
+    pub fn grapheme_at_width(&self, column: ColIdx) -> GraphemeIdx {
+        let grapheme_idx = self.grapheme_idx_at_column(column);
+        if grapheme_idx == self.grapheme_count() {
+            return grapheme_idx;
+        }
+        let start = self.width_until(grapheme_idx);
+        let end = self.width_until(grapheme_idx.saturating_add(1));
+        if column.saturating_sub(start) <= end.saturating_sub(column) {
+            grapheme_idx
+        } else {
+            grapheme_idx.saturating_add(1)
+        }
+    }
+
+    /// 获取不小于指定列的第一个字素边界。
+    ///
+    /// 列落在宽字符或制表符的中间时返回该字素结束的列；超出行宽时原样返回。
+    pub fn grapheme_boundary_at_or_after(&self, column: ColIdx) -> ColIdx {
+        let grapheme_idx = self.grapheme_idx_at_column(column);
+        if grapheme_idx == self.grapheme_count() || self.width_until(grapheme_idx) == column {
+            column
+        } else {
+            self.width_until(grapheme_idx.saturating_add(1))
+        }
+    }
+
+    /// 按给定宽度折行，返回每一行对应的列范围。
+    ///
+    /// 折行只发生在字素之间，宽字符和制表符不会被拆开；宽度不足以放下单个字素时，
+    /// 该字素单独占一行。空行也返回一个空范围。
+    pub fn wrap(&self, width: ColIdx) -> Vec<Range<ColIdx>> {
+        self.wrap_rows(width, false)
+    }
+
+    /// 按给定宽度折行，与 `wrap` 相同，但尽量在空白之后断行，不拆开单词。
+    ///
+    /// 单词比宽度还长时，仍然在字素之间断开。
+    pub fn word_wrap(&self, width: ColIdx) -> Vec<Range<ColIdx>> {
+        self.wrap_rows(width, true)
+    }
+
+    /// 折行的实现。`at_words` 为 `true` 时优先在当前行最后一个空白之后断行
+    fn wrap_rows(&self, width: ColIdx, at_words: bool) -> Vec<Range<ColIdx>> {
+        let mut rows = Vec::new();
+        let mut row_start: ColIdx = 0;
+        let mut column: ColIdx = 0;
+        // 当前行中最后一个空白之后的列
+        let mut word_start: Option<ColIdx> = None;
+        for fragment in &self.fragments {
+            let fragment_end = column.saturating_add(fragment.rendered_width.into());
+            while fragment_end.saturating_sub(row_start) > width && column > row_start {
+                let row_end = word_start
+                    .take()
+                    .filter(|word_start| *word_start > row_start)
+                    .unwrap_or(column);
+                rows.push(row_start..row_end);
+                row_start = row_end;
+            }
+            column = fragment_end;
+            if at_words && fragment.grapheme.chars().all(char::is_whitespace) {
+                word_start = Some(column);
+            }
+        }
+        rows.push(row_start..column);
+        rows
     }
 
     /// 获取行宽度
@@ -227,18 +588,14 @@ impl Line {
     
     /// 插入字符
     pub fn insert_char(&mut self, character: char, at: GraphemeIdx) {
-        debug_assert!(at.saturating_sub(1) <= self.grapheme_count());
-        // 尝试检索相应的片段,直接操作string
-        if let Some(fragment) = self.fragments.get(at) {
-            // 根据字素索引插入
-            self.string.insert(fragment.start, character);
-        } else {
-            // 添加到末尾
-            self.string.push(character);
-        }
+        // 可以插入到任一字素之前，或者行尾(索引等于字素数量)
+        debug_assert!(at <= self.grapheme_count());
+        self.replace_graphemes(at..at, character.encode_utf8(&mut [0; 4]));
+    }
 
-        // 通过rebuild方法将string重新构建成fragments
-        self.rebuild_fragments();
+    /// 在指定的字素索引处插入字符串
+    pub fn insert_str(&mut self, text: &str, at: GraphemeIdx) {
+        self.replace_graphemes(at..at, text);
     }
 
     /// 追加字符
@@ -249,21 +606,33 @@ impl Line {
     /// 删除指定位置字符
     pub fn delete(&mut self, at: GraphemeIdx) {
         debug_assert!(at <= self.grapheme_count());
-        // 尝试检索相应的片段,直接操作string
-        if let Some(fragment) = self.fragments.get(at) {
-            // 获取字素开始索引
-            let start = fragment.start;
-            // 根据grapheme 簇长度计算结束索引
-            let end = fragment
-                .start
-                .saturating_add(fragment.grapheme.len());
-            // 通过索引范围移除
-            self.string.drain(start..end);
-            // rebuild重生构建fragments
-            self.rebuild_fragments();
+        if at < self.grapheme_count() {
+            self.replace_graphemes(at..at.saturating_add(1), "");
+        }
+    }
+
+    /// 获取行首的缩进(空格和制表符)
+    pub fn indentation(&self) -> &str {
+        let text = self.string.as_str();
+        let end = text
+            .find(|character| character != ' ' && character != '\t')
+            .unwrap_or(text.len());
+        text.get(..end).unwrap_or_default()
+    }
+
+    /// 删除指定范围内的字素
+    pub fn delete_range(&mut self, range: Range<GraphemeIdx>) {
+        if range.start < range.end {
+            self.replace_graphemes(range, "");
         }
     }
 
+    /// 删除末尾的一个单词(包括其后的空白)
+    pub fn delete_word_backward(&mut self) {
+        let end = self.grapheme_count();
+        self.delete_range(self.prev_word_start(end)..end);
+    }
+
     /// 删除最后的字符
     pub fn delete_last(&mut self) {
         self.delete(self.grapheme_count().saturating_sub(1));
@@ -282,9 +651,9 @@ impl Line {
             // 分隔后进行rebuild,返回后剩余的
             let remainder = self.string.split_off(fragment.start);
             self.rebuild_fragments();
-            Self::from(&remainder)
+            Self::new(&remainder, self.tab_width)
         } else {
-            Self::default()
+            Self::new("", self.tab_width)
         }
     }
 
@@ -299,11 +668,11 @@ impl Line {
             .position(|fragment| fragment.start >= byte_idx)
     }
 
-    /// 将给定的字素索引转换为字节索引
+    /// 将给定的字素索引转换为字节索引，索引等于字素数量时返回字符串末尾
     fn grapheme_idx_to_byte_idx(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
         debug_assert!(grapheme_idx <= self.grapheme_count());
-        if grapheme_idx == 0 || self.grapheme_count() == 0 {
-            return 0;
+        if grapheme_idx == self.grapheme_count() {
+            return self.string.len();
         }
         self.fragments.get(grapheme_idx).map_or_else(
             || {
@@ -320,6 +689,13 @@ impl Line {
         )
     }
 
+    /// 将给定的字素索引转换为字节索引，索引超出范围时返回字符串末尾
+    fn grapheme_idx_to_byte_idx_or_end(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
+        self.fragments
+            .get(grapheme_idx)
+            .map_or(self.string.len(), |fragment| fragment.start)
+    }
+
     /// 向下搜索给定查询字符串的位置。
     ///
     /// # 参数
@@ -364,13 +740,8 @@ impl Line {
         if from_grapheme_idx == 0 {
             return None;
         }
-        // 获取结束字节索引：如果起始位置正好是字符串的末尾，则使用整个字符串长度；
-        // 否则，将图形符号索引转换为字节索引
-        let end_byte_index = if from_grapheme_idx == self.grapheme_count() {
-            self.string.len()
-        } else {
-            self.grapheme_idx_to_byte_idx(from_grapheme_idx)
-        };
+        // 获取结束字节索引，起始位置正好是字符串末尾时为整个字符串长度
+        let end_byte_index = self.grapheme_idx_to_byte_idx(from_grapheme_idx);
         // 获取从字符串开头到结束字节索引的子字符串
         // 查找所有匹配项并取最后一个，实现反向搜索
         self.find_all(query, 0..end_byte_index)
@@ -378,6 +749,14 @@ impl Line {
             .map(|(_, grapheme_idx)| *grapheme_idx)
     }
 
+    /// 搜索整行中所有互不重叠的匹配项，返回它们的字素索引
+    pub fn search_all(&self, query: &str) -> Vec<GraphemeIdx> {
+        self.find_all(query, 0..self.string.len())
+            .into_iter()
+            .map(|(_, grapheme_idx)| grapheme_idx)
+            .collect()
+    }
+
     /// 根据给定字节索引范围搜索所有匹配的内容，最后返回匹配项的字节索引和图形符号索引的集合。
     ///
     /// # 参数
@@ -462,4 +841,176 @@ impl Deref for Line {
     fn deref(&self) -> &Self::Target {
         &self.string
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_at_end_of_line_with_wide_graphemes() {
+        let mut line = Line::from("你好");
+        line.insert_char('!', line.grapheme_count());
+        assert_eq!(&*line, "你好!");
+        assert_eq!(line.grapheme_count(), 3);
+        assert_eq!(line.width(), 5);
+    }
+
+    #[test]
+    fn insert_into_empty_line() {
+        let mut line = Line::from("");
+        line.insert_char('世', 0);
+        assert_eq!(&*line, "世");
+        assert_eq!(line.width_until(1), 2);
+    }
+
+    #[test]
+    fn delete_at_and_past_end_of_line_with_wide_graphemes() {
+        let mut line = Line::from("你好!");
+        line.delete(2);
+        assert_eq!(&*line, "你好");
+        // 行尾没有字素可以删除
+        line.delete(2);
+        assert_eq!(&*line, "你好");
+        line.delete(0);
+        assert_eq!(&*line, "好");
+    }
+
+    #[test]
+    fn grapheme_index_maps_to_byte_index() {
+        let line = Line::from("a你e\u{301}");
+        assert_eq!(line.grapheme_idx_to_byte_idx(0), 0);
+        assert_eq!(line.grapheme_idx_to_byte_idx(1), 1);
+        assert_eq!(line.grapheme_idx_to_byte_idx(2), 4);
+        // 行尾对应字符串的字节长度
+        assert_eq!(line.grapheme_idx_to_byte_idx(3), "a你e\u{301}".len());
+    }
+
+    #[test]
+    fn search_backward_from_end_of_line() {
+        let line = Line::from("你好你好");
+        assert_eq!(line.search_backward("你", line.grapheme_count()), Some(2));
+        assert_eq!(line.search_forward("好", 2), Some(3));
+    }
+
+    #[test]
+    fn search_all_finds_non_overlapping_matches() {
+        let line = Line::from("aaaa");
+        assert_eq!(line.search_all("aa"), vec![0, 2]);
+        assert!(line.search_all("b").is_empty());
+    }
+
+    #[test]
+    fn find_words_skips_matches_inside_longer_words() {
+        let line = Line::from("foo foobar _foo foo.名foo");
+        assert_eq!(line.word_at(5), Some(4..10));
+        assert_eq!(line.word_at(3), None);
+        assert_eq!(line.find_words("foo"), vec![0..3, 16..19]);
+        assert!(line.find_words("").is_empty());
+    }
+
+    #[test]
+    fn grapheme_boundary_at_or_after_skips_wide_glyph_halves() {
+        let line = Line::from("ab你好");
+        assert_eq!(line.grapheme_boundary_at_or_after(2), 2);
+        assert_eq!(line.grapheme_boundary_at_or_after(3), 4);
+        assert_eq!(line.grapheme_boundary_at_or_after(5), 6);
+        assert_eq!(line.grapheme_boundary_at_or_after(9), 9);
+    }
+
+    #[test]
+    fn grapheme_at_width_returns_nearest_boundary() {
+        let line = Line::from("ab你好");
+        assert_eq!(line.grapheme_at_width(1), 1);
+        // 第 3 列位于“你”的中间，与两侧边界距离相同时取左侧
+        assert_eq!(line.grapheme_at_width(3), 2);
+        assert_eq!(line.grapheme_at_width(100), 4);
+    }
+
+    #[test]
+    fn tabs_expand_to_next_tab_stop() {
+        let line = Line::new("\tfoo", 4);
+        assert_eq!(line.width_until(1), 4);
+        assert_eq!(line.get_visible_graphemes(0..7), "    foo");
+        assert_eq!(Line::new("\tfoo", 8).width_until(1), 8);
+        // 制表符只填充到下一个制表位，`c` 对齐到制表符宽度所在的列
+        let line = Line::new("ab\tc", 4);
+        assert_eq!(line.width_until(3), 4);
+        assert_eq!(line.get_visible_graphemes(0..5), "ab  c");
+        assert_eq!(Line::new("ab\tc", 8).width_until(3), 8);
+    }
+
+    #[test]
+    fn editing_before_tab_lays_out_following_tabs_again() {
+        let mut line = Line::new("ab\tc\td", 4);
+        assert_eq!((line.width_until(3), line.width_until(5)), (4, 8));
+        line.insert_str("xy", 0);
+        assert_eq!((line.width_until(5), line.width_until(7)), (8, 12));
+        line.delete(0);
+        assert_eq!((line.width_until(4), line.width_until(6)), (4, 8));
+        assert_eq!(line.get_visible_graphemes(0..9), "yab c   d");
+    }
+
+    #[test]
+    fn incremental_edits_match_fresh_layout_on_long_line() {
+        let mut line = Line::from(&"😀a\t👍🏽".repeat(2500));
+        assert_eq!(line.grapheme_count(), 10_000);
+        let assert_fresh = |line: &Line| {
+            assert_eq!(line.fragments, Line::new(&line.string, line.tab_width).fragments);
+        };
+        line.insert_char('x', 5000);
+        assert_fresh(&line);
+        line.insert_str("\t", 1);
+        assert_fresh(&line);
+        // 组合字符与前面的 `a` 合并为一个字素
+        let count = line.grapheme_count();
+        line.insert_char('\u{301}', 3);
+        assert_eq!(line.grapheme_count(), count);
+        assert_fresh(&line);
+        line.insert_str("你好\t", 9990);
+        assert_fresh(&line);
+        line.delete(9000);
+        assert_fresh(&line);
+        line.delete_range(100..200);
+        assert_fresh(&line);
+        line.delete(0);
+        assert_fresh(&line);
+        line.set_tab_width(8);
+        line.insert_char('y', 3);
+        assert_fresh(&line);
+        assert_eq!(line.width(), Line::new(&line.string, 8).width());
+    }
+
+    #[test]
+    fn word_wrap_breaks_after_whitespace() {
+        assert_eq!(Line::from("hello world foo").word_wrap(8), vec![0..6, 6..12, 12..15]);
+        // 比宽度还长的单词在字素之间断开
+        assert_eq!(Line::from("abcdefghij").word_wrap(4), vec![0..4, 4..8, 8..10]);
+        assert_eq!(Line::from("").word_wrap(4), vec![0..0]);
+    }
+
+    #[test]
+    fn word_wrap_keeps_wide_graphemes_and_tabs_whole() {
+        // 宽字符放不下时整个移到下一个折行
+        assert_eq!(Line::from("abc你").word_wrap(4), vec![0..3, 3..5]);
+        // 制表符恰好填满折行时，在它之后断行
+        assert_eq!(Line::new("abc\td", 4).word_wrap(4), vec![0..4, 4..5]);
+        // 放不下的制表符整个移到下一个折行，宽度按它原来的列计算
+        assert_eq!(Line::new("ab\tc", 8).word_wrap(5), vec![0..2, 2..8, 8..9]);
+    }
+
+    #[test]
+    fn whitespace_is_rendered_without_changing_the_line() {
+        let line = Line::new("a\tb c", 4);
+        let visible = |range: Range<ColIdx>, render_whitespace| {
+            line.get_annotated_visible_substr(range, &[], None, None, None, render_whitespace)
+                .to_string()
+        };
+        assert_eq!(visible(0..7, false), "a   b c");
+        assert_eq!(visible(0..7, true), "a→  b·c");
+        // 制表符被右边缘截断时仍显示箭头，被左边缘截断时只显示空白
+        assert_eq!(visible(0..2, true), "a→");
+        assert_eq!(visible(2..7, true), "  b·c");
+        assert_eq!(line.to_string(), "a\tb c");
+        assert_eq!(line.grapheme_count(), 5);
+    }
+}
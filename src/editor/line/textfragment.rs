@@ -2,7 +2,7 @@ use crate::prelude::*;
 
 use super::GraphemeWidth;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TextFragment {
     // 图形单元的字符串形式
     pub grapheme: String,
@@ -12,4 +12,6 @@ pub struct TextFragment {
     pub replacement: Option<char>,
     // 字素字节索引
     pub start: ByteIdx,
+    // 字素在行中开始的列，由 `Line::layout_tabs` 计算
+    pub column: ColIdx,
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+use super::command::{Command, System};
+
+/// 录制和回放命令序列(宏)。
+///
+/// 录制的是按键转换后的命令，回放时交给处理输入时相同的方法，
+/// 所以提示中输入的内容也会按原样回放。控制录制和回放的命令本身不会被录制，
+/// 因此宏在回放时不会再次触发回放。
+#[derive(Default)]
+pub struct MacroRecorder {
+    // 正在录制的命令，为 None 时没有在录制
+    recording: Option<Vec<Command>>,
+    // 最近一次录制完成的宏
+    last: Vec<Command>,
+    // 是否正在回放
+    playing: bool,
+}
+
+impl MacroRecorder {
+    /// 开始录制，已经在录制时返回 `false`
+    pub fn start(&mut self) -> bool {
+        if self.recording.is_some() {
+            return false;
+        }
+        self.recording = Some(Vec::new());
+        true
+    }
+
+    /// 停止录制，录制的命令替换之前的宏。
+    ///
+    /// # 返回值
+    /// 返回录制的命令数，没有在录制时返回 `None`。
+    pub fn stop(&mut self) -> Option<usize> {
+        let commands = self.recording.take()?;
+        let count = commands.len();
+        self.last = commands;
+        Some(count)
+    }
+
+    pub const fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// 正在录制时记录命令。录制和回放的控制命令以及调整窗口大小不会被记录
+    pub fn record(&mut self, command: &Command) {
+        let Some(commands) = self.recording.as_mut() else {
+            return;
+        };
+        if !matches!(
+            command,
+            Command::System(System::StartRecord | System::StopRecord | System::PlayMacro | System::Resize(_))
+        ) {
+            commands.push(command.clone());
+        }
+    }
+
+    /// 开始回放，返回要回放的命令。
+    ///
+    /// 正在录制、正在回放或者还没有录制过宏时返回 `None`，回放结束后需要调用 [`Self::finish_playback`]。
+    pub fn start_playback(&mut self) -> Option<Vec<Command>> {
+        if self.playing || self.is_recording() || self.last.is_empty() {
+            return None;
+        }
+        self.playing = true;
+        Some(self.last.clone())
+    }
+
+    pub fn finish_playback(&mut self) {
+        self.playing = false;
+    }
+
+    /// 是否录制过非空的宏
+    pub fn has_macro(&self) -> bool {
+        !self.last.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::command::Edit;
+
+    #[test]
+    fn control_commands_are_not_recorded() {
+        let mut recorder = MacroRecorder::default();
+        recorder.record(&Command::Edit(Edit::Insert('a')));
+        assert!(recorder.start());
+        assert!(!recorder.start());
+        recorder.record(&Command::Edit(Edit::Insert('b')));
+        recorder.record(&Command::System(System::PlayMacro));
+        recorder.record(&Command::System(System::StopRecord));
+        assert_eq!(recorder.stop(), Some(1));
+        assert_eq!(recorder.stop(), None);
+        assert!(recorder.has_macro());
+    }
+
+    #[test]
+    fn playback_cannot_be_nested() {
+        let mut recorder = MacroRecorder::default();
+        assert!(recorder.start_playback().is_none());
+        recorder.start();
+        recorder.record(&Command::Edit(Edit::Insert('a')));
+        // 录制期间不能回放
+        assert!(recorder.start_playback().is_none());
+        recorder.stop();
+        assert_eq!(recorder.start_playback().map(|commands| commands.len()), Some(1));
+        assert!(recorder.start_playback().is_none());
+        recorder.finish_playback();
+        assert!(recorder.start_playback().is_some());
+    }
+}
@@ -1,37 +1,104 @@
 use crate::prelude::*;
 use std::env;
-use std::io::Error;
+use std::io::{Error, ErrorKind};
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::panic::{set_hook, take_hook};
-use crossterm::event::{read, Event, KeyEvent, KeyEventKind};
+use std::time::{Duration, Instant};
+use crossterm::event::{poll, read, Event, KeyEvent, KeyEventKind};
 
 use command::{
-    Command::{self, Edit, Move, System},
-    Edit::InsertNewline,
+    Command::{self, Edit, Move, Select, System},
+    Edit::{Delete, DeleteBackward, DeleteLine, Indent, Insert, InsertNewline, MoveLineDown, MoveLineUp, Paste},
     Move::{Down, Left, Right, Up},
-    System::{Dismiss, Quit, Resize, Save, Search}
+    System::{
+        AddCursorAtNextMatch, AddCursorBelow, Browse, Copy, Dismiss, FindFile, FocusOtherPane, ForceQuit, GoToLine, JoinLines, JumpToMatchingLine, MouseClick, NextBuffer, Open, OpenFile, PlayMacro, PrevBuffer, Quit,
+        RecentFiles, Reload, ReloadConfig, ReloadWithEncoding, Replace, Resize, ReverseLines, Save, SaveQuit, Search, SortLines, SplitLine, StartRecord, StopRecord, ToggleFold, ToggleLineNumbers, ToggleOccurrences,
+        ToggleLinePreview, ToggleOvertype, ToggleScrollbar, ToggleSplit, ToggleWhitespace, WordCount,
+    },
 };
 
 use terminal::Terminal;
-use uicomponents::{CommandBar,MessageBar,View, StatusBar, UIComponent};
-use line::Line;
+use uicomponents::{CommandBar, FinderList, MessageBar, SearchDirection, View, StatusBar, UIComponent, DiskChange};
+pub use uicomponents::TextBuffer;
+use line::{Line, DEFAULT_TAB_WIDTH};
 use documentstatus::DocumentStatus;
-use annotatedstring::{AnnotatedString, AnnotationType};
+use annotatedstring::{AnnotatedString, Annotation, AnnotationType};
+use config::Config;
+use history::History;
+use statusoutput::StatusOutput;
+use pathcompletion::{expand_tilde, PathCompletion};
+use state::State;
+use browser::DirectoryBrowser;
+use finder::Finder;
+use macrorecorder::MacroRecorder;
+use mode::Mode;
 
 mod annotatedstring;
+mod browser;
+mod finder;
 mod terminal;
 mod command;
 mod uicomponents;
 mod documentstatus;
 mod line;
+mod config;
+mod history;
+mod macrorecorder;
+mod mode;
+mod pathcompletion;
+mod state;
+mod statusoutput;
+mod syntax;
 
-// 为保持时进行退出操作所需操作次数
-const QUIT_TIMES: u8 = 3;
+// 没有输入时主循环被唤醒的间隔，用于清除过期的消息和检查是否需要自动保存
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// 左右分屏时每个窗格的最小宽度，终端更窄时不能分屏
+const MIN_PANE_WIDTH: usize = 20;
+// 查找文件时最多显示的匹配结果数
+const MAX_FINDER_RESULTS: usize = 10;
 
 /// 提示类型枚举
 #[derive(Eq, PartialEq, Default)]
 enum PromptType {
     Search,
     Save,
+    // 另存为的文件已存在时确认是否覆盖
+    ConfirmOverwrite,
+    // 输入要在当前缓冲区中打开的文件
+    Open,
+    // 当前缓冲区有未保存的修改时，确认是否丢弃修改并打开其他文件
+    ConfirmOpen,
+    // 输入要在新缓冲区中打开的文件
+    OpenFile,
+    // 按数字选择要打开的最近打开过的文件
+    RecentFiles,
+    // 浏览目录，选择要打开的文件
+    Browse,
+    // 在项目目录树中模糊查找要打开的文件
+    FindFile,
+    // 替换的第一阶段：输入要查找的内容
+    Replace,
+    // 替换的第二阶段：输入替换后的内容
+    ReplaceWith,
+    // 逐个确认是否替换匹配项
+    ReplaceConfirm,
+    // 确认是否重新加载文件并丢弃未保存的修改
+    ConfirmReload,
+    // 输入重新读取文件时使用的编码
+    ReloadEncoding,
+    // 跳转到指定行
+    GoToLine,
+    // 输入内容，跳转到下一个包含它的行
+    JumpToMatchingLine,
+    // 按分隔符拆分当前行
+    SplitLine,
+    // 用分隔符合并选中的行
+    JoinLines,
+    // 模式编辑的普通模式中按 `:` 输入的命令，如 `w`、`q`、`wq`
+    Command,
+    // 输入回放宏的次数
+    PlayMacro,
     #[default]
     None,
 }
@@ -42,10 +109,21 @@ impl PromptType {
     }
 }
 
+// clippy::struct_excessive_bools: 这些标志分别记录互相独立的编辑器状态
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Default)]
 pub struct Editor {
     should_quit: bool,
+    // 当前缓冲区的视图
     view: View,
+    // 所有缓冲区的视图。当前缓冲区被换出到 `view` 中，它的位置上保存一个占位的视图
+    buffers: Vec<View>,
+    // 当前缓冲区在 `buffers` 中的序号
+    active_buffer: usize,
+    // 左右分屏时另一个窗格显示的缓冲区序号，为 None 时没有分屏
+    split_buffer: Option<usize>,
+    // 分屏时当前缓冲区是否显示在右侧的窗格中
+    active_pane_right: bool,
     // 状态栏
     status_bar: StatusBar,
     // 消息栏
@@ -54,17 +132,60 @@ pub struct Editor {
     command_bar: CommandBar,
     // 提示类型
     prompt_type: PromptType,
+    // 启用模式编辑时编辑器所处的模式
+    mode: Mode,
+    // 普通模式中是否已经按下了一次 `d`，再按一次删除当前行
+    pending_delete: bool,
+    // 编辑器配置
+    config: Config,
+    // 启动时读取的配置文件路径，重新加载配置时使用同一个文件
+    config_path: Option<PathBuf>,
+    // 在多次运行之间保存的状态，如最近打开的文件
+    state: State,
+    // 状态文件的路径，为 None 时状态只保存在内存中
+    state_path: Option<PathBuf>,
     // 终端大小
     terminal_size: Size,
     title: String,
     // 用于跟踪用户尝试退出的次数
-    quit_times: u8,
+    quit_times: usize,
+    // 是否在当前缓冲区保存成功后退出，需要先输入文件名时为 true
+    quit_after_save: bool,
+    // 替换后的内容
+    replacement: String,
+    // 本次替换操作已替换的次数
+    replaced_count: usize,
+    // 本次会话中搜索过的内容
+    search_history: History,
+    // 保存或打开文件提示中正在循环选择的路径补全候选项
+    path_completion: Option<PathCompletion>,
+    // 浏览目录时正在浏览的目录和选中的项
+    browser: Option<DirectoryBrowser>,
+    // 第一次查找文件时遍历得到的项目文件列表，之后重复使用
+    finder: Option<Finder>,
+    // 查找文件时显示的匹配结果
+    finder_list: FinderList,
+    // 等待确认覆盖的另存为文件名
+    overwrite_target: String,
+    // 等待确认丢弃修改后在当前缓冲区中打开的文件名
+    pending_open: String,
+    // 启用时将文档状态以 JSON 写入指定的路径
+    status_output: Option<StatusOutput>,
+    // 最后一次处理输入事件的时间，用于判断是否需要自动保存
+    last_activity: Option<Instant>,
+    // 录制和回放宏
+    macro_recorder: MacroRecorder,
+    // 命令行中指定了 `-R`，所有缓冲区都以只读方式打开
+    read_only: bool,
 }
 
 impl Editor {
     // region: struct lifecycle
 
     /// 创建一个新的 `Editor` 实例。
+    ///
+    /// # Errors
+    /// 终端无法初始化(例如不是交互式终端)时返回错误。
     pub fn new() -> Result<Self, Error> {
         // 捕获并处理程序崩溃，确保终端能够正确恢复
         let current_hook = take_hook();
@@ -77,19 +198,51 @@ impl Editor {
 
         // 初始化编辑器参数
         let mut editor = Self::default();
+        editor.buffers.push(View::default());
         let size = Terminal::size().unwrap_or_default();
 
+        // 处理命令行参数。`-R` 表示以只读方式打开文件(包括之后打开的文件)，
+        // `--status-file <路径>` 表示将状态以 JSON 写入该路径
+        let mut file_arg = None;
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-R" => editor.read_only = true,
+                "--status-file" => {
+                    editor.status_output = args.next().map(|path| StatusOutput::new(PathBuf::from(path)));
+                }
+                _ if file_arg.is_none() => file_arg = Some(arg),
+                _ => {}
+            }
+        }
+
+        // 加载并应用配置
+        editor.config_path = Config::default_path();
+        let config_result = editor.load_config();
+        editor.apply_config();
+        editor.state_path = State::default_path();
+        editor.state = editor
+            .state_path
+            .as_deref()
+            .map_or_else(State::default, State::load);
         // 处理大小
         editor.handle_resize_command(size);
         // 设置编辑器默认消息栏消息
-        editor.update_message("HELP: Ctrl-F = find | Ctrl-S = save | Ctrl-Q = quit");
+        editor.update_message("HELP: Ctrl-F = find | Ctrl-R = replace | Ctrl-S = save | Ctrl-Q = quit");
+        if let Err(err) = config_result {
+            editor.update_message(&format!("WARNING! Could not load config, using defaults: {err}"));
+        }
 
-        // 处理命令行参数，尝试加载文件
-        let args: Vec<String> = env::args().collect();
-        if let Some(file_name) = args.get(1) {
-            debug_assert!(!file_name.is_empty());
-            if editor.view.load(file_name).is_err() {
-                editor.update_message(&format!("ERR: Could not open file: {file_name}"));
+        // 尝试加载命令行中指定的文件
+        if let Some(arg) = file_arg {
+            debug_assert!(!arg.is_empty());
+            // 支持 `文件名:行` 和 `文件名:行:列` 的形式，打开后跳转到该位置
+            let (file_name, location) = Self::split_file_location(&arg)
+                .map_or((arg.as_str(), None), |(file_name, location)| (file_name, Some(location)));
+            // 打开失败时保持为未命名的空缓冲区，保存时会重新询问文件名
+            let loaded = editor.load_file(file_name);
+            if let Some(location) = location.filter(|_| loaded) {
+                editor.view.go_to(location);
             }
         }
 
@@ -99,22 +252,84 @@ impl Editor {
         Ok(editor)
     }
 
+    /// 打开文件或目录失败的原因，显示在消息中
+    fn describe_error(err: &Error) -> &'static str {
+        match err.kind() {
+            ErrorKind::IsADirectory => "is a directory",
+            ErrorKind::PermissionDenied => "permission denied",
+            ErrorKind::NotFound => "no such file",
+            _ => "could not be read",
+        }
+    }
+
+    /// 将文件加载到当前缓冲区，并在消息栏报告加载失败的原因或需要注意的情况。
+    ///
+    /// # 返回值
+    /// 加载成功时返回 `true`，失败时当前缓冲区保持不变。
+    fn load_file(&mut self, file_name: &str) -> bool {
+        if let Err(err) = self.view.load(file_name) {
+            let reason = Self::describe_error(&err);
+            self.update_message(&format!("ERR: Could not open {file_name}: {reason}"));
+            return false;
+        }
+        if self.view.is_decoded_lossily() {
+            self.update_message(
+                "WARNING! File could not be decoded cleanly. Saving is disabled to protect it.",
+            );
+        } else if self.view.has_mixed_line_endings() {
+            let line_ending = self.view.get_status().line_ending;
+            self.update_message(&format!(
+                "WARNING! File has mixed line endings, will be saved as {line_ending}."
+            ));
+        } else if self.view.normalized_indentation_lines() > 0 {
+            let count = self.view.normalized_indentation_lines();
+            let style = if self.config.expand_tabs { "spaces" } else { "tabs" };
+            self.update_message(&format!("WARNING! File had mixed indentation, converted {count} lines to {style}."));
+        } else if let Some(tab_width) = self.view.modeline_tab_width() {
+            self.update_message(&format!("Modeline: tab width {tab_width}"));
+        }
+        self.state.add_recent_file(file_name);
+        if let Some(path) = self.state_path.as_deref() {
+            // 状态无法保存时不影响编辑，只是下次启动时看不到这次打开的文件
+            let _ = self.state.save(path);
+        }
+        true
+    }
+
     // endregion
 
     // region: Event Loop
 
     /// 运行编辑器主循环。
+    ///
+    /// 没有输入时每隔 `POLL_INTERVAL` 唤醒一次，以便清除过期的消息、执行自动保存。
+    /// 唤醒后只有在有组件需要重绘时才刷新屏幕，空闲时不会占用 CPU。
+    ///
+    /// # Panics
+    /// 调试构建中读取终端事件失败时 panic，发布构建中忽略该错误。
     pub fn run(&mut self) {
+        // 处理输入事件后总是刷新屏幕，光标位置可能变化
+        let mut should_refresh = true;
         loop {
             // 刷新屏幕
-            self.refresh_screen();
+            if should_refresh || self.needs_redraw() {
+                self.refresh_screen();
+            }
             // 如果应该退出，跳出循环
             if self.should_quit {
                 break;
             }
-            // 读取用户输入事件
-            match read() {
-                Ok(event) => self.evaluate_event(event),
+            // 等待用户输入事件，超时后处理定时任务
+            match poll(POLL_INTERVAL).and_then(|ready| ready.then(read).transpose()) {
+                Ok(Some(event)) => {
+                    self.evaluate_event(event);
+                    should_refresh = true;
+                }
+                Ok(None) => {
+                    self.check_file_on_disk();
+                    self.auto_save_if_idle();
+                    should_refresh = false;
+                }
                 Err(err) => {
                     #[cfg(debug_assertions)]
                     {
@@ -131,13 +346,23 @@ impl Editor {
         }
     }
 
+    /// 是否有组件需要重绘
+    fn needs_redraw(&self) -> bool {
+        self.message_bar.needs_redraw()
+            || self.status_bar.needs_redraw()
+            || self.command_bar.needs_redraw()
+            || self.view.needs_redraw()
+            || self.split_view().is_some_and(UIComponent::needs_redraw)
+    }
+
     /// 刷新屏幕
     fn refresh_screen(&mut self) {
-        if self.terminal_size.height == 0 || self.terminal_size.width == 0 {
+        if self.terminal_size.is_empty() {
             return;
         }
         // 底部栏位所占高度
         let bottom_bar_row = self.terminal_size.height.saturating_sub(1);
+        let view_height = self.view_height();
         // 在刷新屏幕之前隐藏光标。
         let _ = Terminal::hide_caret();
         // 判断是渲染命令栏还是消息栏
@@ -146,13 +371,29 @@ impl Editor {
         } else {
             self.message_bar.render(bottom_bar_row);
         }
-        // 渲染状态栏
-        if self.terminal_size.height > 1 {
-            self.status_bar.render(self.terminal_size.height.saturating_sub(2));
+        // 渲染状态栏，它位于view和消息栏之间
+        if self.status_bar_height() > 0 {
+            self.status_bar.render(view_height);
         }
-        // 渲染view
-        if self.terminal_size.height > 2 {
+        // 渲染view，分屏时同时渲染另一个窗格和两者之间的分隔线
+        if view_height > 0 {
+            // 查找文件的结果列表覆盖在view的底部，view重绘后列表也要重绘
+            if self.view.needs_redraw() || self.split_view().is_some_and(UIComponent::needs_redraw) {
+                self.finder_list.set_needs_redraw(true);
+            }
+            if let Some(index) = self.split_buffer {
+                if self.view.needs_redraw() || self.split_view().is_some_and(UIComponent::needs_redraw) {
+                    self.draw_split_separator();
+                }
+                if let Some(view) = self.buffers.get_mut(index) {
+                    view.render(0);
+                }
+            }
             self.view.render(0);
+            if self.prompt_type == PromptType::FindFile {
+                let finder_row = view_height.saturating_sub(self.finder_list.capacity());
+                self.finder_list.render(finder_row);
+            }
         }
         // 判断是从命令栏还是view获取光标位置
         let new_caret_pos = if self.in_prompt() {
@@ -177,8 +418,18 @@ impl Editor {
     /// 刷新编辑器状态
     pub fn refresh_status(&mut self) {
         // 获取状态,格式化title输出
-        let status = self.view.get_status();
+        let mut status = self.view.get_status();
+        if self.config.modal_editing {
+            status.mode = Some(self.mode);
+        }
+        status.recording = self.macro_recorder.is_recording();
+        if self.buffers.len() > 1 {
+            status.buffer_position = Some((self.active_buffer.saturating_add(1), self.buffers.len()));
+        }
         let title = format!("{} - {NAME}", status.file_name);
+        if let Some(status_output) = self.status_output.as_mut() {
+            status_output.write(&status);
+        }
         // 更新状态栏
         self.status_bar.update_status(status);
         // 判断标题是否已更改,并且写入终端成功.则更新editor保存的title
@@ -192,11 +443,12 @@ impl Editor {
         // 判断是否应该处理该事件
         let should_process = match &event {
             Event::Key(KeyEvent { kind, .. }) => kind == &KeyEventKind::Press,
-            Event::Resize(_, _) => true,
+            Event::Resize(_, _) | Event::Mouse(_) | Event::Paste(_) => true,
             _ => false,
         };
 
         if should_process {
+            self.last_activity = Some(Instant::now());
             if let Ok(command) = Command::try_from(event) {
                 self.process_command(command);
             }
@@ -213,15 +465,44 @@ impl Editor {
             self.handle_resize_command(size);
             return;
         }
+        // 录制时记录所有命令，包括提示中的输入，回放时才能重现相同的操作
+        self.macro_recorder.record(&command);
         match self.prompt_type {
             PromptType::Search => self.process_command_during_search(command),
             PromptType::Save => self.process_command_during_save(command),
+            PromptType::ConfirmOverwrite => self.process_command_during_confirm_overwrite(&command),
+            PromptType::Open => self.process_command_during_open_file(command, false),
+            PromptType::ConfirmOpen => self.process_command_during_confirm_open(&command),
+            PromptType::OpenFile => self.process_command_during_open_file(command, true),
+            PromptType::RecentFiles => self.process_command_during_recent_files(&command),
+            PromptType::Browse => self.process_command_during_browse(&command),
+            PromptType::FindFile => self.process_command_during_find_file(command),
+            PromptType::Replace => self.process_command_during_replace(command),
+            PromptType::ReplaceWith => self.process_command_during_replace_with(command),
+            PromptType::ReplaceConfirm => self.process_command_during_replace_confirm(&command),
+            PromptType::ConfirmReload => self.process_command_during_confirm_reload(&command),
+            PromptType::ReloadEncoding => self.process_command_during_reload_encoding(command),
+            PromptType::GoToLine => self.process_command_during_go_to_line(command),
+            PromptType::JumpToMatchingLine => self.process_command_during_jump_to_matching_line(command),
+            PromptType::SplitLine => self.process_command_during_split_line(command),
+            PromptType::JoinLines => self.process_command_during_join_lines(command),
+            PromptType::Command => self.process_command_during_command(command),
+            PromptType::PlayMacro => self.process_command_during_play_macro(command),
             PromptType::None => self.process_command_no_prompt(command),
         }
     }
 
     /// 无提示时处理命令
     fn process_command_no_prompt(&mut self, command: Command) {
+        // 启用模式编辑时先按当前模式转换命令
+        let command = if self.config.modal_editing {
+            let Some(command) = self.translate_modal_command(command) else {
+                return;
+            };
+            command
+        } else {
+            command
+        };
         // 处理退出
         if matches!(command, System(Quit)) {
             self.handle_quit_command();
@@ -231,17 +512,311 @@ impl Editor {
         self.reset_quit_times();
 
         match command {
+            // 只读时禁止修改缓冲区的操作
+            Edit(_) | System(Replace | SplitLine | JoinLines | SortLines | ReverseLines) if self.view.is_read_only() => {
+                self.update_message("Buffer is read-only");
+            }
             // 忽略退出和调整大小
-            System(Quit | Resize(_) | Dismiss) => {}
+            System(Quit | Resize(_)) => {}
+            // 收起多个光标
+            System(Dismiss) => {
+                self.view.collapse_cursors();
+            }
             // 搜索:设置提示
             System(Search) => self.set_prompt(PromptType::Search),
+            // 替换:设置提示
+            System(Replace) => self.set_prompt(PromptType::Replace),
             // 保存
             System(Save) => self.handle_save_command(),
+            // 保存后退出，以及不保存直接退出
+            System(SaveQuit) => self.handle_save_quit_command(),
+            System(ForceQuit) => self.should_quit = true,
+            // 复制选中的文本
+            System(Copy) => self.handle_copy_command(),
+            // 切换行号显示
+            System(ToggleLineNumbers) => self.view.toggle_line_numbers(),
+            // 跳转到指定行:设置提示
+            System(GoToLine) => self.set_prompt(PromptType::GoToLine),
+            System(JumpToMatchingLine) => self.set_prompt(PromptType::JumpToMatchingLine),
+            // 拆分当前行:设置提示
+            System(SplitLine) => self.set_prompt(PromptType::SplitLine),
+            // 合并选中的行:设置提示
+            System(JoinLines) => self.set_prompt(PromptType::JoinLines),
+            // 从磁盘重新读取文件
+            System(Reload) => self.handle_reload_command(),
+            // 按指定的编码重新读取文件
+            System(ReloadWithEncoding) => {
+                if self.view.is_file_loaded() {
+                    self.set_prompt(PromptType::ReloadEncoding);
+                } else {
+                    self.update_message("No file to reload.");
+                }
+            }
+            // 在消息栏中显示单词数和字符数
+            System(WordCount) => {
+                let (words, chars) = self.view.word_and_char_count();
+                self.update_message(&format!("Words: {words}, characters: {chars}"));
+            }
+            // 在当前缓冲区或新的缓冲区中打开文件:设置提示
+            System(Open) => self.set_prompt(PromptType::Open),
+            System(OpenFile) => self.set_prompt(PromptType::OpenFile),
+            // 列出最近打开的文件，按数字选择
+            System(RecentFiles) => {
+                if self.state.recent_files().is_empty() {
+                    self.update_message("No recent files.");
+                } else {
+                    self.set_prompt(PromptType::RecentFiles);
+                }
+            }
+            // 浏览当前工作目录，选择要打开的文件
+            System(Browse) => self.handle_browse_command(),
+            // 在项目目录树中查找文件
+            System(FindFile) => self.set_prompt(PromptType::FindFile),
+            // 切换缓冲区
+            System(NextBuffer) => self.cycle_buffer(true),
+            System(PrevBuffer) => self.cycle_buffer(false),
+            // 左右分屏
+            System(ToggleSplit) => self.handle_toggle_split_command(),
+            System(FocusOtherPane) => self.focus_other_pane(),
+            // 重新加载配置
+            System(ReloadConfig) => self.handle_reload_config_command(),
+            // 切换当前行的折行预览
+            System(ToggleLinePreview) => self.view.toggle_line_preview(),
+            // 切换插入模式和改写模式
+            System(ToggleOvertype) => self.view.toggle_overwrite(),
+            // 切换是否显示滚动条
+            System(ToggleScrollbar) => self.view.toggle_scrollbar(),
+            // 切换是否显示空白字符
+            System(ToggleWhitespace) => self.view.toggle_render_whitespace(),
+            // 切换是否高亮光标所在单词的出现位置
+            System(ToggleOccurrences) => self.view.toggle_highlight_occurrences(),
+            // 录制和回放宏
+            System(StartRecord) => self.handle_start_record_command(),
+            System(StopRecord) => self.handle_stop_record_command(),
+            System(PlayMacro) => self.handle_play_macro_command(),
+            // 添加光标
+            System(AddCursorBelow) => {
+                let added = self.view.add_cursor_below();
+                self.report_cursor_count(added, "No line below.");
+            }
+            System(AddCursorAtNextMatch) => {
+                let added = self.view.add_cursor_at_next_match();
+                self.report_cursor_count(added, "No more matches.");
+            }
+            // 排序选中的行、将选中的行倒序排列
+            System(SortLines) => {
+                let count = self.view.sort_selected_lines(self.config.sort_ignore_case);
+                self.report_reordered_lines(count, "Sorted");
+            }
+            System(ReverseLines) => {
+                let count = self.view.reverse_selected_lines();
+                self.report_reordered_lines(count, "Reversed");
+            }
+            // 折叠或展开代码块
+            System(ToggleFold) => {
+                if !self.view.toggle_fold() {
+                    self.update_message("Nothing to fold here.");
+                }
+            }
+            // 点击文本区域时移动光标，点击状态栏和消息栏时忽略
+            System(MouseClick(position)) => self.handle_click(position),
             // 编辑
             Edit(edit_command) => self.view.handle_edit_command(edit_command),
             // 移动光标
             Move(move_command) => self.view.handle_move_command(move_command),
+            // 扩展选区
+            Select(move_command) => self.view.handle_select_command(move_command),
+        }
+    }
+
+    // endregion
+
+    // region file reload handling
+
+    /// 处理重新读取文件。有未保存的修改时先确认
+    fn handle_reload_command(&mut self) {
+        if !self.view.is_file_loaded() {
+            self.update_message("No file to reload.");
+        } else if self.view.get_status().is_modified {
+            self.set_prompt(PromptType::ConfirmReload);
+        } else {
+            self.reload();
+        }
+    }
+
+    /// 检查文件是否被其他程序修改或删除。
+    ///
+    /// 文件被修改时，没有未保存的修改就直接重新读取，否则询问是否重新读取；
+    /// 文件被删除时只给出警告，缓冲区的内容保持不变，可以再次保存。
+    /// 有提示打开时暂不检查，等提示关闭后再处理。
+    fn check_file_on_disk(&mut self) {
+        if self.in_prompt() {
+            return;
+        }
+        match self.view.check_file_on_disk() {
+            DiskChange::Unchanged => {}
+            DiskChange::Modified if self.view.get_status().is_modified => {
+                self.update_message("WARNING! File changed on disk.");
+                self.set_prompt(PromptType::ConfirmReload);
+            }
+            DiskChange::Modified => self.reload(),
+            DiskChange::Deleted => self.update_message("WARNING! File was deleted from disk."),
+        }
+    }
+
+    /// 处理确认重新读取文件时的命令
+    fn process_command_during_confirm_reload(&mut self, command: &Command) {
+        match command {
+            Edit(Insert('y' | 'Y')) => {
+                self.set_prompt(PromptType::None);
+                self.reload();
+            }
+            System(Dismiss) | Edit(Insert('n' | 'N')) => {
+                self.set_prompt(PromptType::None);
+                self.update_message("Reload cancelled, unsaved changes kept.");
+            }
+            // 忽略无关的操作
+            System(_) | Move(_) | Select(_) | Edit(_) => {}
+        }
+    }
+
+    /// 处理输入重新读取文件时使用的编码时的命令。
+    ///
+    /// 编码名称无法识别时在提示中报告错误并保持提示打开；文件不能按该编码解码时保留当前缓冲区。
+    fn process_command_during_reload_encoding(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(InsertNewline) => {
+                let label = self.command_bar.value();
+                if label.trim().is_empty() {
+                    self.set_prompt(PromptType::None);
+                    return;
+                }
+                match self.view.reload_with_encoding(&label) {
+                    Ok(()) => {
+                        self.set_prompt(PromptType::None);
+                        let encoding = self.view.get_status().encoding;
+                        self.update_message(&format!("File reloaded as {encoding}."));
+                    }
+                    // 提示期间消息栏被命令栏覆盖，所以错误信息显示在提示符中
+                    Err(err) if err.kind() == ErrorKind::InvalidInput => {
+                        self.command_bar.set_prompt("Unknown encoding! Reload with encoding: ");
+                    }
+                    Err(err) if err.kind() == ErrorKind::InvalidData => {
+                        self.set_prompt(PromptType::None);
+                        self.update_message(&format!(
+                            "ERR: File is not valid {}, buffer unchanged.",
+                            label.trim()
+                        ));
+                    }
+                    Err(_) => {
+                        self.set_prompt(PromptType::None);
+                        self.update_message("ERR: Could not reload file!");
+                    }
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 从磁盘重新读取文件，并在消息栏报告结果
+    fn reload(&mut self) {
+        if self.view.reload().is_ok() {
+            self.update_message("File reloaded from disk.");
+        } else {
+            self.update_message("ERR: Could not reload file!");
+        }
+    }
+
+    // endregion
+
+    // region config handling
+
+    /// 从配置文件中读取配置。
+    ///
+    /// 配置文件不存在时保持当前配置；读取或解析失败时也保持当前配置，并返回错误信息。
+    fn load_config(&mut self) -> Result<(), String> {
+        let Some(path) = self.config_path.as_deref() else {
+            return Ok(());
+        };
+        if !path.exists() {
+            return Ok(());
+        }
+        self.config = Config::load(path)?;
+        Ok(())
+    }
+
+    /// 处理重新加载配置的命令。
+    ///
+    /// 重新读取启动时使用的配置文件并应用到所有组件。默认编码和 modeline
+    /// 只在加载文件时使用，对已经打开的文件不再生效，会在下次打开文件时生效。
+    fn handle_reload_config_command(&mut self) {
+        match self.load_config() {
+            Ok(()) => {
+                self.apply_config();
+                // 重新计算各组件的大小并全部重绘
+                self.handle_resize_command(self.terminal_size);
+                self.update_message("Configuration reloaded.");
+            }
+            Err(err) => {
+                self.update_message(&format!("ERR: Could not reload config: {err}"));
+            }
+        }
+    }
+
+    /// 将配置应用到各个组件
+    fn apply_config(&mut self) {
+        self.status_bar
+            .set_one_based_position(self.config.one_based_position);
+        Self::apply_view_config(&self.config, self.read_only, &mut self.view);
+        for view in &mut self.buffers {
+            Self::apply_view_config(&self.config, self.read_only, view);
         }
+        Terminal::set_theme(self.config.theme);
+        Command::set_keymap(self.config.keymap.clone());
+        // 启用模式编辑时从普通模式开始，关闭时始终处于插入模式
+        self.mode = if self.config.modal_editing {
+            Mode::Normal
+        } else {
+            Mode::Insert
+        };
+        self.pending_delete = false;
+    }
+
+    /// 创建应用了当前配置的空视图，用于打开新的文件
+    fn new_view(&self) -> View {
+        let mut view = View::default();
+        Self::apply_view_config(&self.config, self.read_only, &mut view);
+        view
+    }
+
+    /// 将配置和命令行中的只读选项应用到一个缓冲区的视图
+    fn apply_view_config(config: &Config, read_only: bool, view: &mut View) {
+        view.set_read_only(read_only);
+        // 无法识别的编码会被忽略，继续使用默认编码
+        let _ = view.set_fallback_encoding(&config.default_encoding);
+        view.set_newline_at_eof(config.newline_at_eof);
+        view.set_auto_indent(config.auto_indent);
+        view.set_auto_pair(config.auto_pair);
+        view.set_read_modelines(config.modelines);
+        view.set_tab_width(config.tab_width);
+        view.set_expand_tabs(config.expand_tabs);
+        view.set_normalize_indentation(config.normalize_indentation);
+        view.set_comment_token(&config.comment_token);
+        view.set_trim_trailing_whitespace(config.trim_trailing_whitespace);
+        view.set_trim_trailing_blank_lines(config.trim_trailing_blank_lines);
+        view.set_backup(config.backup);
+        view.set_wrap(config.wrap);
+        view.set_show_word_count(config.show_word_count);
+        view.set_show_line_numbers(config.show_line_numbers);
+        view.set_show_scrollbar(config.show_scrollbar);
+        view.set_render_whitespace(config.render_whitespace);
+        view.set_highlight_occurrences(config.highlight_occurrences);
+        view.set_ruler_column(config.ruler_column);
     }
 
     // endregion
@@ -251,18 +826,65 @@ impl Editor {
     /// 处理调整大小的命令
     fn handle_resize_command(&mut self, size: Size) {
         self.terminal_size = size;
-        // 空出底部两行给消息栏和状态栏
-        self.view.resize(Size {
-            height: size.height.saturating_sub(2),
-            width: size.width,
-        });
+        // 空出底部的行给消息栏和状态栏
+        let height = self.view_height();
+        let (left, right) = Self::pane_widths(size.width);
+        if self.split_buffer.is_some() && right < MIN_PANE_WIDTH {
+            self.split_buffer = None;
+            self.update_message("Terminal is too narrow, split closed.");
+        }
+        if let Some(index) = self.split_buffer {
+            // 右侧窗格从分隔线之后开始
+            let right_col = left.saturating_add(1);
+            let (active, other) = if self.active_pane_right {
+                ((right_col, right), (0, left))
+            } else {
+                ((0, left), (right_col, right))
+            };
+            self.view.set_origin_col(active.0);
+            self.view.resize(Size { height, width: active.1 });
+            if let Some(view) = self.buffers.get_mut(index) {
+                view.set_origin_col(other.0);
+                view.resize(Size { height, width: other.1 });
+            }
+        } else {
+            self.view.set_origin_col(0);
+            self.view.resize(Size {
+                height,
+                width: size.width,
+            });
+        }
         let bar_size = Size {
             height: 1,
             width: size.width,
         };
         self.message_bar.resize(bar_size);
-        self.status_bar.resize(bar_size);
+        self.status_bar.resize(Size {
+            height: self.status_bar_height(),
+            width: size.width,
+        });
         self.command_bar.resize(bar_size);
+        self.finder_list.resize(Size {
+            height: MAX_FINDER_RESULTS.min(height),
+            width: size.width,
+        });
+        if self.prompt_type == PromptType::FindFile {
+            self.update_finder_results();
+        }
+    }
+
+    /// 状态栏占用的行数。终端太矮时先减少状态栏的行数，保证最底部的消息栏可见
+    fn status_bar_height(&self) -> usize {
+        let rows = if self.config.two_line_status { 2 } else { 1 };
+        rows.min(self.terminal_size.height.saturating_sub(1))
+    }
+
+    /// view可用的行数：终端高度减去底部的消息栏和状态栏
+    fn view_height(&self) -> usize {
+        self.terminal_size
+            .height
+            .saturating_sub(1)
+            .saturating_sub(self.status_bar_height())
     }
 
     // endregion
@@ -270,19 +892,68 @@ impl Editor {
     // region quit command handling
 
     /// 处理退出编辑器命令
-    // clippy::arithmetic_side_effects: quit_times is guaranteed to be between 0 and QUIT_TIMES
-    #[allow(clippy::arithmetic_side_effects)]
     fn handle_quit_command(&mut self) {
-        // 未进行修改或退出操作次数累计达到3次,则设置退出标识为true
-        if !self.view.get_status().is_modified || self.quit_times + 1 == QUIT_TIMES {
+        // 未进行修改或退出操作次数累计达到配置的次数,则设置退出标识为true
+        let remaining = self
+            .config
+            .quit_times
+            .saturating_sub(self.quit_times)
+            .saturating_sub(1);
+        let modified_count = self.modified_buffer_count();
+        if modified_count == 0 || remaining == 0 {
             self.should_quit = true;
-        } else if self.view.get_status().is_modified {
+        } else {
             // 文件已进行修改,则格式化消息提示更新到消息栏,并累计退出操作次数
+            let files = if modified_count == 1 {
+                String::from("File has")
+            } else {
+                format!("{modified_count} files have")
+            };
             self.update_message(&format!(
-                "WARNING! File has unsaved changes. Press Ctrl-Q {} more times to quit.",
-                QUIT_TIMES - self.quit_times - 1
+                "WARNING! {files} unsaved changes. Press Ctrl-Q {remaining} more times to quit."
             ));
-            self.quit_times += 1;
+            self.quit_times = self.quit_times.saturating_add(1);
+        }
+    }
+
+    /// 获取有未保存修改的缓冲区数量
+    fn modified_buffer_count(&self) -> usize {
+        // 当前缓冲区已被换出，它在 `buffers` 中的占位视图不会有修改
+        self.buffers
+            .iter()
+            .chain([&self.view])
+            .filter(|view| view.get_status().is_modified)
+            .count()
+    }
+
+    /// 保存当前缓冲区后退出。
+    ///
+    /// 当前缓冲区没有修改时直接退出；未命名的缓冲区先询问文件名，保存成功后才退出。
+    /// 保存失败或取消保存时不退出。
+    fn handle_save_quit_command(&mut self) {
+        if self.view.get_status().is_modified {
+            self.handle_save_command();
+            // 需要输入文件名或确认覆盖时，等保存完成后再退出
+            if self.in_prompt() {
+                self.quit_after_save = true;
+                return;
+            }
+        }
+        self.quit_if_others_saved();
+    }
+
+    /// 当前缓冲区保存之后，其他缓冲区也没有未保存的修改时退出
+    fn quit_if_others_saved(&mut self) {
+        let modified_count = self.modified_buffer_count();
+        if modified_count == 0 {
+            self.should_quit = true;
+        } else if !self.view.get_status().is_modified {
+            let files = if modified_count == 1 {
+                String::from("Another file has")
+            } else {
+                format!("{modified_count} other files have")
+            };
+            self.update_message(&format!("WARNING! {files} unsaved changes, not quitting."));
         }
     }
 
@@ -296,40 +967,166 @@ impl Editor {
 
     // endregion
 
+    // region copy command handling
+
+    /// 复制选中的文本。
+    ///
+    /// 编辑器无法直接访问系统剪贴板，启用 `osc52_clipboard` 时通过 OSC 52 序列交给终端写入剪贴板。
+    fn handle_copy_command(&mut self) {
+        let Some(text) = self.view.selected_text() else {
+            self.update_message("Nothing selected.");
+            return;
+        };
+        if !self.config.osc52_clipboard {
+            self.update_message("ERR: No clipboard available (enable osc52_clipboard in the config).");
+            return;
+        }
+        let count = text.chars().count();
+        match Terminal::set_clipboard(&text) {
+            Ok(false) => self.update_message(&format!("Copied {count} characters.")),
+            Ok(true) => self.update_message("Selection is too large, copied only the beginning."),
+            Err(_) => self.update_message("ERR: Could not copy the selection."),
+        }
+    }
+
+    // endregion
+
     // region save command & prompt handling
 
     /// 处理文件保存
     fn handle_save_command(&mut self) {
-        if self.view.is_file_loaded() {
+        if self.view.is_read_only() {
+            self.update_message("ERR: Buffer is read-only, saving is disabled.");
+        } else if self.view.is_decoded_lossily() {
+            // 解码有损时保存会破坏原文件中无法解码的字节
+            self.update_message("ERR: File was not decoded cleanly, refusing to overwrite it.");
+        } else if self.view.is_file_loaded() {
             self.save(None);
         } else {
             self.set_prompt(PromptType::Save);
         }
     }
 
+    /// 启用了自动保存时，如果已修改的文件在设定的时间内没有任何操作，则自动保存。
+    ///
+    /// 未命名的缓冲区、解码有损的文件和只读的缓冲区不会自动保存。
+    fn auto_save_if_idle(&mut self) {
+        let delay = Duration::from_secs(u64::try_from(self.config.auto_save_delay).unwrap_or(u64::MAX));
+        let is_idle = self
+            .last_activity
+            .is_some_and(|last_activity| last_activity.elapsed() >= delay);
+        if !self.config.auto_save
+            || !is_idle
+            || !self.view.get_status().is_modified
+            || !self.view.is_file_loaded()
+            || self.view.is_decoded_lossily()
+            || self.view.is_read_only()
+        {
+            return;
+        }
+        // 保存失败时同样等待下一个无操作周期再重试，避免反复提示
+        self.last_activity = Some(Instant::now());
+        if self.view.save().is_ok() {
+            self.update_message("File auto-saved.");
+        } else {
+            self.update_message("ERR: Auto-save failed!");
+        }
+    }
+
     /// 处理保存时的命令
     fn process_command_during_save(&mut self, command: Command) {
+        if !matches!(command, Edit(Indent)) {
+            self.reset_path_completion("Save as");
+        }
         match command {
-            // 忽略无关的操作
-            System(Quit | Resize(_) | Search | Save) | Move(_) => {}
             // 丢弃保存操作
             System(Dismiss) => {
                 self.set_prompt(PromptType::None);
+                self.quit_after_save = false;
                 self.update_message("Save aborted.");
             }
             // 按enter确认保存
             Edit(InsertNewline) => {
-                let file_name = self.command_bar.value();
-                self.save(Some(&file_name));
+                let file_name = expand_tilde(&self.command_bar.value());
                 self.set_prompt(PromptType::None);
+                self.save(Some(&file_name));
             }
+            // 按Tab补全文件路径
+            Edit(Indent) => self.complete_path("Save as"),
             // 命令栏输入
             Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 补全保存或打开文件提示中的文件路径，`label` 为提示的名称。
+    ///
+    /// 先补全到所有候选项的最长公共前缀；无法继续补全时，再次按Tab依次选择各个候选项，
+    /// 并在提示中显示当前候选项的序号。没有候选项时不做任何操作。
+    fn complete_path(&mut self, label: &str) {
+        if self.path_completion.is_none() {
+            let input = self.command_bar.value();
+            let completion = PathCompletion::new(&input);
+            if completion.is_empty() {
+                return;
+            }
+            let common = completion.common_completion();
+            if common != input || completion.len() == 1 {
+                self.command_bar.set_value(&common);
+                return;
+            }
+            self.path_completion = Some(completion);
+        }
+        let Some(completion) = self.path_completion.as_mut() else {
+            return;
+        };
+        let (value, index) = completion.cycle();
+        let prompt = format!("{label} [{}/{}]: ", index.saturating_add(1), completion.len());
+        self.command_bar.set_value(&value);
+        self.command_bar.set_prompt(&prompt);
+    }
+
+    /// 结束路径补全的循环选择，恢复名称为 `label` 的提示
+    fn reset_path_completion(&mut self, label: &str) {
+        if self.path_completion.take().is_some() {
+            self.command_bar.set_prompt(&format!("{label}: "));
+        }
+    }
+
+    /// 处理确认覆盖已有文件时的命令
+    fn process_command_during_confirm_overwrite(&mut self, command: &Command) {
+        match command {
+            Edit(Insert('y' | 'Y')) => {
+                self.set_prompt(PromptType::None);
+                let file_name = mem::take(&mut self.overwrite_target);
+                self.write_file(Some(&file_name));
+            }
+            // 不覆盖时回到保存提示，以便输入其他文件名
+            System(Dismiss) | Edit(Insert('n' | 'N')) => {
+                self.set_prompt(PromptType::Save);
+                let file_name = mem::take(&mut self.overwrite_target);
+                self.command_bar.set_value(&file_name);
+            }
+            // 忽略无关的操作
+            System(_) | Move(_) | Select(_) | Edit(_) => {}
         }
     }
 
-    /// 文件保存
+    /// 文件保存。另存为的文件已经存在时，先确认是否覆盖
     fn save(&mut self, file_name: Option<&str>) {
+        if let Some(name) = file_name.filter(|name| View::file_exists(name)) {
+            self.overwrite_target = name.to_string();
+            self.set_prompt(PromptType::ConfirmOverwrite);
+            return;
+        }
+        self.write_file(file_name);
+    }
+
+    /// 将缓冲区内容写入文件，并在消息栏报告结果
+    fn write_file(&mut self, file_name: Option<&str>) {
         let result = if let Some(name) = file_name {
             self.view.save_as(name)
         } else {
@@ -337,88 +1134,1116 @@ impl Editor {
         };
         if result.is_ok() {
             self.update_message("File saved successfully.");
+            if mem::take(&mut self.quit_after_save) {
+                self.quit_if_others_saved();
+            }
         } else {
+            self.quit_after_save = false;
             self.update_message("Error writing file!");
         }
     }
 
     // endregion
 
-    // region search command & prompt handling
-    
-    /// 处理搜索时的命令
-    fn process_command_during_search(&mut self, command: Command) {
+    // region buffer handling
+
+    /// 处理输入要打开的文件时的命令，`in_new_buffer` 决定在新的缓冲区还是当前缓冲区中打开
+    fn process_command_during_open_file(&mut self, command: Command, in_new_buffer: bool) {
+        let label = if in_new_buffer { "Open in new buffer" } else { "Open file" };
+        if !matches!(command, Edit(Indent)) {
+            self.reset_path_completion(label);
+        }
         match command {
-            // 关闭搜索,回到搜索前的文本位置
-            System(Dismiss) => {
-                self.set_prompt(PromptType::None);
-                self.view.dismiss_search();
-            }
-            // 按Enter时，调用exit_search保留缓冲区中的当前位置。
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            // 按Enter打开文件。在当前缓冲区中打开时，有未保存的修改就先确认
             Edit(InsertNewline) => {
+                let file_name = expand_tilde(&self.command_bar.value());
                 self.set_prompt(PromptType::None);
-                self.view.exit_search();
-            }
-            // 在命令行输入要搜索的内容,调用搜索
-            Edit(edit_command) => {
-                self.command_bar.handle_edit_command(edit_command);
-                let query = self.command_bar.value();
-                self.view.search(&query);
+                if file_name.is_empty() {
+                    return;
+                }
+                if in_new_buffer {
+                    self.open_file(&file_name);
+                } else {
+                    self.request_open(file_name);
+                }
             }
-            // 在搜索状态上下左右进行切换已识别的搜索内容
-            Move(Right | Down) => self.view.search_next(),
-            Move(Up | Left) => self.view.search_prev(),
+            // 按Tab补全文件路径
+            Edit(Indent) => self.complete_path(label),
+            // 命令栏输入
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
             // 忽略无关的操作
-            System(Quit | Resize(_) | Search | Save) | Move(_) => {}
+            System(_) | Select(_) => {}
         }
     }
 
-    // endregion
-
-    // region message & command bar
-    
-    /// 设置消息栏信息
-    fn update_message(&mut self, new_message: &str) {
-        self.message_bar.update_message(new_message);
+    /// 在当前缓冲区中打开文件，有未保存的修改时先确认
+    fn request_open(&mut self, file_name: String) {
+        if self.view.get_status().is_modified {
+            self.pending_open = file_name;
+            self.set_prompt(PromptType::ConfirmOpen);
+        } else {
+            self.open_in_current_buffer(&file_name);
+        }
     }
 
-    // endregion
-
-
-    //region prompt handling
-
-    /// 获取是否有提示
-    fn in_prompt(&self) -> bool {
-        !self.prompt_type.is_none()
+    /// 处理选择最近打开的文件时的命令，按数字键在当前缓冲区中打开对应的文件
+    fn process_command_during_recent_files(&mut self, command: &Command) {
+        match command {
+            Edit(Insert(digit @ '1'..='9')) => {
+                let file_name = digit
+                    .to_digit(10)
+                    .and_then(|number| usize::try_from(number).ok())
+                    .and_then(|number| self.state.recent_files().get(number.saturating_sub(1)).copied())
+                    .and_then(Path::to_str)
+                    .map(String::from);
+                if let Some(file_name) = file_name {
+                    self.set_prompt(PromptType::None);
+                    self.request_open(file_name);
+                }
+            }
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            // 忽略无关的操作
+            System(_) | Move(_) | Select(_) | Edit(_) => {}
+        }
     }
 
-    /// 设置提示
-    fn set_prompt(&mut self, prompt_type: PromptType) {
-        match prompt_type {
-            //确保消息栏能在下一个循环周期重绘
-            PromptType::None => self.message_bar.set_needs_redraw(true),
-            // 保存提示
-            PromptType::Save => self.command_bar.set_prompt("Save as: "),
-            // 搜索提示
-            PromptType::Search => {
-                // 进入搜索
-                self.view.enter_search();
-                self.command_bar.set_prompt("Search (Esc to cancel, Arrows to navigate): ");
+    /// 构建列出最近打开的文件的提示，只列出能在终端宽度内放下的文件
+    fn recent_files_prompt(&self) -> String {
+        let files = self.state.recent_files();
+        let suffix = format!("(1-{}, Esc to cancel): ", files.len());
+        let mut prompt = String::from("Recent: ");
+        for (index, file) in files.iter().enumerate() {
+            let name = file
+                .file_name()
+                .map_or_else(|| file.to_string_lossy(), |name| name.to_string_lossy());
+            let entry = format!("{} {name}  ", index.saturating_add(1));
+            if prompt.len().saturating_add(entry.len()).saturating_add(suffix.len()) > self.terminal_size.width {
+                break;
             }
+            prompt.push_str(&entry);
         }
-        self.command_bar.clear_value();
-        self.prompt_type = prompt_type;
+        prompt.push_str(&suffix);
+        prompt
     }
-    // end region
-
-}
 
-impl Drop for Editor {
-    /// 在 `Editor` 被销毁时调用，确保终端恢复正常状态。
-    fn drop(&mut self) {
-        let _ = Terminal::terminate();
-        if self.should_quit {
-            let _ = Terminal::print("Goodbye.\r\n");
+    /// 处理浏览目录的命令，从当前工作目录开始浏览
+    fn handle_browse_command(&mut self) {
+        let result = env::current_dir().and_then(|dir| DirectoryBrowser::open(&dir));
+        match result {
+            Ok(browser) => {
+                self.browser = Some(browser);
+                self.set_prompt(PromptType::Browse);
+            }
+            Err(err) => self.update_message(&format!("ERR: Could not read directory: {}", Self::describe_error(&err))),
         }
     }
-}
\ No newline at end of file
+
+    /// 处理浏览目录时的命令。
+    ///
+    /// 上下方向键选择，输入字符跳到以该字符开头的项，Enter 进入目录或在当前缓冲区中打开文件，
+    /// 退格返回上一级目录，Esc 取消。
+    fn process_command_during_browse(&mut self, command: &Command) {
+        let Some(browser) = self.browser.as_mut() else {
+            self.set_prompt(PromptType::None);
+            return;
+        };
+        let result = match command {
+            Move(Up) => {
+                browser.select_next(false);
+                Ok(None)
+            }
+            Move(Down) => {
+                browser.select_next(true);
+                Ok(None)
+            }
+            Edit(Insert(character)) => {
+                browser.select_by_initial(*character);
+                Ok(None)
+            }
+            Edit(DeleteBackward) => browser.go_up().map(|()| None),
+            Edit(InsertNewline) => browser.activate(),
+            System(Dismiss) => {
+                self.browser = None;
+                self.set_prompt(PromptType::None);
+                return;
+            }
+            // 忽略无关的操作
+            System(_) | Move(_) | Select(_) | Edit(_) => return,
+        };
+        match result {
+            Ok(Some(path)) => {
+                self.browser = None;
+                self.set_prompt(PromptType::None);
+                // 当前工作目录中的文件使用相对路径，与在命令行中打开时一致
+                let path = env::current_dir()
+                    .ok()
+                    .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+                    .unwrap_or(path);
+                self.request_open(path.to_string_lossy().into_owned());
+            }
+            Ok(None) => self.update_browse_prompt(),
+            // 提示期间消息栏被命令栏覆盖，所以错误信息显示在提示符中
+            Err(err) => {
+                let reason = Self::describe_error(&err);
+                if let Some(browser) = &self.browser {
+                    let prompt = format!("Cannot open {}: {reason} (Esc to cancel)", browser.selected_name());
+                    self.command_bar.set_prompt(&prompt);
+                }
+            }
+        }
+    }
+
+    /// 显示正在浏览的目录和选中的项。目录路径太长时只显示末尾的部分
+    fn update_browse_prompt(&mut self) {
+        let Some(browser) = &self.browser else {
+            return;
+        };
+        let (current, total) = browser.position();
+        let selected = browser.selected_name();
+        let suffix = format!(" [{current}/{total}] {selected}");
+        let dir = browser.dir().to_string_lossy();
+        let available = self
+            .terminal_size
+            .width
+            .saturating_sub("Browse ".len())
+            .saturating_sub(suffix.chars().count());
+        let dir_len = dir.chars().count();
+        let dir = if dir_len > available {
+            let kept: String = dir.chars().skip(dir_len.saturating_sub(available.saturating_sub(1))).collect();
+            format!("…{kept}")
+        } else {
+            dir.into_owned()
+        };
+        self.command_bar.set_prompt(&format!("Browse {dir}{suffix}"));
+    }
+
+    /// 处理查找文件时的命令。
+    ///
+    /// 输入内容用于模糊匹配文件路径，上下方向键选择匹配结果，Enter 在当前缓冲区中打开选中的文件，Esc 取消。
+    fn process_command_during_find_file(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(InsertNewline) => {
+                let selected = self.finder_list.selected().map(String::from);
+                self.set_prompt(PromptType::None);
+                if let Some(file_name) = selected {
+                    self.request_open(file_name);
+                }
+            }
+            Move(Up) => self.finder_list.select_next(false),
+            Move(Down) => self.finder_list.select_next(true),
+            // 命令栏输入，输入内容改变后重新匹配
+            Edit(edit_command) => {
+                self.command_bar.handle_edit_command(edit_command);
+                self.update_finder_results();
+            }
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 按命令栏中的输入内容重新匹配文件，更新结果列表和提示中的匹配数
+    fn update_finder_results(&mut self) {
+        let Some(finder) = &self.finder else {
+            return;
+        };
+        let query = self.command_bar.value();
+        let results = finder
+            .matches(&query, self.finder_list.capacity())
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let more = if finder.is_truncated() { "+" } else { "" };
+        self.finder_list.set_items(results);
+        self.command_bar
+            .set_prompt(&format!("Find file ({}{more} files, Esc to cancel): ", finder.len()));
+    }
+
+    /// 处理确认丢弃修改并打开其他文件时的命令
+    fn process_command_during_confirm_open(&mut self, command: &Command) {
+        match command {
+            Edit(Insert('y' | 'Y')) => {
+                self.set_prompt(PromptType::None);
+                let file_name = mem::take(&mut self.pending_open);
+                self.open_in_current_buffer(&file_name);
+            }
+            System(Dismiss) | Edit(Insert('n' | 'N')) => {
+                self.set_prompt(PromptType::None);
+                self.pending_open.clear();
+                self.update_message("Open cancelled, unsaved changes kept.");
+            }
+            // 忽略无关的操作
+            System(_) | Move(_) | Select(_) | Edit(_) => {}
+        }
+    }
+
+    /// 在当前缓冲区中打开文件，替换原来的内容。
+    ///
+    /// 文件加载到一个新的视图中，光标、滚动位置和搜索状态都从头开始；
+    /// 打开失败时保留原来的缓冲区。
+    fn open_in_current_buffer(&mut self, file_name: &str) {
+        let mut view = self.new_view();
+        mem::swap(&mut self.view, &mut view);
+        if !self.load_file(file_name) {
+            self.view = view;
+        }
+        self.handle_resize_command(self.terminal_size);
+    }
+
+    /// 处理左右分屏的命令。
+    ///
+    /// 分屏时另一个窗格显示下一个缓冲区，所以至少需要打开两个缓冲区；
+    /// 终端太窄、窗格宽度小于 `MIN_PANE_WIDTH` 时拒绝分屏。
+    fn handle_toggle_split_command(&mut self) {
+        if self.split_buffer.take().is_some() {
+            self.handle_resize_command(self.terminal_size);
+        } else if self.buffers.len() < 2 {
+            self.update_message("Open another file to split the window (Ctrl-A).");
+        } else if Self::pane_widths(self.terminal_size.width).1 < MIN_PANE_WIDTH {
+            self.update_message("Terminal is too narrow to split.");
+        } else {
+            self.split_buffer = self
+                .active_buffer
+                .saturating_add(1)
+                .checked_rem(self.buffers.len());
+            self.active_pane_right = false;
+            self.handle_resize_command(self.terminal_size);
+        }
+    }
+
+    /// 分屏时将输入焦点切换到另一个窗格
+    fn focus_other_pane(&mut self) {
+        let Some(other) = self.split_buffer else {
+            return;
+        };
+        self.split_buffer = Some(self.active_buffer);
+        self.active_pane_right = !self.active_pane_right;
+        self.switch_buffer(other);
+    }
+
+    /// 处理鼠标点击。分屏时点击另一个窗格会先切换焦点，点击分隔线时忽略
+    fn handle_click(&mut self, position: Position) {
+        if self.split_buffer.is_some() {
+            let (left, _) = Self::pane_widths(self.terminal_size.width);
+            if position.col == left {
+                return;
+            }
+            if (position.col > left) != self.active_pane_right {
+                self.focus_other_pane();
+            }
+        }
+        self.view.handle_click(position);
+    }
+
+    /// 分屏时另一个窗格的视图
+    fn split_view(&self) -> Option<&View> {
+        self.split_buffer.and_then(|index| self.buffers.get(index))
+    }
+
+    /// 左右分屏时两个窗格的宽度，中间留出一列分隔线。宽度为奇数时左侧窗格多一列
+    fn pane_widths(width: usize) -> (usize, usize) {
+        let available = width.saturating_sub(1);
+        let left = available.div_ceil(2);
+        (left, available.saturating_sub(left))
+    }
+
+    /// 在两个窗格之间绘制分隔线
+    fn draw_split_separator(&self) {
+        let (col, _) = Self::pane_widths(self.terminal_size.width);
+        for row in 0..self.view_height() {
+            let _ = Terminal::print_at(Position { row, col }, 1, "│");
+        }
+    }
+
+    /// 在新的缓冲区中打开文件并切换过去。
+    ///
+    /// 当前缓冲区是未修改过的未命名缓冲区时，打开成功后关闭它；
+    /// 打开失败时回到原来的缓冲区。
+    fn open_file(&mut self, file_name: &str) {
+        let previous = self.active_buffer;
+        let replace_previous = self.is_replaceable_buffer();
+        let view = self.new_view();
+        self.buffers.push(view);
+        self.switch_buffer(self.buffers.len().saturating_sub(1));
+        if !self.load_file(file_name) {
+            self.switch_buffer(previous);
+            self.buffers.pop();
+            return;
+        }
+        if replace_previous {
+            self.close_replaced_buffer(previous);
+        }
+    }
+
+    /// 在新的缓冲区中打开内存中的文本并切换过去，供嵌入编辑器的宿主程序在运行前提供内容。
+    ///
+    /// 缓冲区不关联任何文件，保存时会询问文件名。当前缓冲区是未修改过的未命名缓冲区时关闭它。
+    pub fn open_text(&mut self, text: &str) {
+        let previous = self.active_buffer;
+        let replace_previous = self.is_replaceable_buffer();
+        let mut view = View::from_str(text);
+        Self::apply_view_config(&self.config, self.read_only, &mut view);
+        self.buffers.push(view);
+        self.switch_buffer(self.buffers.len().saturating_sub(1));
+        if replace_previous {
+            self.close_replaced_buffer(previous);
+        }
+    }
+
+    /// 当前缓冲区是否可以被新打开的缓冲区替换：未命名且没有修改过。
+    /// 分屏时不替换，以免另一个窗格的缓冲区序号改变
+    fn is_replaceable_buffer(&self) -> bool {
+        self.split_buffer.is_none() && !self.view.is_file_loaded() && !self.view.get_status().is_modified
+    }
+
+    /// 关闭被新打开的缓冲区替换的缓冲区，新缓冲区位于它之后
+    fn close_replaced_buffer(&mut self, previous: usize) {
+        self.buffers.remove(previous);
+        self.active_buffer = self.active_buffer.saturating_sub(1);
+    }
+
+    /// 切换到下一个或上一个缓冲区，分屏时跳过另一个窗格中显示的缓冲区
+    fn cycle_buffer(&mut self, forward: bool) {
+        let count = self.buffers.len();
+        let step = |index: usize| {
+            if forward {
+                index.saturating_add(1).checked_rem(count).unwrap_or(0)
+            } else {
+                index.checked_sub(1).unwrap_or(count.saturating_sub(1))
+            }
+        };
+        let mut index = step(self.active_buffer);
+        if Some(index) == self.split_buffer {
+            index = step(index);
+        }
+        self.switch_buffer(index);
+    }
+
+    /// 切换到指定序号的缓冲区，序号超出范围时回到第一个缓冲区。
+    ///
+    /// 每个缓冲区的光标和滚动位置都保存在各自的视图中，切换后保持不变。
+    fn switch_buffer(&mut self, index: usize) {
+        let index = if index < self.buffers.len() { index } else { 0 };
+        if index == self.active_buffer {
+            return;
+        }
+        // 先把当前视图放回原位置，再换出目标视图
+        if let Some(slot) = self.buffers.get_mut(self.active_buffer) {
+            mem::swap(&mut self.view, slot);
+        }
+        if let Some(slot) = self.buffers.get_mut(index) {
+            mem::swap(&mut self.view, slot);
+        }
+        self.active_buffer = index;
+        // 其他缓冲区的视图可能是在终端大小改变之前创建的
+        self.handle_resize_command(self.terminal_size);
+    }
+
+    // endregion
+
+    // region search command & prompt handling
+    
+    /// 处理搜索时的命令
+    fn process_command_during_search(&mut self, command: Command) {
+        match command {
+            // 关闭搜索,回到搜索前的文本位置
+            System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.view.dismiss_search();
+            }
+            // 按Enter时，调用exit_search保留缓冲区中的当前位置。
+            Edit(InsertNewline) => {
+                self.search_history.push(&self.command_bar.value());
+                self.set_prompt(PromptType::None);
+                self.view.exit_search();
+            }
+            // Alt+上下方向键浏览搜索历史
+            Edit(MoveLineUp) => {
+                if let Some(query) = self.search_history.previous().map(String::from) {
+                    self.command_bar.set_value(&query);
+                    self.view.search(&query);
+                }
+            }
+            Edit(MoveLineDown) => {
+                if let Some(query) = self.search_history.next().map(String::from) {
+                    self.command_bar.set_value(&query);
+                    self.view.search(&query);
+                }
+            }
+            // 在命令行输入要搜索的内容,调用搜索
+            Edit(edit_command) => {
+                self.command_bar.handle_edit_command(edit_command);
+                let query = self.command_bar.value();
+                self.view.search(&query);
+            }
+            // 在搜索状态上下左右进行切换已识别的搜索内容
+            Move(Right | Down) => self.view.search_next(),
+            Move(Up | Left) => self.view.search_prev(),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+        if matches!(self.prompt_type, PromptType::Search) {
+            self.update_search_prompt();
+        }
+    }
+
+    /// 更新搜索提示，输入了搜索内容时显示当前是第几个匹配项，如 `[3/17]`，
+    /// 查找下一个/上一个匹配项越过了文档边界时一并提示
+    fn update_search_prompt(&mut self) {
+        let counter = self
+            .view
+            .search_match_position()
+            .map_or_else(String::new, |(current, total)| format!(" [{current}/{total}]"));
+        let wrapped = self.search_wrap_note().map_or_else(String::new, |note| format!(" - {note}"));
+        self.command_bar
+            .set_prompt(&format!("Search{counter}{wrapped} (Esc to cancel, Arrows to navigate): "));
+    }
+
+    /// 查找下一个/上一个匹配项越过了文档边界时的提示。
+    ///
+    /// 提示期间消息栏被命令栏覆盖，所以提示显示在提示符中，只在确实越过了边界时出现
+    fn search_wrap_note(&self) -> Option<&'static str> {
+        match self.view.search_wrapped()? {
+            SearchDirection::Forward => Some("wrapped to top"),
+            SearchDirection::Backward => Some("wrapped to bottom"),
+        }
+    }
+
+    // endregion
+
+    // region replace command & prompt handling
+
+    /// 处理替换第一阶段(输入查找内容)时的命令
+    fn process_command_during_replace(&mut self, command: Command) {
+        match command {
+            // 取消替换,回到替换前的文本位置
+            System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.view.dismiss_search();
+                self.update_message("Replace aborted.");
+            }
+            // 按Enter确认查找内容,进入输入替换内容的阶段
+            Edit(InsertNewline) => self.set_prompt(PromptType::ReplaceWith),
+            // 与搜索一样,输入时就高亮显示匹配项
+            Edit(edit_command) => {
+                self.command_bar.handle_edit_command(edit_command);
+                let query = self.command_bar.value();
+                self.view.search(&query);
+            }
+            Move(Right | Down) => self.view.search_next(),
+            Move(Up | Left) => self.view.search_prev(),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 处理替换第二阶段(输入替换内容)时的命令
+    fn process_command_during_replace_with(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => {
+                self.set_prompt(PromptType::None);
+                self.view.dismiss_search();
+                self.update_message("Replace aborted.");
+            }
+            // 按Enter确认替换内容,开始逐个确认匹配项
+            Edit(InsertNewline) => {
+                self.replacement = self.command_bar.value();
+                self.replaced_count = 0;
+                if self.view.is_on_search_match() {
+                    self.set_prompt(PromptType::ReplaceConfirm);
+                } else {
+                    self.set_prompt(PromptType::None);
+                    self.view.exit_search();
+                    self.update_message("No matches found.");
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 处理逐个确认替换时的命令
+    ///
+    /// - `y`: 替换当前匹配项并跳到下一个
+    /// - `n`: 跳过当前匹配项
+    /// - `a`: 替换剩余的全部匹配项
+    /// - `Esc` 或 `q`: 结束替换
+    fn process_command_during_replace_confirm(&mut self, command: &Command) {
+        match command {
+            Edit(Insert('y' | 'Y')) => {
+                if self.view.replace_next(&self.replacement) {
+                    self.replaced_count = self.replaced_count.saturating_add(1);
+                }
+                if !self.view.is_on_search_match() {
+                    self.finish_replace();
+                }
+            }
+            Edit(Insert('n' | 'N')) | Move(Right | Down) => self.view.search_next(),
+            Move(Up | Left) => self.view.search_prev(),
+            Edit(Insert('a' | 'A')) => {
+                let count = self.view.replace_all(&self.replacement);
+                self.replaced_count = self.replaced_count.saturating_add(count);
+                self.finish_replace();
+            }
+            System(Dismiss) | Edit(Insert('q' | 'Q')) => self.finish_replace(),
+            // 忽略无关的操作
+            System(_) | Move(_) | Select(_) | Edit(_) => {}
+        }
+        if self.prompt_type == PromptType::ReplaceConfirm {
+            self.update_replace_confirm_prompt();
+        }
+    }
+
+    /// 更新确认替换的提示，跳转到下一个/上一个匹配项时越过了文档边界则一并提示
+    fn update_replace_confirm_prompt(&mut self) {
+        let wrapped = self
+            .search_wrap_note()
+            .map_or_else(String::new, |note| format!("Search {note}. "));
+        self.command_bar
+            .set_prompt(&format!("{wrapped}Replace? (y)es (n)o (a)ll (q)uit: "));
+    }
+
+    /// 结束替换,并在消息栏报告替换的次数
+    fn finish_replace(&mut self) {
+        self.set_prompt(PromptType::None);
+        self.view.exit_search();
+        self.update_message(&format!("Replaced {} occurrence(s).", self.replaced_count));
+    }
+
+    // endregion
+
+    // region modal editing
+
+    /// 按当前模式转换无提示时的命令。
+    ///
+    /// 插入模式中按 Esc 回到普通模式，其他命令保持不变。普通模式中字符键被解释为命令，
+    /// 转换为已有的移动和编辑命令；没有对应命令的字符键被忽略，其他按键(如方向键、Ctrl 组合键)保持不变。
+    ///
+    /// # 返回值
+    /// 命令已经在这里处理完，或者应当被忽略时返回 `None`。
+    fn translate_modal_command(&mut self, command: Command) -> Option<Command> {
+        if self.mode == Mode::Insert {
+            if matches!(command, System(Dismiss)) {
+                self.mode = Mode::Normal;
+                return None;
+            }
+            return Some(command);
+        }
+        let pending_delete = mem::take(&mut self.pending_delete);
+        match command {
+            Edit(Insert(character)) => match character {
+                'h' => Some(Move(Left)),
+                'j' => Some(Move(Down)),
+                'k' => Some(Move(Up)),
+                'l' => Some(Move(Right)),
+                'i' => {
+                    self.mode = Mode::Insert;
+                    None
+                }
+                // 在光标之后插入
+                'a' => {
+                    self.mode = Mode::Insert;
+                    Some(Move(Right))
+                }
+                'x' => Some(Edit(Delete)),
+                'd' if pending_delete => Some(Edit(DeleteLine)),
+                'd' => {
+                    self.pending_delete = true;
+                    None
+                }
+                ':' => {
+                    self.set_prompt(PromptType::Command);
+                    None
+                }
+                _ => None,
+            },
+            Edit(InsertNewline) => Some(Move(Down)),
+            Edit(DeleteBackward) => Some(Move(Left)),
+            Edit(Paste(_)) => None,
+            _ => Some(command),
+        }
+    }
+
+    /// 处理命令提示时的命令
+    fn process_command_during_command(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(InsertNewline) => {
+                let value = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                self.execute_command_line(value.trim());
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 执行命令提示中输入的命令：`w` 保存，`q` 退出，`q!` 放弃修改并退出，
+    /// `wq` 或 `x` 保存后退出，数字跳转到指定行。
+    fn execute_command_line(&mut self, command_line: &str) {
+        match command_line {
+            "" => {}
+            "w" => self.handle_save_command(),
+            "q" => self.quit_if_saved(),
+            "q!" => self.should_quit = true,
+            "wq" | "x" => self.handle_save_quit_command(),
+            _ => {
+                if let Some(location) = Self::parse_line_and_column(command_line) {
+                    self.view.go_to(location);
+                } else {
+                    self.update_message(&format!("ERR: Unknown command: {command_line}"));
+                }
+            }
+        }
+    }
+
+    /// 所有缓冲区都没有未保存的修改时退出，否则提示使用 `q!`
+    fn quit_if_saved(&mut self) {
+        if self.modified_buffer_count() == 0 {
+            self.should_quit = true;
+        } else {
+            self.update_message("ERR: Unsaved changes, use :q! to quit without saving.");
+        }
+    }
+
+    // endregion
+
+    // region line reordering
+
+    /// 在消息栏中报告重新排列的行数，选区少于两行时提示先选中多行
+    fn report_reordered_lines(&mut self, count: usize, action: &str) {
+        if count > 1 {
+            self.update_message(&format!("{action} {count} lines."));
+        } else {
+            self.update_message("Select at least two lines first.");
+        }
+    }
+
+    // endregion
+
+    // region multiple cursors handling
+
+    /// 添加光标后在消息栏中显示光标数，没有添加时显示原因
+    fn report_cursor_count(&mut self, added: bool, reason: &str) {
+        if added {
+            let count = self.view.cursor_count();
+            self.update_message(&format!("{count} cursors (Esc to collapse)"));
+        } else {
+            self.update_message(reason);
+        }
+    }
+
+    // endregion
+
+    // region macro command & prompt handling
+
+    /// 开始录制宏，之后的命令都会被记录，直到停止录制
+    fn handle_start_record_command(&mut self) {
+        if self.macro_recorder.start() {
+            self.update_message("Recording macro, press F8 to stop.");
+        } else {
+            self.update_message("Already recording a macro.");
+        }
+    }
+
+    /// 停止录制宏
+    fn handle_stop_record_command(&mut self) {
+        match self.macro_recorder.stop() {
+            Some(0) => self.update_message("Macro is empty."),
+            Some(count) => self.update_message(&format!("Recorded macro with {count} commands.")),
+            None => self.update_message("Not recording a macro."),
+        }
+    }
+
+    /// 回放宏：先输入重复次数
+    fn handle_play_macro_command(&mut self) {
+        if self.macro_recorder.is_recording() {
+            // 回放的命令会被再次录制，宏可能回放自身
+            self.update_message("Cannot play a macro while recording.");
+        } else if self.macro_recorder.has_macro() {
+            self.set_prompt(PromptType::PlayMacro);
+        } else {
+            self.update_message("No macro recorded.");
+        }
+    }
+
+    /// 处理输入回放次数时的命令
+    fn process_command_during_play_macro(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            // 按Enter开始回放，没有输入时回放一次。输入无效时提示错误并保持提示打开
+            Edit(InsertNewline) => {
+                let value = self.command_bar.value();
+                let value = value.trim();
+                let times = if value.is_empty() {
+                    Some(1)
+                } else {
+                    value.parse::<usize>().ok().filter(|times| *times > 0)
+                };
+                if let Some(times) = times {
+                    self.set_prompt(PromptType::None);
+                    self.play_macro(times);
+                } else {
+                    self.command_bar
+                        .set_prompt("Invalid count! Play macro how many times (Enter = once): ");
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 将宏回放 `times` 次。命令按输入时的方式逐个处理，编辑器退出时停止
+    fn play_macro(&mut self, times: usize) {
+        let Some(commands) = self.macro_recorder.start_playback() else {
+            return;
+        };
+        'replay: for _ in 0..times {
+            for command in &commands {
+                if self.should_quit {
+                    break 'replay;
+                }
+                self.process_command(command.clone());
+            }
+        }
+        self.macro_recorder.finish_playback();
+    }
+
+    // endregion
+
+    // region go to line command & prompt handling
+
+    /// 处理跳转到指定行时的命令
+    fn process_command_during_go_to_line(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            // 按Enter确认跳转。输入无效时提示错误并保持提示打开
+            Edit(InsertNewline) => {
+                let value = self.command_bar.value();
+                if let Some(location) = Self::parse_line_and_column(&value) {
+                    self.set_prompt(PromptType::None);
+                    self.view.go_to(location);
+                } else {
+                    // 提示期间消息栏被命令栏覆盖，所以错误信息显示在提示符中
+                    self.command_bar
+                        .set_prompt("Invalid line number! Go to line (line[:col]): ");
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 处理跳转到下一个包含输入内容的行时的命令
+    fn process_command_during_jump_to_matching_line(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            // 按Enter确认跳转，没有匹配的行时光标不动
+            Edit(InsertNewline) => {
+                let query = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                match self.view.go_to_next_line_containing(&query) {
+                    Some(true) => self.update_message("Jump wrapped to top."),
+                    Some(false) => {}
+                    None => self.update_message("No matching line found."),
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    /// 解析 `行`、`:行` 或 `行:列` 格式的位置(从 1 开始计数)，返回从 0 开始的文本位置。
+    ///
+    /// 无法解析时返回 `None`。
+    fn parse_line_and_column(value: &str) -> Option<Location> {
+        let value = value.trim();
+        let value = value.strip_prefix(':').unwrap_or(value);
+        let (line, col) = match value.split_once(':') {
+            Some((line, col)) => (line, Some(col)),
+            None => (value, None),
+        };
+        let line = line.trim().parse::<usize>().ok()?;
+        let col = match col {
+            Some(col) => col.trim().parse::<usize>().ok()?,
+            None => 1,
+        };
+        Some(Location {
+            grapheme_index: col.saturating_sub(1),
+            line_index: line.saturating_sub(1),
+        })
+    }
+
+    /// 拆分命令行参数末尾的 `:行` 或 `:行:列` 后缀，返回文件名和从 0 开始的文本位置。
+    ///
+    /// 只有末尾是数字的后缀才会被拆分；参数本身就是已存在的文件时不拆分，
+    /// 以便打开名称中带有冒号的文件。没有这样的后缀时返回 `None`。
+    fn split_file_location(arg: &str) -> Option<(&str, Location)> {
+        if Path::new(arg).exists() {
+            return None;
+        }
+        let is_number = |value: &str| !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit());
+        let (rest, last) = arg.rsplit_once(':').filter(|(_, last)| is_number(last))?;
+        let (file_name, line, col) = match rest.rsplit_once(':') {
+            Some((file_name, line)) if is_number(line) => (file_name, line, Some(last)),
+            _ => (rest, last, None),
+        };
+        if file_name.is_empty() {
+            return None;
+        }
+        let line = line.parse::<usize>().ok()?;
+        let col = col.map_or(Some(1), |col| col.parse::<usize>().ok())?;
+        Some((
+            file_name,
+            Location {
+                grapheme_index: col.saturating_sub(1),
+                line_index: line.saturating_sub(1),
+            },
+        ))
+    }
+
+    // endregion
+
+    // region split line command & prompt handling
+
+    /// 处理拆分当前行时的命令
+    fn process_command_during_split_line(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            // 按Enter确认分隔符并拆分
+            Edit(InsertNewline) => {
+                let delimiter = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                let count = self.view.split_current_line(&delimiter);
+                if count > 1 {
+                    self.update_message(&format!("Split line into {count} lines."));
+                } else {
+                    self.update_message("Delimiter not found.");
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    // endregion
+
+    // region join lines command & prompt handling
+
+    /// 处理合并选中行时的命令
+    fn process_command_during_join_lines(&mut self, command: Command) {
+        match command {
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            // 按Enter确认分隔符并合并。分隔符可以为空
+            Edit(InsertNewline) => {
+                let separator = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                let count = self.view.join_selected_lines(&separator);
+                if count > 1 {
+                    self.update_message(&format!("Joined {count} lines."));
+                } else {
+                    self.update_message("Select at least two lines to join.");
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+            // 在输入内容中移动光标
+            Move(move_command) => self.command_bar.handle_move_command(move_command),
+            // 忽略无关的操作
+            System(_) | Select(_) => {}
+        }
+    }
+
+    // endregion
+
+    // region message & command bar
+    
+    /// 设置消息栏信息
+    fn update_message(&mut self, new_message: &str) {
+        self.message_bar.update_message(new_message);
+    }
+
+    // endregion
+
+
+    //region prompt handling
+
+    /// 获取是否有提示
+    fn in_prompt(&self) -> bool {
+        !self.prompt_type.is_none()
+    }
+
+    /// 设置提示
+    fn set_prompt(&mut self, prompt_type: PromptType) {
+        // 离开查找文件时重绘被结果列表覆盖的view
+        if self.prompt_type == PromptType::FindFile {
+            self.view.set_needs_redraw(true);
+            if let Some(index) = self.split_buffer {
+                if let Some(view) = self.buffers.get_mut(index) {
+                    view.set_needs_redraw(true);
+                }
+            }
+        }
+        match prompt_type {
+            //确保消息栏能在下一个循环周期重绘
+            PromptType::None => self.message_bar.set_needs_redraw(true),
+            // 保存提示
+            PromptType::Save => self.command_bar.set_prompt("Save as: "),
+            PromptType::Open => self.command_bar.set_prompt("Open file: "),
+            PromptType::ConfirmOpen => self
+                .command_bar
+                .set_prompt("Buffer has unsaved changes. Discard them and open the file? (y/n): "),
+            PromptType::OpenFile => self.command_bar.set_prompt("Open in new buffer: "),
+            PromptType::RecentFiles => {
+                let prompt = self.recent_files_prompt();
+                self.command_bar.set_prompt(&prompt);
+            }
+            PromptType::Browse => self.update_browse_prompt(),
+            // 第一次查找时遍历当前工作目录，结果在 `clear_value` 之后按空的输入内容更新
+            PromptType::FindFile => {
+                if self.finder.is_none() {
+                    let root = env::current_dir().unwrap_or_default();
+                    self.finder = Some(Finder::scan(&root));
+                }
+            }
+            // 搜索提示
+            PromptType::Search => {
+                // 进入搜索
+                self.view.enter_search();
+                self.search_history.reset();
+                self.update_search_prompt();
+            }
+            // 替换提示,查找阶段复用搜索功能
+            PromptType::Replace => {
+                self.view.enter_search();
+                self.command_bar.set_prompt("Replace (Esc to cancel): ");
+            }
+            PromptType::ReplaceWith => self.command_bar.set_prompt("Replace with: "),
+            PromptType::GoToLine => self.command_bar.set_prompt("Go to line (line[:col]): "),
+            PromptType::JumpToMatchingLine => self.command_bar.set_prompt("Jump to next line containing: "),
+            PromptType::SplitLine => self.command_bar.set_prompt("Split line at: "),
+            PromptType::JoinLines => self.command_bar.set_prompt("Join lines with separator: "),
+            PromptType::Command => self.command_bar.set_prompt(":"),
+            PromptType::PlayMacro => self.command_bar.set_prompt("Play macro how many times (Enter = once): "),
+            PromptType::ReplaceConfirm => self.update_replace_confirm_prompt(),
+            PromptType::ConfirmReload => self
+                .command_bar
+                .set_prompt("Reload file and discard unsaved changes? (y/n): "),
+            // 有未保存的修改时提醒重新读取会丢弃它们
+            PromptType::ReloadEncoding => {
+                let prompt = if self.view.get_status().is_modified {
+                    "Reload with encoding (discards changes): "
+                } else {
+                    "Reload with encoding: "
+                };
+                self.command_bar.set_prompt(prompt);
+            }
+            PromptType::ConfirmOverwrite => self
+                .command_bar
+                .set_prompt(&format!("{} already exists. Overwrite? (y/n): ", self.overwrite_target)),
+        }
+        self.command_bar.clear_value();
+        self.prompt_type = prompt_type;
+        if self.prompt_type == PromptType::FindFile {
+            self.update_finder_results();
+        }
+    }
+    // end region
+
+}
+
+impl Drop for Editor {
+    /// 在 `Editor` 被销毁时调用，确保终端恢复正常状态。
+    fn drop(&mut self) {
+        let _ = Terminal::terminate();
+        if self.should_quit {
+            let _ = Terminal::print("Goodbye.\r\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::ManuallyDrop;
+    use command::Edit;
+
+    #[test]
+    fn files_opened_later_stay_read_only_under_dash_r() {
+        // `Editor` 销毁时会恢复终端，测试中不需要
+        let mut editor = ManuallyDrop::new(Editor::default());
+        editor.read_only = true;
+        editor.buffers.push(View::default());
+        editor.apply_config();
+        let path = env::temp_dir().join(format!("hecto-test-{}-dash-r", std::process::id()));
+        std::fs::write(&path, "text").unwrap();
+        let file_name = path.to_str().unwrap();
+        editor.open_file(file_name);
+        assert!(editor.view.is_read_only());
+        editor.open_in_current_buffer(file_name);
+        assert!(editor.view.is_read_only());
+        editor.read_only = false;
+        editor.open_file(file_name);
+        assert!(!editor.view.is_read_only());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn invalid_config_keeps_current_settings() {
+        let path = std::env::temp_dir().join(format!("hecto-test-{}-editor-config.toml", std::process::id()));
+        let mut editor = ManuallyDrop::new(Editor::default());
+        editor.config_path = Some(path.clone());
+        // 配置文件不存在时使用默认配置
+        assert!(editor.load_config().is_ok());
+        assert!(editor.config.one_based_position);
+        std::fs::write(&path, "one_based_position = false\n").unwrap();
+        assert!(editor.load_config().is_ok());
+        assert!(!editor.config.one_based_position);
+        // 解析失败时保留之前的配置
+        std::fs::write(&path, "one_based_position = true\ndefault_encoding = \n").unwrap();
+        assert!(editor.load_config().is_err());
+        assert!(!editor.config.one_based_position);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn open_text_replaces_empty_untitled_buffer() {
+        let mut editor = ManuallyDrop::new(Editor::default());
+        editor.buffers.push(View::default());
+        editor.open_text("one\ntwo");
+        assert_eq!(editor.buffers.len(), 1);
+        assert_eq!(editor.view.get_status().total_lines, 2);
+        assert!(!editor.view.get_status().is_modified);
+        // 当前缓冲区修改过时，在新的缓冲区中打开
+        editor.view.handle_edit_command(Edit::Insert('x'));
+        editor.open_text("three");
+        assert_eq!(editor.buffers.len(), 2);
+        assert_eq!(editor.view.current_line_text(), "three");
+    }
+}
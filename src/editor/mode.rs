@@ -0,0 +1,19 @@
+/// 启用模式编辑(`modal_editing`)时编辑器所处的模式
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub enum Mode {
+    // 按键作为命令：h/j/k/l 移动光标，i/a 进入插入模式，x 删除字符，dd 删除行，: 打开命令提示
+    Normal,
+    // 按键作为文本插入，按 Esc 回到普通模式
+    #[default]
+    Insert,
+}
+
+impl Mode {
+    /// 状态栏中显示的模式名称
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "NORMAL",
+            Self::Insert => "INSERT",
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::{env, fs::read_dir, path::PathBuf};
+
+/// 文件路径补全
+///
+/// 根据输入内容中最后一个 `/` 之前的目录，列出以之后的内容开头的文件和目录。
+/// 目录的候选项以 `/` 结尾。
+pub struct PathCompletion {
+    // 输入内容中的目录部分(保留用户输入的 `~`)
+    dir: String,
+    // 候选的文件名，按字母顺序排列
+    candidates: Vec<String>,
+    // 循环选择时当前选中的候选项
+    index: Option<usize>,
+}
+
+impl PathCompletion {
+    /// 列出与输入内容匹配的文件和目录。目录无法读取时没有候选项
+    pub fn new(input: &str) -> Self {
+        let (dir, prefix) = input
+            .rfind('/')
+            .map_or(("", input), |index| input.split_at(index.saturating_add(1)));
+        let search_dir = if dir.is_empty() {
+            PathBuf::from(".")
+        } else {
+            PathBuf::from(expand_tilde(dir))
+        };
+        let mut candidates: Vec<String> = read_dir(search_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter_map(|entry| {
+                        let name = entry.file_name().into_string().ok()?;
+                        // 除非明确输入了 `.`，否则不补全隐藏文件
+                        if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                            return None;
+                        }
+                        Some(if entry.path().is_dir() { format!("{name}/") } else { name })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        candidates.sort();
+        Self {
+            dir: dir.to_string(),
+            candidates,
+            index: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// 获取所有候选项的最长公共前缀补全后的内容，只有一个候选项时就是该候选项
+    pub fn common_completion(&self) -> String {
+        let common = self.candidates.iter().skip(1).fold(
+            self.candidates.first().map_or("", String::as_str),
+            |common, candidate| {
+                let len = common
+                    .char_indices()
+                    .zip(candidate.chars())
+                    .find(|((_, left), right)| left != right)
+                    .map_or(common.len().min(candidate.len()), |((index, _), _)| index);
+                common.get(..len).unwrap_or_default()
+            },
+        );
+        format!("{}{common}", self.dir)
+    }
+
+    /// 选中下一个候选项，返回补全后的内容和该候选项的序号(从 0 开始)
+    pub fn cycle(&mut self) -> (String, usize) {
+        let index = self
+            .index
+            .map_or(0, |index| index.saturating_add(1))
+            .checked_rem(self.candidates.len())
+            .unwrap_or(0);
+        self.index = Some(index);
+        let candidate = self.candidates.get(index).map_or("", String::as_str);
+        (format!("{}{candidate}", self.dir), index)
+    }
+}
+
+/// 将路径开头的 `~` 展开为用户的主目录，无法确定主目录时保持不变
+pub fn expand_tilde(path: &str) -> String {
+    let Some(rest) = path.strip_prefix('~') else {
+        return path.to_string();
+    };
+    if !rest.is_empty() && !rest.starts_with('/') {
+        // `~user` 形式的路径不做处理
+        return path.to_string();
+    }
+    env::var("HOME").map_or_else(|_| path.to_string(), |home| format!("{home}{rest}"))
+}
@@ -0,0 +1,92 @@
+use std::{
+    env,
+    fs::{self, read_to_string},
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+// 最多记住的最近打开的文件数，选择时按数字键 1-9
+const MAX_RECENT_FILES: usize = 9;
+
+/// 在多次运行之间保存的编辑器状态，例如最近打开的文件
+#[derive(Default)]
+pub struct State {
+    // 最近打开的文件的绝对路径，最近打开的在最前
+    recent_files: Vec<PathBuf>,
+}
+
+impl State {
+    /// 获取状态文件的默认路径。
+    ///
+    /// 优先使用 `HECTO_STATE` 环境变量指定的文件，否则使用
+    /// `$XDG_STATE_HOME/hecto/state.toml`，未设置时使用 `~/.local/state/hecto/state.toml`。
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("HECTO_STATE") {
+            return Some(PathBuf::from(path));
+        }
+        env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+            .map(|state_dir| state_dir.join("hecto").join("state.toml"))
+    }
+
+    /// 从文件中读取状态。文件不存在或内容无法解析时返回空的状态
+    pub fn load(path: &Path) -> Self {
+        let Some(table) = read_to_string(path)
+            .ok()
+            .and_then(|contents| contents.parse::<Table>().ok())
+        else {
+            return Self::default();
+        };
+        let recent_files = table
+            .get("recent_files")
+            .and_then(Value::as_array)
+            .map(|files| {
+                files
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(PathBuf::from)
+                    .take(MAX_RECENT_FILES)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { recent_files }
+    }
+
+    /// 将状态写入文件，目录不存在时先创建
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let recent_files = self
+            .recent_files
+            .iter()
+            .filter_map(|file| file.to_str())
+            .map(|file| Value::String(file.to_string()))
+            .collect();
+        let mut table = Table::new();
+        table.insert(String::from("recent_files"), Value::Array(recent_files));
+        fs::write(path, table.to_string())
+    }
+
+    /// 记录打开的文件，移到列表的最前面。已经在列表中的文件不会重复出现
+    pub fn add_recent_file(&mut self, file_name: &str) {
+        let Ok(path) = fs::canonicalize(file_name) else {
+            return;
+        };
+        self.recent_files.retain(|file| *file != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// 获取最近打开的文件中仍然存在的文件，最近打开的在最前
+    pub fn recent_files(&self) -> Vec<&Path> {
+        self.recent_files
+            .iter()
+            .map(PathBuf::as_path)
+            .filter(|file| file.is_file())
+            .collect()
+    }
+}
@@ -0,0 +1,45 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc::{channel, Sender},
+    thread,
+};
+
+use super::DocumentStatus;
+
+/// 将文档状态以单行 JSON 写入指定的文件或 FIFO，供外部工具读取编辑器的状态。
+///
+/// 写入在后台线程中进行：FIFO 没有读取方时打开会阻塞，不能让它卡住编辑器。
+/// 每次写入都会覆盖文件的内容，文件中始终只有最新的状态；积压的旧状态会被直接丢弃。
+pub struct StatusOutput {
+    sender: Sender<String>,
+    // 上一次发送的内容，状态没有变化时不重复写入
+    last: String,
+}
+
+impl StatusOutput {
+    pub fn new(path: PathBuf) -> Self {
+        let (sender, receiver) = channel::<String>();
+        thread::spawn(move || {
+            while let Ok(mut json) = receiver.recv() {
+                while let Ok(newer) = receiver.try_recv() {
+                    json = newer;
+                }
+                // 写入失败(如路径不可写)时忽略，等待下一次状态变化再试
+                let _ = fs::write(&path, json);
+            }
+        });
+        Self {
+            sender,
+            last: String::new(),
+        }
+    }
+
+    /// 状态有变化时写入新的状态
+    pub fn write(&mut self, status: &DocumentStatus) {
+        let json = status.to_json();
+        if json != self.last && self.sender.send(format!("{json}\n")).is_ok() {
+            self.last = json;
+        }
+    }
+}
@@ -0,0 +1,129 @@
+/// 根据文件扩展名识别的语言。
+///
+/// 语法高亮器、行注释符号和状态栏中显示的语言名称都由这里的对应关系决定，
+/// 新增扩展名时只需要修改 [`Language::from_extension`]。
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Language {
+    Rust,
+    C,
+    Cpp,
+    Go,
+    Java,
+    JavaScript,
+    TypeScript,
+    Kotlin,
+    Swift,
+    Python,
+    Shell,
+    Ruby,
+    Perl,
+    Toml,
+    Yaml,
+    Config,
+    Lua,
+    Sql,
+    Haskell,
+    Markdown,
+    Json,
+    Html,
+    Css,
+    PlainText,
+}
+
+impl Language {
+    /// 根据文件扩展名识别语言，没有扩展名或无法识别时为纯文本
+    pub fn from_extension(extension: Option<&str>) -> Self {
+        match extension {
+            Some("rs") => Self::Rust,
+            Some("c" | "h") => Self::C,
+            Some("cpp" | "hpp" | "cc") => Self::Cpp,
+            Some("go") => Self::Go,
+            Some("java") => Self::Java,
+            Some("js") => Self::JavaScript,
+            Some("ts") => Self::TypeScript,
+            Some("kt") => Self::Kotlin,
+            Some("swift") => Self::Swift,
+            Some("py") => Self::Python,
+            Some("sh" | "bash" | "zsh") => Self::Shell,
+            Some("rb") => Self::Ruby,
+            Some("pl") => Self::Perl,
+            Some("toml") => Self::Toml,
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("conf") => Self::Config,
+            Some("lua") => Self::Lua,
+            Some("sql") => Self::Sql,
+            Some("hs") => Self::Haskell,
+            Some("md" | "markdown") => Self::Markdown,
+            Some("json") => Self::Json,
+            Some("html" | "htm") => Self::Html,
+            Some("css") => Self::Css,
+            _ => Self::PlainText,
+        }
+    }
+
+    /// 显示在状态栏中的语言名称
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Rust => "Rust",
+            Self::C => "C",
+            Self::Cpp => "C++",
+            Self::Go => "Go",
+            Self::Java => "Java",
+            Self::JavaScript => "JavaScript",
+            Self::TypeScript => "TypeScript",
+            Self::Kotlin => "Kotlin",
+            Self::Swift => "Swift",
+            Self::Python => "Python",
+            Self::Shell => "Shell",
+            Self::Ruby => "Ruby",
+            Self::Perl => "Perl",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+            Self::Config => "Config",
+            Self::Lua => "Lua",
+            Self::Sql => "SQL",
+            Self::Haskell => "Haskell",
+            Self::Markdown => "Markdown",
+            Self::Json => "JSON",
+            Self::Html => "HTML",
+            Self::Css => "CSS",
+            Self::PlainText => "Plain Text",
+        }
+    }
+
+    /// 行注释的符号，没有行注释的语言返回 `None`
+    pub const fn comment_token(self) -> Option<&'static str> {
+        match self {
+            Self::Rust
+            | Self::C
+            | Self::Cpp
+            | Self::Go
+            | Self::Java
+            | Self::JavaScript
+            | Self::TypeScript
+            | Self::Kotlin
+            | Self::Swift => Some("//"),
+            Self::Python | Self::Shell | Self::Ruby | Self::Perl | Self::Toml | Self::Yaml | Self::Config => Some("#"),
+            Self::Lua | Self::Sql | Self::Haskell => Some("--"),
+            Self::Markdown | Self::Json | Self::Html | Self::Css | Self::PlainText => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_map_to_names_and_comment_tokens() {
+        let language = Language::from_extension(Some("rs"));
+        assert_eq!(language.name(), "Rust");
+        assert_eq!(language.comment_token(), Some("//"));
+        assert_eq!(Language::from_extension(Some("md")).name(), "Markdown");
+        assert_eq!(Language::from_extension(Some("yml")).comment_token(), Some("#"));
+        // 无法识别的扩展名和没有扩展名的文件都是纯文本
+        assert_eq!(Language::from_extension(Some("xyz")), Language::PlainText);
+        assert_eq!(Language::from_extension(None).name(), "Plain Text");
+        assert_eq!(Language::PlainText.comment_token(), None);
+    }
+}
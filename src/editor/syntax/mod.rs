@@ -0,0 +1,37 @@
+use super::{Annotation, Line};
+
+pub use language::Language;
+pub use plainhighlighter::PlainHighlighter;
+pub use rusthighlighter::RustHighlighter;
+
+mod language;
+mod plainhighlighter;
+mod rusthighlighter;
+
+/// 语法高亮器
+///
+/// 新增一种语言时，实现这个 trait 并在 `for_extension` 中注册对应的文件扩展名即可，
+/// 不需要修改 `View`。
+pub trait Highlighter {
+    /// 对一行文本进行语法高亮，返回的注释使用该行中的字节索引
+    fn highlight(&self, line: &Line) -> Vec<Annotation>;
+}
+
+impl Default for Box<dyn Highlighter> {
+    fn default() -> Self {
+        Box::new(PlainHighlighter)
+    }
+}
+
+/// 根据文件扩展名选择语法高亮器，没有对应高亮器的语言不做高亮
+pub fn for_extension(extension: Option<&str>) -> Box<dyn Highlighter> {
+    match Language::from_extension(extension) {
+        Language::Rust => Box::new(RustHighlighter),
+        _ => Box::new(PlainHighlighter),
+    }
+}
+
+/// 根据文件扩展名获取行注释的符号，无法识别的扩展名返回 `None`
+pub fn comment_token(extension: Option<&str>) -> Option<&'static str> {
+    Language::from_extension(extension).comment_token()
+}
@@ -0,0 +1,10 @@
+use super::{Annotation, Highlighter, Line};
+
+/// 不做任何高亮的高亮器，用于无法识别的文件类型
+pub struct PlainHighlighter;
+
+impl Highlighter for PlainHighlighter {
+    fn highlight(&self, _: &Line) -> Vec<Annotation> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,124 @@
+use crate::prelude::*;
+
+use super::super::AnnotationType;
+use super::{Annotation, Highlighter, Line};
+
+// Rust 的关键字
+const RUST_KEYWORDS: [&str; 39] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Rust 语法高亮器，高亮关键字、字符串和字符字面量、数字以及注释。
+///
+/// 每一行单独处理，不保留跨行的状态：跨越多行的字符串和块注释只有第一行会被高亮，
+/// 之后的行会被当作普通代码处理。
+pub struct RustHighlighter;
+
+impl Highlighter for RustHighlighter {
+    fn highlight(&self, line: &Line) -> Vec<Annotation> {
+        highlight_rust(line)
+    }
+}
+
+/// 对一行 Rust 代码进行语法高亮
+fn highlight_rust(text: &str) -> Vec<Annotation> {
+    let mut result = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, character)) = chars.next() {
+        let rest = text.get(start..).unwrap_or_default();
+        if rest.starts_with("//") {
+            // 行注释一直到行尾
+            result.push(annotation(AnnotationType::Comment, start, text.len()));
+            break;
+        } else if rest.starts_with("/*") {
+            // 块注释到 "*/" 为止，没有结束时到行尾为止
+            let end = rest
+                .get(2..)
+                .and_then(|after| after.find("*/"))
+                .map_or(text.len(), |index| start.saturating_add(index).saturating_add(4));
+            result.push(annotation(AnnotationType::Comment, start, end));
+            while chars.next_if(|(index, _)| *index < end).is_some() {}
+        } else if character == '"' {
+            let end = string_end(text, start.saturating_add(1));
+            result.push(annotation(AnnotationType::String, start, end));
+            while chars.next_if(|(index, _)| *index < end).is_some() {}
+        } else if character == '\'' {
+            // 字符字面量；否则是生命周期，不做高亮
+            if let Some(end) = char_literal_end(text, start) {
+                result.push(annotation(AnnotationType::String, start, end));
+                while chars.next_if(|(index, _)| *index < end).is_some() {}
+            }
+        } else if character.is_ascii_digit() {
+            // 数字可以带有前缀、后缀、下划线和小数点，例如 0xFF、1_000u32、1.5e3
+            let mut end = start.saturating_add(1);
+            while let Some((index, next)) = chars.next_if(|(index, next)| {
+                next.is_ascii_alphanumeric()
+                    || *next == '_'
+                    || (*next == '.'
+                        && text
+                            .get(index.saturating_add(1)..)
+                            .and_then(|after| after.chars().next())
+                            .is_some_and(|after| after.is_ascii_digit()))
+            }) {
+                end = index.saturating_add(next.len_utf8());
+            }
+            result.push(annotation(AnnotationType::Number, start, end));
+        } else if character.is_alphabetic() || character == '_' {
+            let mut end = start.saturating_add(character.len_utf8());
+            while let Some((index, next)) =
+                chars.next_if(|(_, next)| next.is_alphanumeric() || *next == '_')
+            {
+                end = index.saturating_add(next.len_utf8());
+            }
+            if text
+                .get(start..end)
+                .is_some_and(|word| RUST_KEYWORDS.contains(&word))
+            {
+                result.push(annotation(AnnotationType::Keyword, start, end));
+            }
+        }
+    }
+    result
+}
+
+fn annotation(annotation_type: AnnotationType, start: ByteIdx, end: ByteIdx) -> Annotation {
+    Annotation {
+        annotation_type,
+        start,
+        end,
+    }
+}
+
+/// 查找字符串字面量的结束位置(结束的双引号之后)，没有结束时返回行尾
+fn string_end(text: &str, from: ByteIdx) -> ByteIdx {
+    let mut escaped = false;
+    for (index, character) in text.get(from..).unwrap_or_default().char_indices() {
+        match character {
+            '\\' => escaped = !escaped,
+            '"' if !escaped => return from.saturating_add(index).saturating_add(1),
+            _ => escaped = false,
+        }
+    }
+    text.len()
+}
+
+/// 查找从 `start` 处的单引号开始的字符字面量的结束位置。
+///
+/// 形如 `'a`、`'static` 的生命周期不是字符字面量，返回 `None`。
+fn char_literal_end(text: &str, start: ByteIdx) -> Option<ByteIdx> {
+    let rest = text.get(start.saturating_add(1)..)?;
+    let mut chars = rest.char_indices();
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        // 转义字符，例如 '\n'、'\''、'\u{1F600}'
+        let close = rest.get(2..)?.find('\'')?;
+        return Some(start.saturating_add(close).saturating_add(4));
+    }
+    match chars.next() {
+        Some((index, '\'')) => Some(start.saturating_add(index).saturating_add(2)),
+        _ => None,
+    }
+}
@@ -2,6 +2,7 @@ use crossterm::style::Color;
 use crate::editor::annotatedstring::AnnotationType;
 
 /// 终端可以使用的属性
+#[derive(Clone, Copy)]
 pub struct Attribute {
     // 前景字体颜色
     pub foreground: Option<Color>,
@@ -9,33 +10,18 @@ pub struct Attribute {
     pub background: Option<Color>,
 }
 
+impl Attribute {
+    pub const fn new(foreground: Option<Color>, background: Option<Color>) -> Self {
+        Self {
+            foreground,
+            background,
+        }
+    }
+}
+
 impl From<AnnotationType> for Attribute {
+    /// 颜色由当前主题决定，见 `Terminal::set_theme`
     fn from(annotation_type: AnnotationType) -> Self {
-        match annotation_type {
-            AnnotationType::Match => Self {
-                foreground: Some(Color::Rgb {
-                    r: 255,
-                    g: 255,
-                    b: 255,
-                }),
-                background: Some(Color::Rgb {
-                    r: 100,
-                    g: 100,
-                    b: 100,
-                }),
-            },
-            AnnotationType::SelectedMatch => Self {
-                foreground: Some(Color::Rgb {
-                    r: 255,
-                    g: 255,
-                    b: 255,
-                }),
-                background: Some(Color::Rgb {
-                    r: 255,
-                    g: 251,
-                    b: 0,
-                }),
-            },
-        }
+        super::current_theme().attribute(annotation_type)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,77 @@
+// Base64 编码使用的字符表
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// OSC 52 序列中最多写入的文本字节数。
+///
+/// 许多终端会忽略过长的序列(如 hterm 限制在 100000 个字符左右)，
+/// 这里限制编码前的文本，使编码后的内容不超过 100000 字节。
+pub const MAX_CLIPBOARD_BYTES: usize = 75_000;
+
+/// 构建将文本写入剪贴板的 OSC 52 序列，超过上限的文本在字符边界处截断。
+///
+/// # 返回值
+/// 返回序列，以及文本是否被截断。
+pub fn osc52_sequence(text: &str) -> (String, bool) {
+    let end = (0..=MAX_CLIPBOARD_BYTES.min(text.len()))
+        .rev()
+        .find(|index| text.is_char_boundary(*index))
+        .unwrap_or(0);
+    let truncated = end < text.len();
+    let payload = encode_base64(text.get(..end).unwrap_or_default().as_bytes());
+    // `c` 表示系统剪贴板。序列以 BEL 结尾，比以 ST 结尾支持的终端更多
+    (format!("\x1b]52;c;{payload}\x07"), truncated)
+}
+
+/// 按标准的 Base64 编码(带 `=` 填充)
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3).saturating_mul(4));
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        for (target, byte) in group.iter_mut().zip(chunk) {
+            *target = *byte;
+        }
+        let [first, second, third] = group;
+        // 3 个字节共 24 位，每 6 位对应一个字符
+        let indices = [
+            first >> 2,
+            ((first & 0b11) << 4) | (second >> 4),
+            ((second & 0b1111) << 2) | (third >> 6),
+            third & 0b11_1111,
+        ];
+        // n 个字节编码为 n + 1 个字符，其余用 `=` 填充
+        for (position, index) in indices.iter().enumerate() {
+            if position <= chunk.len() {
+                encoded.push(char::from(BASE64_ALPHABET.get(usize::from(*index)).copied().unwrap_or(b'A')));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64("你好".as_bytes()), "5L2g5aW9");
+    }
+
+    #[test]
+    fn long_text_is_truncated_at_char_boundary() {
+        let (sequence, truncated) = osc52_sequence("hi");
+        assert_eq!(sequence, "\x1b]52;c;aGk=\x07");
+        assert!(!truncated);
+        // 每个字符占 3 个字节，上限不是 3 的倍数时也不能截断在字符中间
+        let text = "你".repeat(MAX_CLIPBOARD_BYTES);
+        let (sequence, truncated) = osc52_sequence(&text);
+        assert!(truncated);
+        let payload_len = sequence.len().saturating_sub("\x1b]52;c;\x07".len());
+        assert_eq!(payload_len, MAX_CLIPBOARD_BYTES.div_ceil(3).saturating_mul(4));
+    }
+}
@@ -1,4 +1,7 @@
 use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
 use crossterm::{queue, Command};
 use crossterm::style::{
     Attribute::{Reset, Reverse},
@@ -6,12 +9,24 @@ use crossterm::style::{
 };
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, size, Clear, ClearType, DisableLineWrap, EnableLineWrap, EnterAlternateScreen, LeaveAlternateScreen, SetTitle};
 use std::io::{stdout, Error, Write};
+use std::sync::{PoisonError, RwLock};
 
 use super::{Position, Size, AnnotatedString};
 
 use attribute::Attribute;
+pub use theme::Theme;
 
 mod attribute;
+mod clipboard;
+mod theme;
+
+// 当前使用的颜色主题
+static THEME: RwLock<Theme> = RwLock::new(Theme::dark());
+
+/// 获取当前使用的颜色主题
+fn current_theme() -> Theme {
+    *THEME.read().unwrap_or_else(PoisonError::into_inner)
+}
 
 /// 表示终端。
 /// 平台边缘情况处理：当 `usize` < `u16` 时：
@@ -23,6 +38,10 @@ pub struct Terminal;
 impl Terminal {
     // 结束程序
     pub fn terminate() -> Result<(), Error> {
+        // 停止捕获鼠标事件
+        Self::disable_mouse_capture()?;
+        // 关闭括号粘贴模式
+        Self::disable_bracketed_paste()?;
         // 退出备用屏幕
         Self::leave_alternate_screen()?;
         // 重新启用换行
@@ -43,6 +62,10 @@ impl Terminal {
         Self::enter_alternate_screen()?;
         // 禁用换行
         Self::disable_line_wrap()?;
+        // 捕获鼠标事件，以支持点击定位光标
+        Self::enable_mouse_capture()?;
+        // 开启括号粘贴模式，粘贴的内容会作为一个事件一次性收到
+        Self::enable_bracketed_paste()?;
         // 清屏
         Self::clear_screen()?;
         // 刷新缓冲区
@@ -62,6 +85,30 @@ impl Terminal {
         Ok(())
     }
 
+    /// 开始捕获鼠标事件
+    pub fn enable_mouse_capture() -> Result<(), Error> {
+        Self::queue_command(EnableMouseCapture)?;
+        Ok(())
+    }
+
+    /// 停止捕获鼠标事件
+    pub fn disable_mouse_capture() -> Result<(), Error> {
+        Self::queue_command(DisableMouseCapture)?;
+        Ok(())
+    }
+
+    /// 开启括号粘贴模式
+    pub fn enable_bracketed_paste() -> Result<(), Error> {
+        Self::queue_command(EnableBracketedPaste)?;
+        Ok(())
+    }
+
+    /// 关闭括号粘贴模式
+    pub fn disable_bracketed_paste() -> Result<(), Error> {
+        Self::queue_command(DisableBracketedPaste)?;
+        Ok(())
+    }
+
     /// 设置终端标题
     pub fn set_title(title: &str) -> Result<(), Error> {
         Self::queue_command(SetTitle(title))?;
@@ -124,19 +171,40 @@ impl Terminal {
         Ok(())
     }
 
-    /// 打印注释行
-    pub fn print_annotated_row(row: usize, annotated_string: &AnnotatedString,) -> Result<(), Error> {
-        // 移动光标到对应行,并清除整行内容
-        Self::move_caret_to(Position { row, col: 0 })?;
-        Self::clear_line()?;
-        // 打印
+    /// 在指定位置打印文本，只清除从该位置开始的 `width` 列，不影响同一行的其他内容
+    pub fn print_at(origin: Position, width: usize, line_text: &str) -> Result<(), Error> {
+        Self::clear_columns(origin, width)?;
+        Self::print(line_text)?;
+        Ok(())
+    }
+
+    /// 在指定位置打印注释行，只清除从该位置开始的 `width` 列
+    pub fn print_annotated_at(
+        origin: Position,
+        width: usize,
+        annotated_string: &AnnotatedString,
+    ) -> Result<(), Error> {
+        Self::clear_columns(origin, width)?;
+        Self::print_annotated(annotated_string)
+    }
+
+    /// 用空格覆盖从指定位置开始的 `width` 列，然后把光标移回该位置
+    fn clear_columns(origin: Position, width: usize) -> Result<(), Error> {
+        Self::move_caret_to(origin)?;
+        Self::print(&" ".repeat(width))?;
+        Self::move_caret_to(origin)?;
+        Ok(())
+    }
+
+    /// 在光标处打印注释文本，有标注的部分使用对应的颜色
+    fn print_annotated(annotated_string: &AnnotatedString) -> Result<(), Error> {
         annotated_string
             .into_iter()
             .try_for_each(|part| -> Result<(), Error> {
                 // 如果有标注就设置对应颜色打印
                 if let Some(annotation_type) = part.annotation_type {
                     let attribute: Attribute = annotation_type.into();
-                    Self::set_attribute(&attribute)?;
+                    Self::set_attribute(attribute)?;
                 }
                 Self::print(part.string)?;
                 // 打印完成后重置颜色
@@ -146,8 +214,13 @@ impl Terminal {
         Ok(())
     }
 
+    /// 设置标注使用的颜色主题，下次绘制时生效
+    pub fn set_theme(theme: Theme) {
+        *THEME.write().unwrap_or_else(PoisonError::into_inner) = theme;
+    }
+
     /// 设置终端属性(颜色)
-    fn set_attribute(attribute: &Attribute) -> Result<(), Error> {
+    fn set_attribute(attribute: Attribute) -> Result<(), Error> {
         if let Some(foreground_color) = attribute.foreground {
             Self::queue_command(SetForegroundColor(foreground_color))?;
         }
@@ -169,6 +242,19 @@ impl Terminal {
         Self::print_row(row, &format!("{Reverse}{line_text:width$.width$}{Reset}"))
     }
 
+    /// 通过 OSC 52 序列让终端把文本放入系统剪贴板，适用于 SSH 等无法直接访问剪贴板的环境。
+    ///
+    /// 终端不支持 OSC 52 时序列会被忽略(部分终端会把它显示出来)。
+    /// 文本超过终端通常接受的长度时只写入开头的部分。
+    ///
+    /// # 返回值
+    /// 返回文本是否被截断。
+    pub fn set_clipboard(text: &str) -> Result<bool, Error> {
+        let (sequence, truncated) = clipboard::osc52_sequence(text);
+        Self::print(&sequence)?;
+        Ok(truncated)
+    }
+
     /// 打印
     pub fn print(str: &str) -> Result<(), Error> {
         Self::queue_command(Print(str))?;
@@ -0,0 +1,219 @@
+use std::{env, sync::OnceLock};
+
+use crossterm::style::Color;
+use toml::Value;
+
+use super::attribute::Attribute;
+use crate::editor::annotatedstring::AnnotationType;
+
+/// 各种标注类型使用的颜色
+#[derive(Clone, Copy)]
+pub struct Theme {
+    search_match: Attribute,
+    selected_match: Attribute,
+    line_number: Attribute,
+    modified_line: Attribute,
+    selection: Attribute,
+    keyword: Attribute,
+    string: Attribute,
+    number: Attribute,
+    comment: Attribute,
+    matching_bracket: Attribute,
+    ruler: Attribute,
+    cursor: Attribute,
+    fold: Attribute,
+    word_occurrence: Attribute,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// 适合深色背景终端的主题
+    pub const fn dark() -> Self {
+        Self {
+            search_match: Attribute::new(Some(rgb(255, 255, 255)), Some(rgb(100, 100, 100))),
+            selected_match: Attribute::new(Some(rgb(255, 255, 255)), Some(rgb(255, 251, 0))),
+            line_number: Attribute::new(Some(rgb(128, 128, 128)), None),
+            modified_line: Attribute::new(Some(rgb(152, 195, 121)), None),
+            selection: Attribute::new(None, Some(rgb(60, 90, 140))),
+            keyword: Attribute::new(Some(rgb(198, 120, 221)), None),
+            string: Attribute::new(Some(rgb(152, 195, 121)), None),
+            number: Attribute::new(Some(rgb(209, 154, 102)), None),
+            comment: Attribute::new(Some(rgb(92, 99, 112)), None),
+            matching_bracket: Attribute::new(Some(rgb(255, 255, 255)), Some(rgb(90, 90, 90))),
+            ruler: Attribute::new(None, Some(rgb(50, 50, 50))),
+            cursor: Attribute::new(Some(rgb(0, 0, 0)), Some(rgb(200, 200, 200))),
+            fold: Attribute::new(Some(rgb(128, 128, 128)), None),
+            word_occurrence: Attribute::new(None, Some(rgb(62, 68, 81))),
+        }
+    }
+
+    /// 适合浅色背景终端的主题
+    pub const fn light() -> Self {
+        Self {
+            search_match: Attribute::new(Some(rgb(0, 0, 0)), Some(rgb(200, 200, 200))),
+            selected_match: Attribute::new(Some(rgb(0, 0, 0)), Some(rgb(255, 215, 0))),
+            line_number: Attribute::new(Some(rgb(150, 150, 150)), None),
+            modified_line: Attribute::new(Some(rgb(80, 161, 79)), None),
+            selection: Attribute::new(None, Some(rgb(173, 214, 255))),
+            keyword: Attribute::new(Some(rgb(166, 38, 164)), None),
+            string: Attribute::new(Some(rgb(80, 161, 79)), None),
+            number: Attribute::new(Some(rgb(152, 104, 1)), None),
+            comment: Attribute::new(Some(rgb(160, 161, 167)), None),
+            matching_bracket: Attribute::new(Some(rgb(0, 0, 0)), Some(rgb(210, 210, 210))),
+            ruler: Attribute::new(None, Some(rgb(235, 235, 235))),
+            cursor: Attribute::new(Some(rgb(255, 255, 255)), Some(rgb(80, 80, 80))),
+            fold: Attribute::new(Some(rgb(150, 150, 150)), None),
+            word_occurrence: Attribute::new(None, Some(rgb(225, 228, 235))),
+        }
+    }
+
+    /// 按名称获取内置主题，名称无法识别时返回 `None`
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    /// 获取标注类型使用的颜色。终端不支持真彩色时转换为最接近的 16 色
+    pub fn attribute(&self, annotation_type: AnnotationType) -> Attribute {
+        let attribute = match annotation_type {
+            AnnotationType::Match => self.search_match,
+            AnnotationType::SelectedMatch => self.selected_match,
+            AnnotationType::LineNumber => self.line_number,
+            AnnotationType::ModifiedLine => self.modified_line,
+            AnnotationType::Selection => self.selection,
+            AnnotationType::Keyword => self.keyword,
+            AnnotationType::String => self.string,
+            AnnotationType::Number => self.number,
+            AnnotationType::Comment => self.comment,
+            AnnotationType::MatchingBracket => self.matching_bracket,
+            AnnotationType::Ruler => self.ruler,
+            AnnotationType::Cursor => self.cursor,
+            AnnotationType::Fold => self.fold,
+            AnnotationType::WordOccurrence => self.word_occurrence,
+        };
+        if supports_true_color() {
+            attribute
+        } else {
+            Attribute::new(
+                attribute.foreground.map(nearest_ansi_color),
+                attribute.background.map(nearest_ansi_color),
+            )
+        }
+    }
+
+    /// 用配置中的值覆盖一种标注类型的颜色。
+    ///
+    /// 值可以是一个颜色(只设置前景色)，也可以是包含 `foreground` 和 `background` 的表。
+    /// 颜色可以写成 `#rrggbb`，也可以是 `red`、`dark_grey` 这样的颜色名称。
+    ///
+    /// # 返回值
+    /// 标注类型名称或颜色无法识别时，返回描述错误的信息。
+    pub fn set_color(&mut self, name: &str, value: &Value) -> Result<(), String> {
+        let attribute = match name {
+            "match" => &mut self.search_match,
+            "selected_match" => &mut self.selected_match,
+            "line_number" => &mut self.line_number,
+            "modified_line" => &mut self.modified_line,
+            "selection" => &mut self.selection,
+            "keyword" => &mut self.keyword,
+            "string" => &mut self.string,
+            "number" => &mut self.number,
+            "comment" => &mut self.comment,
+            "matching_bracket" => &mut self.matching_bracket,
+            "ruler" => &mut self.ruler,
+            "cursor" => &mut self.cursor,
+            "fold" => &mut self.fold,
+            "word_occurrence" => &mut self.word_occurrence,
+            _ => return Err(format!("unknown color '{name}'")),
+        };
+        let color = |value: Option<&Value>| -> Result<Option<Color>, String> {
+            value
+                .map(|value| {
+                    value
+                        .as_str()
+                        .and_then(parse_color)
+                        .ok_or_else(|| format!("invalid color for '{name}'"))
+                })
+                .transpose()
+        };
+        match value {
+            Value::Table(table) => {
+                if let Some(foreground) = color(table.get("foreground"))? {
+                    attribute.foreground = Some(foreground);
+                }
+                if let Some(background) = color(table.get("background"))? {
+                    attribute.background = Some(background);
+                }
+            }
+            value => attribute.foreground = color(Some(value))?,
+        }
+        Ok(())
+    }
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+/// 解析 `#rrggbb` 格式或颜色名称
+fn parse_color(value: &str) -> Option<Color> {
+    let Some(hex) = value.strip_prefix('#') else {
+        return Color::try_from(value).ok();
+    };
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |range| hex.get(range).and_then(|digits| u8::from_str_radix(digits, 16).ok());
+    Some(rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+}
+
+/// 根据 `COLORTERM` 环境变量判断终端是否支持真彩色
+fn supports_true_color() -> bool {
+    static TRUE_COLOR: OnceLock<bool> = OnceLock::new();
+    *TRUE_COLOR.get_or_init(|| {
+        env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit")
+    })
+}
+
+/// 找到与 RGB 颜色最接近的 16 色，其他颜色保持不变
+fn nearest_ansi_color(color: Color) -> Color {
+    const PALETTE: [(Color, (u8, u8, u8)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::DarkRed, (128, 0, 0)),
+        (Color::DarkGreen, (0, 128, 0)),
+        (Color::DarkYellow, (128, 128, 0)),
+        (Color::DarkBlue, (0, 0, 128)),
+        (Color::DarkMagenta, (128, 0, 128)),
+        (Color::DarkCyan, (0, 128, 128)),
+        (Color::Grey, (192, 192, 192)),
+        (Color::DarkGrey, (128, 128, 128)),
+        (Color::Red, (255, 0, 0)),
+        (Color::Green, (0, 255, 0)),
+        (Color::Yellow, (255, 255, 0)),
+        (Color::Blue, (0, 0, 255)),
+        (Color::Magenta, (255, 0, 255)),
+        (Color::Cyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+    let Color::Rgb { r, g, b } = color else {
+        return color;
+    };
+    let distance = |(pr, pg, pb): (u8, u8, u8)| {
+        [(r, pr), (g, pg), (b, pb)]
+            .into_iter()
+            .map(|(channel, palette)| u32::from(channel.abs_diff(palette)))
+            .fold(0_u32, |sum, diff| sum.saturating_add(diff.saturating_mul(diff)))
+    };
+    PALETTE
+        .into_iter()
+        .min_by_key(|(_, palette)| distance(*palette))
+        .map_or(color, |(ansi, _)| ansi)
+}
@@ -1,6 +1,10 @@
 use std::{cmp::min, io::Error};
 use crate::prelude::*;
-use super::super::{command::Edit, line::Line, Terminal};
+use super::super::{
+    command::{Edit, Move},
+    line::Line,
+    Terminal,
+};
 use super::UIComponent;
 
 #[derive(Default)]
@@ -9,33 +13,103 @@ pub struct CommandBar {
     prompt: String,
     /// 当前输入的内容值
     value: Line,
+    /// 光标所在的字素索引
+    caret: GraphemeIdx,
     needs_redraw: bool,
     size: Size,
 }
 
 impl CommandBar {
 
-    /// 处理编辑命令
+    /// 处理编辑命令，插入和删除都在光标处进行
     pub fn handle_edit_command(&mut self, command: Edit) {
         match command {
-            Edit::Insert(character) => self.value.append_char(character),
-            Edit::Delete | Edit::InsertNewline=> {}
-            Edit::DeleteBackward => self.value.delete_last(),
+            Edit::Insert(character) => self.insert_str(&character.to_string()),
+            Edit::Indent => self.insert_str("\t"),
+            Edit::Delete => self.value.delete(self.caret),
+            Edit::DeleteBackward => {
+                if self.caret > 0 {
+                    self.caret = self.caret.saturating_sub(1);
+                    self.value.delete(self.caret);
+                }
+            }
+            Edit::DeleteWordBackward => {
+                let word_start = self.value.prev_word_start(self.caret);
+                self.value.delete_range(word_start..self.caret);
+                self.caret = word_start;
+            }
+            Edit::DeleteToLineEnd => self
+                .value
+                .delete_range(self.caret..self.value.grapheme_count()),
+            // 命令栏只有一行，只粘贴第一行的内容
+            Edit::Paste(text) => {
+                if let Some(first_line) = text.lines().next() {
+                    self.insert_str(first_line);
+                }
+            }
+            Edit::InsertNewline
+            | Edit::ToggleBlockComment
+            | Edit::ToggleComment
+            | Edit::DuplicateLine
+            | Edit::JoinLine
+            | Edit::DeleteLine
+            | Edit::MoveLineUp
+            | Edit::MoveLineDown
+            | Edit::Dedent => {}
         }
         self.set_needs_redraw(true);
     }
 
+    /// 处理移动命令，在输入内容中移动光标。不适用于单行输入的命令会被忽略
+    pub fn handle_move_command(&mut self, command: Move) {
+        let len = self.value.grapheme_count();
+        self.caret = match command {
+            Move::Left => self.caret.saturating_sub(1),
+            Move::Right => self.caret.saturating_add(1).min(len),
+            Move::StartOfLine | Move::DocumentStart => 0,
+            Move::EndOfLine | Move::DocumentEnd => len,
+            Move::WordForward => self.value.next_word_start(self.caret),
+            Move::WordBackward => self.value.prev_word_start(self.caret),
+            Move::Up
+            | Move::Down
+            | Move::PageUp
+            | Move::PageDown
+            | Move::PageLeft
+            | Move::PageRight
+            | Move::MatchBracket => return,
+        };
+        self.set_needs_redraw(true);
+    }
+
+    /// 在光标处插入文本，光标移动到插入的文本之后
+    fn insert_str(&mut self, text: &str) {
+        let old_len = self.value.grapheme_count();
+        self.value.insert_str(text, self.caret);
+        // 插入组合字符时可能与前面的字素合并，按字素数量的变化移动光标
+        let inserted = self.value.grapheme_count().saturating_sub(old_len);
+        self.caret = self.caret.saturating_add(inserted);
+    }
+
+    /// 输入内容中从哪一列开始显示，确保光标始终可见
+    fn visible_start(&self) -> ColIdx {
+        let area_for_value = self.size.width.saturating_sub(self.prompt.len());
+        self.value
+            .width_until(self.caret)
+            .saturating_sub(area_for_value)
+    }
+
     /// 获取插入符(光标对应列位置)
     /// 
-    /// 插入符号的 x 位置（它所在的列）是输入内容宽度加上提示符的长度，
+    /// 插入符号的 x 位置（它所在的列）是光标之前的输入内容宽度加上提示符的长度，
     /// 假设 `self.prompt` 仅由 ASCII 字符组成。或者它是终端的宽度（即终端的最右侧），
     /// 取两者中的较小值。
     pub fn caret_position_col(&self) -> ColIdx {
         
-        let max_width = self
-            .prompt
-            .len()
-            .saturating_add(self.value.grapheme_count());
+        let max_width = self.prompt.len().saturating_add(
+            self.value
+                .width_until(self.caret)
+                .saturating_sub(self.visible_start()),
+        );
         min(max_width, self.size.width)
     }
 
@@ -50,9 +124,17 @@ impl CommandBar {
         self.set_needs_redraw(true);
     }
 
+    /// 设置命令栏的值，例如从历史记录中恢复
+    pub fn set_value(&mut self, value: &str) {
+        self.value = Line::from(value);
+        self.caret = self.value.grapheme_count();
+        self.set_needs_redraw(true);
+    }
+
     /// 清空命令栏的值
     pub fn clear_value(&mut self) {
         self.value = Line::default();
+        self.caret = 0;
         self.set_needs_redraw(true);
     }
 }
@@ -71,12 +153,14 @@ impl UIComponent for CommandBar {
     }
 
     fn draw(&mut self, origin: RowIdx) -> Result<(), Error> {
+        if self.size.is_empty() {
+            return Ok(());
+        }
         // 计算用于显示输入值的空间大小，等于终端宽度减去提示符长度。
         let area_for_value = self.size.width.saturating_sub(self.prompt.len());
-        // 获取命令栏值的宽度（以字素为单位）。
-        let value_end = self.value.width();
-        // 计算要显示的命令栏值的起始位置，确保始终显示值的左侧部分。
-        let value_start = value_end.saturating_sub(area_for_value);
+        // 计算要显示的命令栏值的起始位置，确保光标始终可见。
+        let value_start = self.visible_start();
+        let value_end = value_start.saturating_add(area_for_value);
         // 创建最终要显示的消息，包含提示符和截取后的命令栏值。
         let message = format!(
             "{}{}",
@@ -92,4 +176,17 @@ impl UIComponent for CommandBar {
         // 打印到指定行
         Terminal::print_row(origin, &to_print)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_size_command_bar_draws_nothing() {
+        let mut command_bar = CommandBar::default();
+        command_bar.set_prompt("Search: ");
+        command_bar.set_value("query");
+        assert!(command_bar.draw(0).is_ok());
+        assert_eq!(command_bar.caret_position_col(), 0);
+    }
+}
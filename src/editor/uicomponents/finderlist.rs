@@ -0,0 +1,95 @@
+use crate::prelude::*;
+use std::io::Error;
+use super::super::{line::Line, Terminal};
+use super::UIComponent;
+
+/// 查找文件时显示在命令栏上方的匹配结果列表，选中的项反色显示
+#[derive(Default)]
+pub struct FinderList {
+    // 按匹配程度从高到低排列的路径
+    items: Vec<String>,
+    // 当前选中项的序号
+    selected: usize,
+    needs_redraw: bool,
+    size: Size,
+}
+
+impl FinderList {
+    /// 列表最多能显示的项数
+    pub const fn capacity(&self) -> usize {
+        self.size.height
+    }
+
+    /// 替换列表中的项，选中第一项
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.selected = 0;
+        self.set_needs_redraw(true);
+    }
+
+    /// 选中下一项(`forward` 为 `true` 时)或上一项，到达一端后从另一端继续
+    pub fn select_next(&mut self, forward: bool) {
+        let len = self.items.len();
+        let step = if forward { 1 } else { len.saturating_sub(1) };
+        self.selected = self.selected.saturating_add(step).checked_rem(len).unwrap_or(0);
+        self.set_needs_redraw(true);
+    }
+
+    /// 当前选中的路径，列表为空时返回 `None`
+    pub fn selected(&self) -> Option<&str> {
+        self.items.get(self.selected).map(String::as_str)
+    }
+}
+
+impl UIComponent for FinderList {
+    fn set_needs_redraw(&mut self, value: bool) {
+        self.needs_redraw = value;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+    }
+
+    fn draw(&mut self, origin_row: RowIdx) -> Result<(), Error> {
+        if self.size.is_empty() {
+            return Ok(());
+        }
+        // 始终占满整个区域，没有对应项的行留空，这样结果变少时不会留下之前的内容
+        for row in 0..self.size.height {
+            let current_row = origin_row.saturating_add(row);
+            let Some(item) = self.items.get(row) else {
+                Terminal::print_row(current_row, "")?;
+                continue;
+            };
+            // 路径前留出两列，太长时截断到终端宽度
+            let visible = Line::from(item).get_visible_graphemes(0..self.size.width.saturating_sub(2));
+            if row == self.selected {
+                Terminal::print_inverted_row(current_row, &format!("> {visible}"))?;
+            } else {
+                Terminal::print_row(current_row, &format!("  {visible}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut list = FinderList::default();
+        assert_eq!(list.selected(), None);
+        list.set_items(vec![String::from("a"), String::from("b")]);
+        list.select_next(false);
+        assert_eq!(list.selected(), Some("b"));
+        list.select_next(true);
+        assert_eq!(list.selected(), Some("a"));
+        assert!(list.draw(0).is_ok());
+    }
+}
@@ -1,11 +1,13 @@
 mod commandbar;
+mod finderlist;
 mod messagebar;
 mod statusbar;
 mod view;
 mod uicomponent;
 
 pub use commandbar::CommandBar;
+pub use finderlist::FinderList;
 pub use messagebar::MessageBar;
 pub use statusbar::StatusBar;
-pub use view::View;
+pub use view::{DiskChange, SearchDirection, TextBuffer, View};
 pub use uicomponent::UIComponent;
\ No newline at end of file
@@ -8,7 +8,9 @@ pub struct StatusBar {
     current_status: DocumentStatus,
     // 是否需要重新渲染
     needs_redraw: bool,
-    size: Size
+    size: Size,
+    // 位置是否从 1 开始显示
+    one_based_position: bool,
 }
 
 impl StatusBar {
@@ -19,11 +21,19 @@ impl StatusBar {
             self.needs_redraw = true;
         }
     }
+
+    // 设置位置是否从 1 开始显示
+    pub fn set_one_based_position(&mut self, one_based: bool) {
+        if self.one_based_position != one_based {
+            self.one_based_position = one_based;
+            self.needs_redraw = true;
+        }
+    }
 }
 
 impl UIComponent for StatusBar {
     fn set_needs_redraw(&mut self, value: bool) {
-        self.needs_redraw = value
+        self.needs_redraw = value;
     }
 
     fn needs_redraw(&self) -> bool {
@@ -31,34 +41,121 @@ impl UIComponent for StatusBar {
     }
 
     fn set_size(&mut self, size: Size) {
-        self.size = size
+        self.size = size;
     }
 
     fn draw(&mut self, origin_row: RowIdx) -> Result<(), std::io::Error> {
-        // 组装状态栏的第一部分：文件名、行数和是否修改的指示符
-        let line_count = self.current_status.line_count_to_string();
-        let modified_indicator = self.current_status.modified_indicator_to_string();
+        if self.size.is_empty() {
+            return Ok(());
+        }
+        // 在指定的位置打印倒置颜色的状态栏行
+        for (row, text) in self.rows().iter().enumerate() {
+            Terminal::print_inverted_row(origin_row.saturating_add(row), text)?;
+        }
+        Ok(())
+    }
+}
+
+impl StatusBar {
+    /// 组装状态栏每一行的内容。
+    ///
+    /// 只有一行时，左侧是文件信息，右侧是位置等指示符；有两行(或更多行)时，
+    /// 第一行显示文件信息和编辑状态，第二行靠右显示语言、编码、换行符和光标位置。
+    fn rows(&self) -> Vec<String> {
+        let status = &self.current_status;
+        // 文件信息：录制标志、模式、缓冲区序号、文件名、只读标志、行数和是否修改的指示符
+        let recording = status.recording_indicator_to_string();
+        let mode = status.mode_to_string();
+        let buffer_position = status.buffer_position_to_string();
+        let line_count = status.line_count_to_string();
+        let modified_indicator = status.modified_indicator_to_string();
+        let read_only_indicator = status.read_only_indicator_to_string();
         let beginning = format!(
-            "{} - {line_count} {modified_indicator}",
-            self.current_status.file_name
+            "{recording}{mode}{buffer_position}{}{read_only_indicator} - {line_count} {modified_indicator}",
+            status.file_name
         );
 
-        // 组装整个状态栏，在末尾加上位置指示符
-        let position_indicator = self.current_status.position_indicator_to_string();
-        // 计算剩余空间的长度，确保状态栏内容不会超出终端宽度
-        let remainder_len = self.size.width.saturating_sub(beginning.len());
-        // 使用格式化字符串将所有部分组合起来，确保位置指示符靠右对齐
-        let status = format!("{beginning}{position_indicator:>remainder_len$}");
-        
-        // 只有当状态栏内容完全适合终端宽度时才打印；否则，打印空字符串以清除该行
-        let to_print = if status.len() <= self.size.width {
-            status
-        } else {
-            String::new()
-        };
-        // 在指定的位置打印倒置颜色的状态栏行
-        Terminal::print_inverted_row(origin_row, &to_print)?;
+        let position = status.position_indicator_to_string(self.one_based_position);
+        let percentage = status.percentage_to_string();
+        let horizontal_offset = status.horizontal_offset_to_string();
+        let word_count = status.word_count_to_string();
+        let whitespace = status.whitespace_indicator_to_string();
+        let overwrite = status.overwrite_indicator_to_string();
+        let file_type = format!("{} {} {}", status.language, status.encoding, status.line_ending);
+        if self.size.height > 1 {
+            let editing = format!("{word_count}{horizontal_offset}{whitespace}{overwrite}");
+            return vec![
+                self.fit_row(&beginning, &[editing, String::new()]),
+                // 终端较窄时依次省略语言/编码/换行符和百分比
+                self.fit_row(
+                    "",
+                    &[format!("{file_type} {position} {percentage}"), format!("{position} {percentage}"), position],
+                ),
+            ];
+        }
+        // 右侧的位置指示符。终端较窄时依次省略语言/编码/换行符、百分比、插入/改写模式、空白字符标志、水平滚动的列数和单词数
+        let candidates = [
+            format!("{word_count}{horizontal_offset}{whitespace}{overwrite} {file_type} {position} {percentage}"),
+            format!("{word_count}{horizontal_offset}{whitespace}{overwrite} {position} {percentage}"),
+            position,
+        ];
+        vec![self.fit_row(&beginning, &candidates)]
+    }
 
-        Ok(())
+    /// 在左侧内容之后靠右显示第一个能放下的指示符，左右之间至少留一个空格。都放不下时该行为空
+    fn fit_row(&self, beginning: &str, candidates: &[String]) -> String {
+        let fitting_indicator = candidates.iter().find(|indicator| {
+            beginning
+                .len()
+                .saturating_add(indicator.len())
+                .saturating_add(1)
+                <= self.size.width
+        });
+        fitting_indicator.map_or_else(String::new, |indicator| {
+            // 计算剩余空间的长度，确保位置指示符靠右对齐
+            let remainder_len = self.size.width.saturating_sub(beginning.len());
+            format!("{beginning}{indicator:>remainder_len$}")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_size_status_bar_draws_nothing() {
+        let mut status_bar = StatusBar::default();
+        status_bar.update_status(DocumentStatus {
+            file_name: String::from("file.txt"),
+            ..DocumentStatus::default()
+        });
+        assert!(status_bar.draw(0).is_ok());
+        status_bar.resize(Size { height: 1, width: 0 });
+        assert!(status_bar.draw(0).is_ok());
+    }
+
+    #[test]
+    fn two_row_status_bar_moves_file_type_and_position_to_second_row() {
+        let mut status_bar = StatusBar::default();
+        status_bar.update_status(DocumentStatus {
+            file_name: String::from("main.rs"),
+            total_lines: 10,
+            language: String::from("Rust"),
+            encoding: String::from("UTF-8"),
+            line_ending: String::from("LF"),
+            ..DocumentStatus::default()
+        });
+        status_bar.resize(Size { height: 1, width: 60 });
+        let rows = status_bar.rows();
+        assert_eq!(rows.len(), 1);
+        assert!(rows.iter().all(|row| row.len() == 60));
+        assert!(rows.first().is_some_and(|row| row.starts_with("main.rs - 10 lines") && row.contains("Rust UTF-8 LF")));
+        status_bar.resize(Size { height: 2, width: 30 });
+        let rows = status_bar.rows();
+        assert_eq!(
+            rows,
+            vec![format!("{:<27}INS", "main.rs - 10 lines"), format!("{:>30}", "Rust UTF-8 LF Ln 0, Col 0 10%")]
+        );
     }
-}
\ No newline at end of file
+}
@@ -31,7 +31,7 @@ pub trait UIComponent {
                     let _ = err;
                 }
             } else {
-                self.set_needs_redraw(false)
+                self.set_needs_redraw(false);
             }
         }
     }
@@ -1,30 +1,126 @@
-use std::{fs::{read_to_string, File}, io::{Error, Write}};
+use std::{cell::Cell, collections::BTreeSet, ffi::OsString, fs::{self, read, File}, io::{Error, ErrorKind, Write}, ops::Range, path::{Path, PathBuf}};
+use encoding_rs::Encoding;
 use super::FileInfo;
-use super::Line;
+use super::TextEncoding;
+use super::LineEnding;
+use super::{Line, Lines, DEFAULT_TAB_WIDTH};
+use super::modeline::{self, Modeline};
+use super::LineEdit;
+use super::{Annotation, AnnotationType, Highlighter};
+use unicode_segmentation::UnicodeSegmentation;
 use crate::prelude::*;
 
-#[derive(Default)]
+// clippy::struct_excessive_bools: 这些标志分别描述互相独立的文件属性，不适合合并为枚举
+#[allow(clippy::struct_excessive_bools)]
 pub struct Buffer {
-    pub lines: Vec<Line>,
+    pub lines: Lines,
     pub file_info: FileInfo,
-    // dirty 标志表示缓冲区是否已被修改。此文件中的所有其他更改旨在在插入时将 dirty 切换为 true。
+    // dirty 标志表示缓冲区是否已被修改。此文件中的所有其他更改都通过 `mark_modified` 将 dirty 切换为 true。
     pub dirty: bool,
+    // 保存时使用的换行符，加载时从文件中检测
+    pub line_ending: LineEnding,
+    // 加载的文件是否混用了多种换行符
+    pub mixed_line_endings: bool,
+    // 文件最后一行之后是否有换行符
+    pub trailing_newline: bool,
+    // 文件解码是否有损。有损时覆盖保存会破坏原文件中无法解码的字节
+    pub lossy_decoded: bool,
+    // 加载时从文件中读取到的 modeline
+    pub modeline: Option<Modeline>,
+    // 所有行使用的制表符宽度
+    pub tab_width: ColIdx,
+    // 本次打开文件后是否已经备份过原文件
+    backup_made: bool,
+    // 内容每次修改后递增，用于判断缓存的统计结果是否过期
+    revision: usize,
+    // 缓存的字符数和单词数，以及计算它们时的 `revision`
+    counts: Cell<Option<(usize, usize, usize)>>,
+    // 上次保存(或加载)之后修改过的行
+    modified_lines: BTreeSet<LineIdx>,
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self {
+            lines: Lines::new(),
+            file_info: FileInfo::default(),
+            dirty: false,
+            line_ending: LineEnding::default(),
+            mixed_line_endings: false,
+            // 新文件保存时以换行符结尾
+            trailing_newline: true,
+            lossy_decoded: false,
+            modeline: None,
+            tab_width: DEFAULT_TAB_WIDTH,
+            backup_made: false,
+            revision: 0,
+            counts: Cell::new(None),
+            modified_lines: BTreeSet::new(),
+        }
+    }
+}
+
+impl From<&str> for Buffer {
+    /// 从内存中的文本创建缓冲区，缓冲区不关联任何文件
+    fn from(contents: &str) -> Self {
+        // `lines` 会同时去掉 \n 和 \r\n，换行符类型单独记录下来，保存时按原样写回
+        let (line_ending, mixed_line_endings) = LineEnding::detect(contents);
+        Self {
+            lines: contents.lines().map(Line::from).collect(),
+            line_ending,
+            mixed_line_endings,
+            trailing_newline: contents.ends_with('\n'),
+            ..Self::default()
+        }
+    }
 }
 
 impl Buffer {
 
     /// 读取文件内容到buffer中
-    pub fn load(file_name: &str) -> Result<Self, Error> {
-        let contents = read_to_string(file_name)?;
-        let lines = contents.lines()
-            .map(|value| Line::from(value))
-            .collect();
+    ///
+    /// 文件内容会被解码为 UTF-8：优先根据 BOM 判断编码，其次尝试 UTF-8，
+    /// 都不满足时使用 `fallback` 编码。
+    ///
+    /// # 返回值
+    /// 路径是目录时返回 `ErrorKind::IsADirectory` 错误，无论平台读取目录时报告什么错误。
+    pub fn load(file_name: &str, fallback: &'static Encoding) -> Result<Self, Error> {
+        let bytes = Self::read_file(file_name)?;
+        let (contents, encoding, lossy_decoded) = TextEncoding::decode(&bytes, fallback);
+        Ok(Self::from_decoded(file_name, &contents, encoding, lossy_decoded))
+    }
 
-        Ok(Self{
-            lines,
-            file_info: FileInfo::from(file_name),
-            dirty: false,
-        })
+    /// 按指定的编码读取文件内容，不自动检测编码。
+    ///
+    /// # 返回值
+    /// 文件中有不能按该编码解码的字节时返回 `ErrorKind::InvalidData` 错误，路径是目录时与 `load` 相同。
+    pub fn load_as(file_name: &str, encoding: &'static Encoding) -> Result<Self, Error> {
+        let bytes = Self::read_file(file_name)?;
+        let (contents, encoding, lossy_decoded) = TextEncoding::decode_as(&bytes, encoding);
+        if lossy_decoded {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{file_name} is not valid {encoding}"),
+            ));
+        }
+        Ok(Self::from_decoded(file_name, &contents, encoding, false))
+    }
+
+    /// 读取文件的原始字节。路径是目录时返回 `ErrorKind::IsADirectory` 错误
+    fn read_file(file_name: &str) -> Result<Vec<u8>, Error> {
+        if Path::new(file_name).is_dir() {
+            return Err(Error::new(ErrorKind::IsADirectory, format!("{file_name} is a directory")));
+        }
+        read(file_name)
+    }
+
+    /// 用解码后的文件内容创建关联到该文件的缓冲区
+    fn from_decoded(file_name: &str, contents: &str, encoding: TextEncoding, lossy_decoded: bool) -> Self {
+        let mut buffer = Self::from(contents);
+        buffer.file_info = FileInfo::from(file_name).with_encoding(encoding);
+        buffer.file_info.record_modified();
+        buffer.lossy_decoded = lossy_decoded;
+        buffer
     }
 
     /// 向下搜索给定查询字符串的位置。
@@ -34,19 +130,21 @@ impl Buffer {
     /// - `from`: 搜索的起始位置（行索引和字素索引）。
     ///
     /// # 返回值
-    /// 如果找到匹配项，则返回匹配项的位置；否则返回 `None`。
+    /// 如果找到匹配项，则返回匹配项的位置，以及是否越过文档末尾回到了顶部；否则返回 `None`。
     ///
     /// # 逻辑说明
     /// 该方法从指定位置开始向下搜索，直到文档末尾，然后环绕回文档顶部继续搜索，
     /// 确保当前行被搜索两次（一次从中点开始，一次从行首开始），以捕捉所有可能的匹配。
-    pub fn search_forward(&self, query: &str, from: Location) -> Option<Location> {
+    pub fn search_forward(&self, query: &str, from: Location) -> Option<(Location, bool)> {
         if query.is_empty() {
             return None;
         }
         // 标记是否是第一次处理当前行
         let mut is_first = true;
 
-        for (line_index, line) in self
+        // 从起始行到最后一行需要的步数，超过后就回到了文档顶部
+        let steps_to_end = self.lines.len().saturating_sub(from.line_index);
+        for (step, (line_index, line)) in self
             .lines
             .iter()
             .enumerate()
@@ -55,6 +153,7 @@ impl Buffer {
             .skip(from.line_index)
             // 为了确保当前行被搜索两次（一次从中点开始，一次从行首开始），多取一行
             .take(self.lines.len().saturating_add(1))
+            .enumerate()
         {
             // 确定当前行的起始字素索引：
             // - 如果是第一次处理当前行，则从 `from.grapheme_index` 开始；
@@ -68,10 +167,11 @@ impl Buffer {
 
             // 在当前行中搜索查询字符串，如果找到匹配项，则返回匹配位置。
             if let Some(grapheme_index) = line.search_forward(query, from_grapheme_index) {
-                return Some(Location {
+                let location = Location {
                     grapheme_index,
                     line_index,
-                });
+                };
+                return Some((location, step >= steps_to_end));
             }
         }
         None
@@ -84,19 +184,19 @@ impl Buffer {
     /// - `from`: 搜索的起始位置（行索引和字素索引）。
     ///
     /// # 返回值
-    /// 如果找到匹配项，则返回匹配项的位置；否则返回 `None`。
+    /// 如果找到匹配项，则返回匹配项的位置，以及是否越过文档顶部回到了末尾；否则返回 `None`。
     ///
     /// # 逻辑说明
     /// 该方法从指定位置开始向上搜索，直到文档顶部，然后环绕回文档底部继续搜索，
     /// 确保当前行被搜索两次（一次从中点开始，一次从行尾开始），以捕捉所有可能的匹配。
-    pub fn search_backward(&self, query: &str, from: Location) -> Option<Location> {
+    pub fn search_backward(&self, query: &str, from: Location) -> Option<(Location, bool)> {
         if query.is_empty() {
             return None;
         }
         // 标记是否是第一次处理当前行
         let mut is_first = true;
 
-        for (line_index, line) in self
+        for (step, (line_index, line)) in self
             .lines
             .iter()
             .enumerate()
@@ -107,6 +207,7 @@ impl Buffer {
             .skip(self.lines.len().saturating_sub(from.line_index).saturating_sub(1))
             // 为了确保当前行被搜索两次（一次从中点开始，一次从行尾开始），多取一行
             .take(self.lines.len().saturating_add(1))
+            .enumerate()
         {
             // 确定当前行的起始字素索引：
             // - 如果是第一次处理当前行，则从 `from.grapheme_index` 开始；
@@ -119,42 +220,192 @@ impl Buffer {
             };
             // 在当前行中反向搜索查询字符串，如果找到匹配项，则返回匹配位置。
             if let Some(grapheme_index) = line.search_backward(query, from_grapheme_index) {
-                return Some(Location {
+                let location = Location {
                     grapheme_index,
                     line_index,
-                });
+                };
+                // 向上经过的步数超过起始行的行索引时，已经越过了第一行
+                return Some((location, step > from.line_index));
             }
         }
         None
     }
 
-    /// 保存文件内容
-    fn save_to_file(&self, file_info: &FileInfo) -> Result<(), Error> {
+    /// 按文档顺序列出查询字符串的所有匹配位置。
+    ///
+    /// 同一行内的匹配项互不重叠，与逐个向下搜索时跳过的方式一致。
+    pub fn search_all(&self, query: &str) -> Vec<Location> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line_index, line)| {
+                line.search_all(query)
+                    .into_iter()
+                    .map(move |grapheme_index| Location {
+                        grapheme_index,
+                        line_index,
+                    })
+            })
+            .collect()
+    }
+
+    /// 获取保存时写入文件的文本：使用加载时检测到的换行符，
+    /// 最后一行是否以换行符结尾取决于原文件是否以换行符结尾
+    pub fn contents(&self) -> String {
+        let terminator = self.line_ending.as_str();
+        let mut contents = String::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            contents.push_str(line);
+            if index.saturating_add(1) < self.lines.len() || self.trailing_newline {
+                contents.push_str(terminator);
+            }
+        }
+        contents
+    }
+
+    /// 保存文件内容。
+    ///
+    /// 内容先写入同一目录下的临时文件，再重命名为目标文件，写入失败时原文件保持不变。
+    /// `backup` 为 `true` 且目标文件已存在时，先将它复制为 `文件名~`。
+    fn save_to_file(&self, file_info: &FileInfo, backup: bool) -> Result<(), Error> {
         if let Some(path) = file_info.get_path() {
-            let mut file = File::create(path)?;
-            for line in &self.lines {
-                writeln!(file, "{line}")?;
+            let contents = self.contents();
+            // 先完成编码再写入文件，编码失败时不会破坏原文件
+            let encoding = file_info.encoding();
+            let bytes = encoding.encode(&contents).ok_or_else(|| {
+                Error::other(format!("Text cannot be encoded as {encoding}"))
+            })?;
+            // 目标是符号链接时写入它指向的文件，而不是用普通文件替换链接
+            let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            if backup && path.exists() {
+                fs::copy(&path, Self::sibling_path(&path, "", "~"))?;
+            }
+            let temp_path = Self::sibling_path(&path, ".", ".hecto-tmp");
+            let result = Self::write_and_replace(&temp_path, &path, &bytes);
+            if result.is_err() {
+                let _ = fs::remove_file(&temp_path);
             }
+            result?;
+        }
+        Ok(())
+    }
+
+    /// 将内容写入临时文件，再用它替换目标文件。目标文件已存在时保留它的权限
+    fn write_and_replace(temp_path: &Path, path: &Path, bytes: &[u8]) -> Result<(), Error> {
+        let mut file = File::create(temp_path)?;
+        file.write_all(bytes)?;
+        // 显式刷新并同步到磁盘，确保写入错误能被返回，而不是在文件关闭时被静默丢弃
+        file.flush()?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(temp_path, metadata.permissions())?;
+        }
+        fs::rename(temp_path, path)?;
+        // 同步所在目录，确保重命名本身也已写入磁盘。有的平台无法打开目录，此时忽略
+        if let Some(dir) = path.parent().and_then(|dir| File::open(dir).ok()) {
+            let _ = dir.sync_all();
         }
         Ok(())
     }
 
-    /// 另存为
+    /// 在同一目录下，给文件名加上前缀和后缀得到的路径
+    fn sibling_path(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+        let mut file_name = OsString::from(prefix);
+        file_name.push(path.file_name().unwrap_or_default());
+        file_name.push(suffix);
+        path.with_file_name(file_name)
+    }
+
+    /// 另存为。不会备份目标位置已有的文件
     pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        let file_info = FileInfo::from(file_name);
-        self.save_to_file(&file_info)?;
+        let mut file_info = FileInfo::from(file_name).with_encoding(self.file_info.encoding());
+        self.save_to_file(&file_info, false)?;
+        file_info.record_modified();
         self.file_info = file_info;
         self.dirty = false;
+        self.modified_lines.clear();
+        // 新文件的内容来自本缓冲区，之后保存时无需再备份
+        self.backup_made = true;
         Ok(())
     }
-    
-    /// 保存现有文件
-    pub fn save(&mut self) -> Result<(), Error> {
-        self.save_to_file(&self.file_info)?;
+
+    /// 保存现有文件。`backup` 为 `true` 时，本次打开文件后第一次保存前备份原文件
+    pub fn save(&mut self, backup: bool) -> Result<(), Error> {
+        self.save_to_file(&self.file_info, backup && !self.backup_made)?;
+        self.file_info.record_modified();
         self.dirty = false;
+        self.modified_lines.clear();
+        self.backup_made = self.backup_made || backup;
         Ok(())
     }
 
+    /// 标记缓冲区已被修改。所有修改内容的方法都需要调用它
+    fn mark_modified(&mut self) {
+        self.dirty = true;
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    /// 标记缓冲区已被修改，并记录修改过的行：`lines` 范围内的行被替换为 `inserted` 个新行。
+    ///
+    /// 范围之后已记录的行随之上下移动，新行全部记为已修改。
+    fn mark_lines_modified(&mut self, lines: Range<LineIdx>, inserted: usize) {
+        let removed = lines.len();
+        let tail = self.modified_lines.split_off(&lines.start);
+        self.modified_lines.extend(
+            tail.into_iter()
+                .filter(|line_index| *line_index >= lines.end)
+                .map(|line_index| line_index.saturating_sub(removed).saturating_add(inserted)),
+        );
+        self.modified_lines
+            .extend(lines.start..lines.start.saturating_add(inserted));
+        self.mark_modified();
+    }
+
+    /// 标记缓冲区中的一行被修改
+    fn mark_line_modified(&mut self, line_index: LineIdx) {
+        self.mark_lines_modified(line_index..line_index.saturating_add(1), 1);
+    }
+
+    /// 指定行在上次保存之后是否被修改过
+    pub fn is_line_modified(&self, line_index: LineIdx) -> bool {
+        self.modified_lines.contains(&line_index)
+    }
+
+    /// 获取字符数。按字素计数，由多个码点组成的字素算作一个字符，不包括换行符
+    pub fn char_count(&self) -> usize {
+        self.counts().0
+    }
+
+    /// 内容的修订号，每次修改后都会变化
+    pub const fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// 获取按 Unicode 单词边界划分的单词数
+    pub fn word_count(&self) -> usize {
+        self.counts().1
+    }
+
+    /// 统计字符数和单词数。内容没有变化时直接使用上次的结果
+    fn counts(&self) -> (usize, usize) {
+        if let Some((revision, chars, words)) = self.counts.get() {
+            if revision == self.revision {
+                return (chars, words);
+            }
+        }
+        let (chars, words) = self.lines.iter().fold((0_usize, 0_usize), |(chars, words), line| {
+            (
+                chars.saturating_add(line.grapheme_count()),
+                words.saturating_add(line.unicode_words().count()),
+            )
+        });
+        self.counts.set(Some((self.revision, chars, words)));
+        (chars, words)
+    }
+
     /// buffer是否为空
     pub fn is_empty(&self) -> bool {
         self.lines.is_empty()
@@ -165,10 +416,119 @@ impl Buffer {
         self.file_info.has_path()
     }
 
+    /// 查找与 `at` 处的括号配对的括号。
+    ///
+    /// 从该括号开始向后(左括号)或向前(右括号)逐行扫描，遇到同类括号时增加嵌套层数。
+    /// 高亮器标记为字符串或注释的括号会被忽略。最多扫描 `max_lines` 行(包括括号所在的行)。
+    ///
+    /// # 返回值
+    /// `at` 处不是括号，或者在扫描的行中括号没有配对时返回 `None`。
+    pub fn find_matching_bracket(
+        &self,
+        at: Location,
+        highlighter: &dyn Highlighter,
+        max_lines: usize,
+    ) -> Option<Location> {
+        let line = self.lines.get(at.line_index)?;
+        let (byte_idx, bracket) = line.grapheme_indices(true).nth(at.grapheme_index)?;
+        let (closing, forward) = match bracket {
+            "(" => (")", true),
+            "[" => ("]", true),
+            "{" => ("}", true),
+            ")" => ("(", false),
+            "]" => ("[", false),
+            "}" => ("{", false),
+            _ => return None,
+        };
+        if Self::is_in_string_or_comment(&highlighter.highlight(line), byte_idx) {
+            return None;
+        }
+
+        // 当前的嵌套层数，起始的括号本身算作第一层
+        let mut depth: usize = 0;
+        let line_indices: Box<dyn Iterator<Item = LineIdx>> = if forward {
+            Box::new(at.line_index..self.height())
+        } else {
+            Box::new((0..=at.line_index).rev())
+        };
+        for line_index in line_indices.take(max_lines) {
+            let line = self.lines.get(line_index)?;
+            let highlights = highlighter.highlight(line);
+            let mut graphemes: Vec<(GraphemeIdx, (ByteIdx, &str))> =
+                line.grapheme_indices(true).enumerate().collect();
+            if line_index == at.line_index {
+                // 只扫描起始括号及其之后(向前扫描时为之前)的字素
+                if forward {
+                    graphemes.drain(..at.grapheme_index);
+                } else {
+                    graphemes.truncate(at.grapheme_index.saturating_add(1));
+                }
+            }
+            if !forward {
+                graphemes.reverse();
+            }
+            for (grapheme_index, (byte_idx, grapheme)) in graphemes {
+                if grapheme != bracket && grapheme != closing
+                    || Self::is_in_string_or_comment(&highlights, byte_idx)
+                {
+                    continue;
+                }
+                if grapheme == bracket {
+                    depth = depth.saturating_add(1);
+                } else {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return Some(Location { grapheme_index, line_index });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// 判断字节索引处的内容是否位于字符串或注释中
+    fn is_in_string_or_comment(highlights: &[Annotation], byte_idx: ByteIdx) -> bool {
+        highlights.iter().any(|annotation| {
+            matches!(annotation.annotation_type, AnnotationType::String | AnnotationType::Comment)
+                && annotation.start <= byte_idx
+                && byte_idx < annotation.end
+        })
+    }
+
+    /// 在文件开头和结尾的几行中查找 modeline，使用最先找到的一个
+    pub fn find_modeline(&self) -> Option<Modeline> {
+        let height = self.height();
+        let head = 0..modeline::SCANNED_LINES.min(height);
+        // 文件较短时避免重复扫描开头的行
+        let tail = height.saturating_sub(modeline::SCANNED_LINES).max(head.end)..height;
+        head.chain(tail)
+            .find_map(|line_index| Modeline::parse(self.line_text(line_index)))
+    }
+
+    /// 设置制表符宽度，所有行会按新的宽度重新计算渲染宽度
+    ///
+    /// # 返回值
+    /// 宽度没有变化时不做任何处理，返回 `false`。
+    pub fn set_tab_width(&mut self, tab_width: ColIdx) -> bool {
+        if self.tab_width == tab_width {
+            return false;
+        }
+        self.tab_width = tab_width;
+        for line in &mut self.lines {
+            line.set_tab_width(tab_width);
+        }
+        true
+    }
+
     pub fn height(&self) -> usize {
         self.lines.len()
     }
 
+    /// 获取指定行的纯文本内容，行不存在时返回空字符串
+    pub fn line_text(&self, line_index: LineIdx) -> &str {
+        self.lines.get(line_index).map_or("", |line| line)
+    }
+
     // 插入字符
     pub fn insert_char(&mut self, character: char, at: Location) {
         // if at.line_index > self.height() {
@@ -176,11 +536,11 @@ impl Buffer {
         // }
         debug_assert!(at.line_index <= self.height());
         if at.line_index == self.height() {
-            self.lines.push(Line::from(&character.to_string()));
-            self.dirty = true;
+            self.lines.push(Line::new(&character.to_string(), self.tab_width));
+            self.mark_lines_modified(at.line_index..at.line_index, 1);
         } else if let Some(line) = self.lines.get_mut(at.line_index) {
             line.insert_char(character, at.grapheme_index);
-            self.dirty = true;
+            self.mark_line_modified(at.line_index);
         }
     }
     
@@ -196,25 +556,713 @@ impl Buffer {
                 // 安全性：由于我们已经检查了下一行的存在，因此可以安全地使用索引访问。
                 #[allow(clippy::indexing_slicing)]
                 self.lines[at.line_index].append(&next_line);
-                self.dirty = true;
+                self.mark_lines_modified(at.line_index..at.line_index.saturating_add(2), 1);
             } else if at.grapheme_index < line.grapheme_count() {
                 // 删除指定位置的字符
                 #[allow(clippy::indexing_slicing)]
                 self.lines[at.line_index].delete(at.grapheme_index);
-                self.dirty = true;
+                self.mark_line_modified(at.line_index);
             }
             // 如果删除位置超出了当前行的长度，但没有下一行可合并，则不做任何操作
         }
     }
 
-    pub fn insert_newline(&mut self, at: Location) {
+    /// 在分隔符出现的每个位置拆分指定行，分隔符本身会被移除。
+    ///
+    /// # 返回值
+    /// 返回拆分后得到的行数。分隔符为空或行不存在时返回 0。
+    pub fn split_line_at(&mut self, line_index: LineIdx, delimiter: &str) -> usize {
+        if delimiter.is_empty() || line_index >= self.height() {
+            return 0;
+        }
+        let parts: Vec<Line> = self
+            .line_text(line_index)
+            .split(delimiter)
+            .map(|part| Line::new(part, self.tab_width))
+            .collect();
+        let count = parts.len();
+        // 只有找到分隔符时才真正修改缓冲区
+        if count > 1 {
+            self.lines.splice(line_index..=line_index, parts);
+            self.mark_lines_modified(line_index..line_index.saturating_add(1), count);
+        }
+        count
+    }
+
+    /// 在指定位置一次性插入一段可能包含多行的文本。
+    ///
+    /// 文本按 `\n` 拆分后直接构建新行，而不是逐个字符插入，适合粘贴大段内容。
+    ///
+    /// # 返回值
+    /// 返回插入内容末尾的位置。
+    pub fn insert_text(&mut self, at: Location, text: &str) -> Location {
+        if text.is_empty() || at.line_index > self.height() {
+            return at;
+        }
+        // 在最后一行之后插入时，先补上一个空行，它不算作被替换的行
+        let replaced = if at.line_index == self.height() {
+            self.lines.push(Line::new("", self.tab_width));
+            0
+        } else {
+            1
+        };
+        let tab_width = self.tab_width;
+        let Some(line) = self.lines.get_mut(at.line_index) else {
+            return at;
+        };
+        // 插入位置之后的内容会接到最后一段文本的后面
+        let tail = line.split(at.grapheme_index);
+        let mut parts = text.split('\n');
+        line.append(&Line::new(parts.next().unwrap_or_default(), tab_width));
+        let mut new_lines: Vec<Line> = parts.map(|part| Line::new(part, tab_width)).collect();
+        let end_line_index = at.line_index.saturating_add(new_lines.len());
+        let last_line = new_lines.last_mut().unwrap_or(line);
+        let end = Location {
+            line_index: end_line_index,
+            grapheme_index: last_line.grapheme_count(),
+        };
+        last_line.append(&tail);
+        let insert_at = at.line_index.saturating_add(1);
+        let inserted = new_lines.len().saturating_add(1);
+        self.lines.splice(insert_at..insert_at, new_lines);
+        self.mark_lines_modified(at.line_index..at.line_index.saturating_add(replaced), inserted);
+        end
+    }
+
+    /// 切换 `start` 到 `end` (包含)之间所有行的注释状态。
+    ///
+    /// 如果所有非空行都已经以 `token` 开头(忽略缩进)，则删除这些注释符号及其后的一个空格；
+    /// 否则在所有非空行的最小公共缩进处插入 `token` 和一个空格，保留各行之间相对的缩进。
+    /// 空行保持不变。
+    ///
+    /// # 返回值
+    /// 返回每一行的修改，用于调整光标和选区的位置。
+    pub fn toggle_block_comment(
+        &mut self,
+        start: LineIdx,
+        end: LineIdx,
+        token: &str,
+    ) -> Vec<LineEdit> {
+        let end = end.min(self.height().saturating_sub(1));
+        if token.is_empty() || start > end {
+            return Vec::new();
+        }
+        let non_blank: Vec<LineIdx> = (start..=end)
+            .filter(|line_index| !self.line_text(*line_index).trim().is_empty())
+            .collect();
+        let token_len = Line::from(token).grapheme_count();
+        let all_commented = !non_blank.is_empty()
+            && non_blank.iter().all(|line_index| {
+                self.line_text(*line_index)
+                    .trim_start_matches([' ', '\t'])
+                    .starts_with(token)
+            });
+        // 缩进只包含空格和制表符，字节数与字素数量相同
+        let common_indent = non_blank
+            .iter()
+            .filter_map(|line_index| self.lines.get(*line_index))
+            .map(|line| line.indentation().len())
+            .min()
+            .unwrap_or(0);
+
+        let mut edits = Vec::new();
+        for line_index in non_blank {
+            let Some(line) = self.lines.get_mut(line_index) else {
+                continue;
+            };
+            let edit = if all_commented {
+                let at = line.indentation().len();
+                let followed_by_space = line
+                    .get(at.saturating_add(token.len())..)
+                    .is_some_and(|rest| rest.starts_with(' '));
+                let removed = token_len.saturating_add(usize::from(followed_by_space));
+                line.delete_range(at..at.saturating_add(removed));
+                LineEdit { line_index, at, removed, inserted: 0 }
+            } else {
+                line.insert_str(&format!("{token} "), common_indent);
+                LineEdit {
+                    line_index,
+                    at: common_indent,
+                    removed: 0,
+                    inserted: token_len.saturating_add(1),
+                }
+            };
+            edits.push(edit);
+        }
+        for edit in &edits {
+            self.mark_line_modified(edit.line_index);
+        }
+        edits
+    }
+
+    /// 复制指定的行，插入到它的下一行。
+    ///
+    /// # 返回值
+    /// 行不存在(例如空缓冲区)时不做任何处理，返回 `false`。
+    pub fn duplicate_line(&mut self, line_index: LineIdx) -> bool {
+        let Some(line) = self.lines.get(line_index) else {
+            return false;
+        };
+        let copy = line.clone();
+        let insert_at = line_index.saturating_add(1);
+        self.lines.insert(insert_at, copy);
+        self.mark_lines_modified(insert_at..insert_at, 1);
+        true
+    }
+
+    /// 删除指定的行。
+    ///
+    /// # 返回值
+    /// 行不存在时不做任何处理，返回 `false`。
+    pub fn delete_line(&mut self, line_index: LineIdx) -> bool {
+        if line_index >= self.height() {
+            return false;
+        }
+        self.lines.remove(line_index);
+        self.mark_lines_modified(line_index..line_index.saturating_add(1), 0);
+        true
+    }
+
+    /// 交换指定的行与它的上一行(`up` 为 `true` 时)或下一行。
+    ///
+    /// # 返回值
+    /// 行不存在，或者已经位于文档开头/结尾而无法移动时返回 `false`。
+    pub fn move_line(&mut self, line_index: LineIdx, up: bool) -> bool {
+        let target = if up {
+            line_index.checked_sub(1)
+        } else {
+            Some(line_index.saturating_add(1))
+        };
+        match target {
+            Some(target) if line_index < self.height() && target < self.height() => {
+                self.lines.swap(line_index, target);
+                self.mark_line_modified(line_index);
+                self.mark_line_modified(target);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 逐行切换 `start` 到 `end` (包含)之间每个非空行的注释。
+    ///
+    /// 已经注释的行删除缩进之后的注释符号(以及紧随其后的一个空格)，
+    /// 其他行在缩进之后插入注释符号和一个空格。
+    pub fn toggle_line_comments(&mut self, start: LineIdx, end: LineIdx, token: &str) -> Vec<LineEdit> {
+        if token.is_empty() {
+            return Vec::new();
+        }
+        let token_len = Line::from(token).grapheme_count();
+        let mut edits = Vec::new();
+        for line_index in start..=end.min(self.height().saturating_sub(1)) {
+            let Some(line) = self.lines.get_mut(line_index) else {
+                continue;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            // 缩进只包含空格和制表符，字节数与字素数量相同
+            let at = line.indentation().len();
+            let rest = line.get(at..).unwrap_or_default();
+            let edit = if let Some(after_token) = rest.strip_prefix(token) {
+                let removed = token_len.saturating_add(usize::from(after_token.starts_with(' ')));
+                line.delete_range(at..at.saturating_add(removed));
+                LineEdit { line_index, at, removed, inserted: 0 }
+            } else {
+                line.insert_str(&format!("{token} "), at);
+                LineEdit { line_index, at, removed: 0, inserted: token_len.saturating_add(1) }
+            };
+            edits.push(edit);
+        }
+        for edit in &edits {
+            self.mark_line_modified(edit.line_index);
+        }
+        edits
+    }
+
+    /// 在 `start` 到 `end` (包含)之间每个非空行的开头插入 `unit`
+    pub fn indent_lines(&mut self, start: LineIdx, end: LineIdx, unit: &str) -> Vec<LineEdit> {
+        let inserted = Line::from(unit).grapheme_count();
+        let mut edits = Vec::new();
+        for line_index in start..=end.min(self.height().saturating_sub(1)) {
+            let Some(line) = self.lines.get_mut(line_index) else {
+                continue;
+            };
+            if line.is_empty() {
+                continue;
+            }
+            line.insert_str(unit, 0);
+            edits.push(LineEdit { line_index, at: 0, removed: 0, inserted });
+        }
+        for edit in &edits {
+            self.mark_line_modified(edit.line_index);
+        }
+        edits
+    }
+
+    /// 删除 `start` 到 `end` (包含)之间每一行开头的空白，每行最多删除制表符宽度列
+    pub fn dedent_lines(&mut self, start: LineIdx, end: LineIdx) -> Vec<LineEdit> {
+        let tab_width = self.tab_width;
+        let mut edits = Vec::new();
+        for line_index in start..=end.min(self.height().saturating_sub(1)) {
+            let Some(line) = self.lines.get_mut(line_index) else {
+                continue;
+            };
+            let mut column: ColIdx = 0;
+            let mut removed: GraphemeIdx = 0;
+            for character in line.indentation().chars() {
+                if column >= tab_width {
+                    break;
+                }
+                // 制表符一直延伸到下一个制表位
+                let width = if character == '\t' {
+                    tab_width.saturating_sub(column.checked_rem(tab_width).unwrap_or(0))
+                } else {
+                    1
+                };
+                column = column.saturating_add(width);
+                removed = removed.saturating_add(1);
+            }
+            if removed == 0 {
+                continue;
+            }
+            line.delete_range(0..removed);
+            edits.push(LineEdit { line_index, at: 0, removed, inserted: 0 });
+        }
+        for edit in &edits {
+            self.mark_line_modified(edit.line_index);
+        }
+        edits
+    }
+
+    /// 行首的缩进中是否混用了制表符和空格：有的行用制表符缩进而有的行用空格缩进，
+    /// 或者同一行的缩进中两者都有。只包含空白的行不计算在内
+    pub fn has_mixed_indentation(&self) -> bool {
+        let mut tabs = false;
+        let mut spaces = false;
+        for line in self.lines.iter().filter(|line| !line.trim().is_empty()) {
+            let indentation = line.indentation();
+            tabs = tabs || indentation.contains('\t');
+            spaces = spaces || indentation.contains(' ');
+            if tabs && spaces {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 将每一行的缩进统一为空格(`use_spaces` 为 `true` 时)或制表符，缩进的宽度保持不变。
+    ///
+    /// 只修改行首的空白，只包含空白的行保持不变。转换为制表符时，不足一个制表符宽度的部分仍使用空格。
+    ///
+    /// # 返回值
+    /// 返回被修改的每一行的修改记录，没有任何修改时返回空列表，也不会标记为已修改。
+    pub fn normalize_indentation(&mut self, use_spaces: bool) -> Vec<LineEdit> {
+        let tab_width = self.tab_width.max(1);
+        let mut edits = Vec::new();
+        for (line_index, line) in self.lines.iter_mut().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            // 缩进只包含空格和制表符，字节数与字素数量相同
+            let removed = line.indentation().len();
+            let width = line.width_until(removed);
+            let normalized = if use_spaces {
+                " ".repeat(width)
+            } else {
+                let tabs = width.checked_div(tab_width).unwrap_or(0);
+                let spaces = width.checked_rem(tab_width).unwrap_or(0);
+                format!("{}{}", "\t".repeat(tabs), " ".repeat(spaces))
+            };
+            if line.indentation() == normalized {
+                continue;
+            }
+            line.delete_range(0..removed);
+            line.insert_str(&normalized, 0);
+            edits.push(LineEdit { line_index, at: 0, removed, inserted: normalized.len() });
+        }
+        for edit in &edits {
+            self.mark_line_modified(edit.line_index);
+        }
+        edits
+    }
+
+    /// 删除每一行末尾的空格和制表符。
+    ///
+    /// # 返回值
+    /// 返回被修改的每一行的修改记录，没有任何修改时返回空列表，也不会标记为已修改。
+    pub fn trim_trailing_whitespace(&mut self) -> Vec<LineEdit> {
+        let mut edits = Vec::new();
+        for (line_index, line) in self.lines.iter_mut().enumerate() {
+            // 空格和制表符都是单字节的字素，字节数与字素数量相同
+            let removed = line.len().saturating_sub(line.trim_end_matches([' ', '\t']).len());
+            if removed == 0 {
+                continue;
+            }
+            let at = line.grapheme_count().saturating_sub(removed);
+            line.delete_range(at..at.saturating_add(removed));
+            edits.push(LineEdit { line_index, at, removed, inserted: 0 });
+        }
+        for edit in &edits {
+            self.mark_line_modified(edit.line_index);
+        }
+        edits
+    }
+
+    /// 删除文档末尾的空白行(为空或只有空白字符的行)，并让文件以一个换行符结尾。
+    ///
+    /// # 返回值
+    /// 有任何修改时返回 `true` 并标记为已修改。
+    pub fn trim_trailing_blank_lines(&mut self) -> bool {
+        let blank_lines = self
+            .lines
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        let height = self.height();
+        let len = height.saturating_sub(blank_lines);
+        self.lines.splice(len.., []);
+        // 删除了所有行时文件为空，不需要换行符
+        let needs_newline = !self.is_empty() && !self.trailing_newline;
+        if needs_newline {
+            self.trailing_newline = true;
+        }
+        let changed = blank_lines > 0 || needs_newline;
+        if changed {
+            self.mark_lines_modified(len..height, 0);
+        }
+        changed
+    }
+
+    /// 用分隔符将 `start` 到 `end` (包含)之间的所有行合并为一行
+    pub fn join_lines(&mut self, start: LineIdx, end: LineIdx, separator: &str) {
+        let end = end.min(self.height().saturating_sub(1));
+        if start >= end {
+            return;
+        }
+        let joined = (start..=end)
+            .filter_map(|line_index| self.lines.get(line_index))
+            .map(|line| &**line)
+            .collect::<Vec<&str>>()
+            .join(separator);
+        self.lines.splice(start..=end, [Line::new(&joined, self.tab_width)]);
+        self.mark_lines_modified(start..end.saturating_add(1), 1);
+    }
+
+    /// 按内容排序 `start` 到 `end` (包含)之间的行，`ignore_case` 为 `true` 时不区分大小写。
+    /// 排序是稳定的，内容相同的行保持原来的顺序。
+    ///
+    /// # 返回值
+    /// 行的顺序有变化时返回 `true` 并标记为已修改。
+    pub fn sort_lines(&mut self, start: LineIdx, end: LineIdx, ignore_case: bool) -> bool {
+        let mut lines = self.lines_between(start, end);
+        lines.sort_by_cached_key(|line| if ignore_case { line.to_lowercase() } else { line.to_string() });
+        self.replace_lines(start, lines)
+    }
+
+    /// 将 `start` 到 `end` (包含)之间的行倒序排列。
+    ///
+    /// # 返回值
+    /// 行的顺序有变化时返回 `true` 并标记为已修改。
+    pub fn reverse_lines(&mut self, start: LineIdx, end: LineIdx) -> bool {
+        let mut lines = self.lines_between(start, end);
+        lines.reverse();
+        self.replace_lines(start, lines)
+    }
+
+    /// 复制 `start` 到 `end` (包含)之间的行
+    fn lines_between(&self, start: LineIdx, end: LineIdx) -> Vec<Line> {
+        (start..=end)
+            .filter_map(|line_index| self.lines.get(line_index))
+            .cloned()
+            .collect()
+    }
+
+    /// 用重新排列后的行替换从 `start` 开始的同样数量的行，内容没有变化时不做修改
+    fn replace_lines(&mut self, start: LineIdx, lines: Vec<Line>) -> bool {
+        let end = start.saturating_add(lines.len());
+        let unchanged = lines
+            .iter()
+            .zip(start..end)
+            .all(|(line, line_index)| self.lines.get(line_index).is_some_and(|old| **old == **line));
+        if unchanged {
+            return false;
+        }
+        let count = lines.len();
+        self.lines.splice(start..end, lines);
+        self.mark_lines_modified(start..end, count);
+        true
+    }
+
+    /// 将下一行合并到指定行的末尾：去掉下一行开头的缩进，两行之间用一个空格分隔。
+    /// 指定行为空、以空白结尾，或下一行去掉缩进后为空时不添加空格。
+    ///
+    /// # 返回值
+    /// 返回合并点(原来的行尾)的字素索引。指定行是最后一行时不做任何操作并返回 `None`。
+    pub fn join_next_line(&mut self, line_index: LineIdx) -> Option<GraphemeIdx> {
+        let next_index = line_index.saturating_add(1);
+        let next_line = self.lines.get(next_index)?;
+        let indentation = next_line.indentation().len();
+        let next_is_blank = indentation == next_line.grapheme_count();
+        let line = self.lines.get(line_index)?;
+        let join_point = line.grapheme_count();
+        let needs_separator = !next_is_blank
+            && !line.is_empty()
+            && !line.ends_with(|character: char| character.is_whitespace());
+        // 缩进只包含空格和制表符，每个字符都是一个字素
+        self.delete_range(next_index, 0..indentation);
+        if needs_separator {
+            self.insert_char(' ', Location { grapheme_index: join_point, line_index });
+        }
+        // 复用删除行尾时合并两行的逻辑
+        let line_end = self.lines.get(line_index).map_or(join_point, Line::grapheme_count);
+        self.delete(Location { grapheme_index: line_end, line_index });
+        Some(join_point)
+    }
+
+    /// 删除指定行中某个范围内的字素，不会合并下一行
+    pub fn delete_range(&mut self, line_index: LineIdx, range: Range<GraphemeIdx>) {
+        if range.start >= range.end {
+            return;
+        }
+        if let Some(line) = self.lines.get_mut(line_index) {
+            line.delete_range(range);
+            self.mark_line_modified(line_index);
+        }
+    }
+
+    /// 是否位于文档末尾(最后一行的行尾，或最后一行之后)
+    pub fn is_at_end(&self, at: Location) -> bool {
+        at.line_index >= self.height()
+            || (at.line_index.saturating_add(1) == self.height()
+                && at.grapheme_index >= self.lines.get(at.line_index).map_or(0, Line::grapheme_count))
+    }
+
+    /// 在指定位置插入换行。
+    ///
+    /// # 参数
+    /// - `at`: 插入换行的位置。
+    /// - `skip_at_end`: 为 `true` 时，在文档末尾插入换行不做任何操作，避免产生多余的空行。
+    ///
+    /// # 返回值
+    /// 返回是否插入了换行。
+    pub fn insert_newline(&mut self, at: Location, skip_at_end: bool) -> bool {
+        if skip_at_end && self.is_at_end(at) {
+            return false;
+        }
         if at.line_index == self.height() {
-            self.lines.push(Line::default());
-            self.dirty = true;
+            self.lines.push(Line::new("", self.tab_width));
+            self.mark_lines_modified(at.line_index..at.line_index, 1);
+            true
         } else if let Some(line) = self.lines.get_mut(at.line_index) {
             let new = line.split(at.grapheme_index);
             self.lines.insert(at.line_index.saturating_add(1), new);
-            self.dirty = true;
+            self.mark_lines_modified(at.line_index..at.line_index.saturating_add(1), 2);
+            true
+        } else {
+            false
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    /// 在临时目录中写入文件，返回它的路径
+    fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = env::temp_dir().join(format!("hecto-test-{}-{name}", process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// 加载文件后不做修改直接保存，返回保存后的文件内容
+    fn load_and_save(name: &str, contents: &[u8]) -> Vec<u8> {
+        let path = temp_file(name, contents);
+        let mut buffer = Buffer::load(path.to_str().unwrap(), encoding_rs::WINDOWS_1252).unwrap();
+        buffer.save(false).unwrap();
+        let saved = read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        saved
+    }
+
+    #[test]
+    fn load_as_forces_encoding_and_rejects_invalid_bytes() {
+        // “é” 的 UTF-8 编码被当作 Latin-1 时显示为两个字符
+        let path = temp_file("forced", "é".as_bytes());
+        let file_name = path.to_str().unwrap();
+        let buffer = Buffer::load_as(file_name, encoding_rs::WINDOWS_1252).unwrap();
+        assert_eq!(buffer.line_text(0), "Ã©");
+        assert_eq!(buffer.file_info.encoding().to_string(), "windows-1252");
+        let buffer = Buffer::load_as(file_name, encoding_rs::UTF_8).unwrap();
+        assert_eq!(buffer.line_text(0), "é");
+        fs::write(&path, b"\xe9").unwrap();
+        let err = Buffer::load_as(file_name, encoding_rs::UTF_8).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn search_reports_wrapping_past_document_boundary() {
+        let at = |line_index, grapheme_index| Location { grapheme_index, line_index };
+        let buffer = Buffer::from("ab\nab");
+        assert_eq!(buffer.search_forward("b", at(0, 0)), Some((at(0, 1), false)));
+        assert_eq!(buffer.search_forward("b", at(1, 2)), Some((at(0, 1), true)));
+        assert_eq!(buffer.search_backward("a", at(1, 1)), Some((at(1, 0), false)));
+        assert_eq!(buffer.search_backward("a", at(0, 0)), Some((at(1, 0), true)));
+        // 唯一的匹配项在起始行中起始位置之前时，也要越过文档末尾才能找到
+        let buffer = Buffer::from("xa");
+        assert_eq!(buffer.search_forward("a", at(0, 2)), Some((at(0, 1), true)));
+    }
+
+    #[test]
+    fn search_all_finds_non_overlapping_matches_within_lines() {
+        let at = |line_index, grapheme_index| Location { grapheme_index, line_index };
+        let buffer = Buffer::from("aaaa\naXa\n你a你a");
+        // 重叠的匹配项只取前一个，与逐个向下搜索一致
+        assert_eq!(buffer.search_all("aa"), vec![at(0, 0), at(0, 2)]);
+        assert_eq!(buffer.search_all("a"), vec![at(0, 0), at(0, 1), at(0, 2), at(0, 3), at(1, 0), at(1, 2), at(2, 1), at(2, 3)]);
+        // 匹配项不会跨越行，比行更长的查询没有匹配项
+        assert!(buffer.search_all("a\na").is_empty());
+        assert!(buffer.search_all("aaaaa").is_empty());
+        assert!(buffer.search_all("").is_empty());
+    }
+
+    #[test]
+    fn save_keeps_missing_trailing_newline() {
+        assert_eq!(load_and_save("no-newline", b"first\nlast"), b"first\nlast");
+    }
+
+    #[test]
+    fn save_keeps_trailing_newline_and_line_endings() {
+        assert_eq!(load_and_save("newline", b"first\nlast\n"), b"first\nlast\n");
+        assert_eq!(load_and_save("crlf", b"first\r\nlast"), b"first\r\nlast");
+        assert_eq!(load_and_save("empty", b""), b"");
+    }
+
+    #[test]
+    fn edited_file_round_trips_line_endings() {
+        for (name, contents, expected) in [
+            ("edit-newline", "a\nb\n", "xa\nb\n"),
+            ("edit-no-newline", "a\nb", "xa\nb"),
+            ("edit-crlf", "a\r\nb\r\n", "xa\r\nb\r\n"),
+            ("edit-crlf-no-newline", "a\r\nb", "xa\r\nb"),
+            ("edit-lone-newline", "\n", "x\n"),
+            ("edit-empty", "", "x"),
+        ] {
+            let path = temp_file(name, contents.as_bytes());
+            let mut buffer = Buffer::load(path.to_str().unwrap(), encoding_rs::WINDOWS_1252).unwrap();
+            buffer.insert_char('x', Location { grapheme_index: 0, line_index: 0 });
+            buffer.save(false).unwrap();
+            assert_eq!(read(&path).unwrap(), expected.as_bytes(), "{name}");
+            // 再次加载后保存，内容不变
+            let mut buffer = Buffer::load(path.to_str().unwrap(), encoding_rs::WINDOWS_1252).unwrap();
+            buffer.save(false).unwrap();
+            assert_eq!(read(&path).unwrap(), expected.as_bytes(), "{name}");
+            fs::remove_file(&path).unwrap();
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn save_as_writes_contents_before_returning() {
+        let path = env::temp_dir().join(format!("hecto-test-{}-save-as", process::id()));
+        let mut buffer = Buffer::from("hello\nworld");
+        buffer.insert_char('!', Location { grapheme_index: 5, line_index: 1 });
+        assert!(buffer.save_as(path.to_str().unwrap()).is_ok());
+        assert!(!buffer.dirty);
+        assert_eq!(read(&path).unwrap(), b"hello\nworld!");
+        fs::remove_file(&path).unwrap();
+        // 无法写入时返回错误，缓冲区仍标记为已修改
+        let missing = env::temp_dir().join(format!("hecto-test-{}-missing/file", process::id()));
+        buffer.insert_char('?', Location { grapheme_index: 0, line_index: 0 });
+        assert!(buffer.save_as(missing.to_str().unwrap()).is_err());
+        assert!(buffer.dirty);
+    }
+
+    #[test]
+    fn split_line_at_every_delimiter() {
+        let mut buffer = Buffer::from("first\na,b,c\nlast");
+        assert_eq!(buffer.split_line_at(1, ","), 3);
+        assert_eq!(buffer.contents(), "first\na\nb\nc\nlast");
+        assert!(buffer.is_line_modified(1) && buffer.is_line_modified(3));
+        // 没有分隔符时保持不变
+        assert_eq!(buffer.split_line_at(0, ","), 1);
+        assert_eq!(buffer.split_line_at(0, ""), 0);
+        assert_eq!(buffer.split_line_at(9, ","), 0);
+        assert_eq!(buffer.height(), 5);
+    }
+
+    #[test]
+    fn modeline_is_only_read_near_start_and_end() {
+        let filler = "text\n".repeat(modeline::SCANNED_LINES);
+        let buffer = Buffer::from(format!("{filler}# vim: ts=2\n{filler}").as_str());
+        assert_eq!(buffer.find_modeline(), None);
+        let buffer = Buffer::from(format!("{filler}{filler}# vim: ts=2").as_str());
+        assert_eq!(buffer.find_modeline().and_then(|modeline| modeline.tab_width), Some(2));
+        let buffer = Buffer::from(format!("# vim: ts=8\n{filler}{filler}").as_str());
+        assert_eq!(buffer.find_modeline().and_then(|modeline| modeline.tab_width), Some(8));
+    }
+
+    #[test]
+    fn join_next_line_trims_indentation_and_adds_one_space() {
+        let mut buffer = Buffer::from("foo\n    bar\nlast");
+        assert_eq!(buffer.join_next_line(0), Some(3));
+        assert_eq!(buffer.contents(), "foo bar\nlast");
+        assert_eq!(buffer.join_next_line(1), None);
+    }
+
+    #[test]
+    fn join_next_line_without_separator() {
+        // 当前行以空白结尾或下一行为空时不添加空格
+        let mut buffer = Buffer::from("foo \nbar\nbaz\n  ");
+        assert_eq!(buffer.join_next_line(0), Some(4));
+        assert_eq!(buffer.join_next_line(1), Some(3));
+        assert_eq!(buffer.contents(), "foo bar\nbaz");
+        assert!(buffer.dirty);
+    }
+
+    #[test]
+    fn normalize_indentation_only_touches_leading_whitespace() {
+        let mut buffer = Buffer::from("\tif x {\n        a(\"\t \");\n\t  b();\n \t\n}");
+        assert!(buffer.has_mixed_indentation());
+        assert_eq!(buffer.normalize_indentation(true).len(), 2);
+        assert_eq!(buffer.contents(), "    if x {\n        a(\"\t \");\n      b();\n \t\n}");
+        assert!(!buffer.has_mixed_indentation());
+        assert!(buffer.dirty);
+        // 转换为制表符时，不足一个制表符宽度的部分仍使用空格
+        assert_eq!(buffer.normalize_indentation(false).len(), 3);
+        assert_eq!(buffer.contents(), "\tif x {\n\t\ta(\"\t \");\n\t  b();\n \t\n}");
+        assert!(Buffer::from("\ta\n\t\tb\n").normalize_indentation(false).is_empty());
+    }
+
+    #[test]
+    fn sort_and_reverse_lines_in_range() {
+        let mut buffer = Buffer::from("first\nb\nC\na\nlast");
+        assert!(buffer.sort_lines(1, 3, false));
+        assert_eq!(buffer.contents(), "first\nC\na\nb\nlast");
+        assert!(buffer.sort_lines(1, 3, true));
+        assert_eq!(buffer.contents(), "first\na\nb\nC\nlast");
+        // 已经有序时不做修改
+        assert!(!buffer.sort_lines(1, 3, true));
+        assert!(buffer.reverse_lines(0, 4));
+        assert_eq!(buffer.contents(), "last\nC\nb\na\nfirst");
+        assert!(!buffer.reverse_lines(2, 2));
+    }
+
+    #[test]
+    fn modified_lines_shift_with_inserted_and_removed_lines() {
+        let mut buffer = Buffer::from("a\nb\nc\nd");
+        buffer.insert_char('x', Location { grapheme_index: 1, line_index: 2 });
+        assert!(buffer.is_line_modified(2));
+        // 在上面插入一行后，已修改的行随之下移
+        buffer.insert_newline(Location { grapheme_index: 1, line_index: 0 }, false);
+        assert!(buffer.is_line_modified(0) && buffer.is_line_modified(1));
+        assert!(!buffer.is_line_modified(2));
+        assert!(buffer.is_line_modified(3));
+        // 删除一行后，下面的行随之上移
+        assert!(buffer.delete_line(1));
+        assert!(buffer.is_line_modified(2));
+        assert!(!buffer.is_line_modified(3));
+    }
+}
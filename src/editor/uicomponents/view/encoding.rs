@@ -0,0 +1,125 @@
+use std::fmt::{self, Display};
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// 无法识别文件编码时默认使用的编码(Latin-1 的超集)
+pub const DEFAULT_FALLBACK: &Encoding = WINDOWS_1252;
+
+/// 文件的文本编码
+///
+/// 缓冲区内部始终使用 UTF-8 的 `String`，只有在读写文件时才进行编码转换。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TextEncoding {
+    // 编码
+    encoding: &'static Encoding,
+    // 文件开头是否带有 BOM，保存时按原样写回
+    bom: bool,
+}
+
+impl Default for TextEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+impl TextEncoding {
+    /// 根据标签(如 "latin1"、"utf-8")查找编码
+    pub fn for_label(label: &str) -> Option<&'static Encoding> {
+        Encoding::for_label(label.trim().as_bytes())
+    }
+
+    /// 将文件内容解码为 UTF-8 字符串。
+    ///
+    /// # 参数
+    /// - `bytes`: 文件的原始字节。
+    /// - `fallback`: 没有 BOM 且不是合法 UTF-8 时使用的编码。
+    ///
+    /// # 返回值
+    /// 返回解码后的字符串、检测到的编码，以及解码是否有损(存在无法解码的字节)。
+    pub fn decode(bytes: &[u8], fallback: &'static Encoding) -> (String, Self, bool) {
+        // 优先根据 BOM 判断编码
+        if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+            let (text, lossy) =
+                encoding.decode_without_bom_handling(bytes.get(bom_len..).unwrap_or_default());
+            return (text.into_owned(), Self { encoding, bom: true }, lossy);
+        }
+        // 其次尝试 UTF-8
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return (text.to_string(), Self::default(), false);
+        }
+        // 最后使用配置的默认编码
+        let (text, lossy) = fallback.decode_without_bom_handling(bytes);
+        (
+            text.into_owned(),
+            Self {
+                encoding: fallback,
+                bom: false,
+            },
+            lossy,
+        )
+    }
+
+    /// 按指定的编码解码文件内容，不再自动检测编码。
+    ///
+    /// 文件开头有该编码的 BOM 时跳过 BOM，保存时写回；其他编码的 BOM 按普通字节解码。
+    ///
+    /// # 返回值
+    /// 返回解码后的字符串、使用的编码，以及解码是否有损(存在无法解码的字节)。
+    pub fn decode_as(bytes: &[u8], encoding: &'static Encoding) -> (String, Self, bool) {
+        let bom_len = Encoding::for_bom(bytes)
+            .filter(|(bom_encoding, _)| *bom_encoding == encoding)
+            .map_or(0, |(_, bom_len)| bom_len);
+        let (text, lossy) = encoding.decode_without_bom_handling(bytes.get(bom_len..).unwrap_or_default());
+        (text.into_owned(), Self { encoding, bom: bom_len > 0 }, lossy)
+    }
+
+    /// 将 UTF-8 字符串按当前编码重新编码为字节。
+    ///
+    /// # 返回值
+    /// 如果字符串中有无法用当前编码表示的字符，则返回 `None`，以免写入错误的字节。
+    pub fn encode(self, text: &str) -> Option<Vec<u8>> {
+        let mut bytes = Vec::new();
+        if self.bom {
+            bytes.extend_from_slice(self.bom_bytes());
+        }
+        // encoding_rs 不支持编码为 UTF-16，需要手动处理
+        if self.encoding == UTF_16LE {
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        } else if self.encoding == UTF_16BE {
+            bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        } else {
+            let (encoded, _, unmappable) = self.encoding.encode(text);
+            if unmappable {
+                return None;
+            }
+            bytes.extend_from_slice(&encoded);
+        }
+        Some(bytes)
+    }
+
+    /// 获取编码对应的 BOM
+    fn bom_bytes(self) -> &'static [u8] {
+        if self.encoding == UTF_16LE {
+            &[0xFF, 0xFE]
+        } else if self.encoding == UTF_16BE {
+            &[0xFE, 0xFF]
+        } else if self.encoding == UTF_8 {
+            &[0xEF, 0xBB, 0xBF]
+        } else {
+            &[]
+        }
+    }
+}
+
+impl Display for TextEncoding {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.bom {
+            write!(formatter, "{} BOM", self.encoding.name())
+        } else {
+            write!(formatter, "{}", self.encoding.name())
+        }
+    }
+}
@@ -1,25 +1,105 @@
 use std::{
     fmt::{self, Display},
+    fs::metadata,
+    io::ErrorKind,
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 
+use super::TextEncoding;
+
 #[derive(Default, Debug)]
 pub struct FileInfo {
     path: Option<PathBuf>,
+    // 文件的文本编码
+    encoding: TextEncoding,
+    // 加载或保存时文件在磁盘上的修改时间，用于检测文件是否被其他程序修改
+    modified: Option<SystemTime>,
+}
+
+/// 文件在磁盘上的变化
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum DiskChange {
+    Unchanged,
+    // 文件被其他程序修改
+    Modified,
+    // 文件被删除
+    Deleted,
 }
 
 impl FileInfo {
     pub fn from(file_name: &str) -> Self {
         Self {
             path: Some(PathBuf::from(file_name)),
+            encoding: TextEncoding::default(),
+            modified: None,
         }
     }
 
+    /// 设置文件编码
+    pub const fn with_encoding(mut self, encoding: TextEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// 获取文件编码
+    pub const fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
     /// 获取文件路径引用
     pub fn get_path(&self) -> Option<&Path> {
         self.path.as_deref()
     }
 
+    /// 获取文件扩展名，没有路径或扩展名时返回 `None`
+    pub fn extension(&self) -> Option<&str> {
+        self.get_path()
+            .and_then(|path| path.extension())
+            .and_then(|extension| extension.to_str())
+    }
+
+    /// 获取路径指向的文件是否已经存在
+    pub fn exists(&self) -> bool {
+        self.get_path().is_some_and(Path::exists)
+    }
+
+    /// 记录文件当前在磁盘上的修改时间，在加载和保存文件后调用
+    pub fn record_modified(&mut self) {
+        self.modified = self
+            .get_path()
+            .and_then(|path| metadata(path).and_then(|metadata| metadata.modified()).ok());
+    }
+
+    /// 检查文件在磁盘上是否发生了变化。
+    ///
+    /// 发现变化后会记录新的状态，同一次变化只报告一次。文件被删除后不再检查，
+    /// 直到重新保存文件。
+    pub fn check_on_disk(&mut self) -> DiskChange {
+        let (Some(path), Some(recorded)) = (self.get_path(), self.modified) else {
+            return DiskChange::Unchanged;
+        };
+        match metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) if modified != recorded => {
+                self.modified = Some(modified);
+                DiskChange::Modified
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                self.modified = None;
+                DiskChange::Deleted
+            }
+            // 暂时无法读取文件信息时，等待下一次检查
+            Ok(_) | Err(_) => DiskChange::Unchanged,
+        }
+    }
+
+    /// 获取文件是否没有写权限，文件不存在时返回 `false`
+    pub fn is_read_only(&self) -> bool {
+        self.get_path()
+            .and_then(|path| metadata(path).ok())
+            .is_some_and(|metadata| metadata.permissions().readonly())
+    }
+
     /// 获取路径是否存在bool
     pub const fn has_path(&self) -> bool {
         self.path.is_some()
@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+/// 行内的一处修改：在 `at` 处删除 `removed` 个字素，再插入 `inserted` 个字素。
+///
+/// 批量修改多行后，用它来调整光标和选区锚点的位置。
+#[derive(Clone, Copy, Debug)]
+pub struct LineEdit {
+    pub line_index: LineIdx,
+    pub at: GraphemeIdx,
+    pub removed: GraphemeIdx,
+    pub inserted: GraphemeIdx,
+}
+
+impl LineEdit {
+    /// 根据这处修改调整位置。
+    ///
+    /// 修改点之前的位置保持不变；位于被删除内容中间的位置移动到修改点；
+    /// 之后的位置按删除和插入的字素数量平移。
+    pub fn adjust(&self, location: Location) -> Location {
+        if location.line_index != self.line_index || location.grapheme_index < self.at {
+            return location;
+        }
+        let grapheme_index = location
+            .grapheme_index
+            .saturating_sub(self.removed)
+            .max(self.at)
+            .saturating_add(self.inserted);
+        Location {
+            line_index: location.line_index,
+            grapheme_index,
+        }
+    }
+
+    /// 依次应用多处修改来调整位置
+    pub fn adjust_all(edits: &[Self], location: Location) -> Location {
+        edits.iter().fold(location, |location, edit| edit.adjust(location))
+    }
+}
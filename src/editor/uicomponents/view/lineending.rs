@@ -0,0 +1,44 @@
+use std::fmt::{self, Display};
+
+/// 换行符类型
+#[derive(Default, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum LineEnding {
+    // Unix 风格: \n
+    #[default]
+    Lf,
+    // Windows 风格: \r\n
+    CrLf,
+}
+
+impl LineEnding {
+    /// 检测文本中占多数的换行符类型。
+    ///
+    /// # 返回值
+    /// 返回占多数的换行符类型，以及文本中是否混用了两种换行符。
+    /// 数量相同时使用 `Lf`。
+    pub fn detect(contents: &str) -> (Self, bool) {
+        let total = contents.matches('\n').count();
+        let crlf = contents.matches("\r\n").count();
+        let lf = total.saturating_sub(crlf);
+        let dominant = if crlf > lf { Self::CrLf } else { Self::Lf };
+        (dominant, crlf > 0 && lf > 0)
+    }
+
+    /// 获取换行符对应的字符串
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+impl Display for LineEnding {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+        };
+        write!(formatter, "{name}")
+    }
+}
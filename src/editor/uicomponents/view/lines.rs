@@ -0,0 +1,387 @@
+//! 缓冲区中行的存储方式。
+//!
+//! 默认直接使用 `Vec<Line>`。启用 `rope` feature 后改用按块存储的行序列：
+//! 插入和删除行只需移动所在块中的元素，按行号访问通过二分查找块的起始行号完成，
+//! 打开很大的文件时编辑更流畅。两种实现提供相同的接口，`Buffer` 和 `View` 无需区分。
+
+#[cfg(not(feature = "rope"))]
+pub type Lines = Vec<super::Line>;
+
+#[cfg(feature = "rope")]
+pub use chunked::Lines;
+
+#[cfg(feature = "rope")]
+mod chunked {
+    use std::iter::Flatten;
+    use std::ops::{Bound, Index, IndexMut, RangeBounds};
+    use std::slice;
+
+    use super::super::Line;
+
+    // 每个块最多存放的行数，超过后拆分为两块
+    const MAX_CHUNK_LEN: usize = 1024;
+
+    /// 按块存储的行序列(行的 rope)
+    #[derive(Default)]
+    pub struct Lines {
+        // 存放行的块，除了整个序列为空时以外，每个块都不为空
+        chunks: Vec<Vec<Line>>,
+        // 每个块第一行的行号，与 `chunks` 一一对应
+        starts: Vec<usize>,
+        // 总行数
+        len: usize,
+    }
+
+    impl Lines {
+        pub const fn new() -> Self {
+            Self {
+                chunks: Vec::new(),
+                starts: Vec::new(),
+                len: 0,
+            }
+        }
+
+        pub const fn len(&self) -> usize {
+            self.len
+        }
+
+        pub const fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        pub fn get(&self, index: usize) -> Option<&Line> {
+            let (chunk, offset) = self.locate(index)?;
+            self.chunks.get(chunk)?.get(offset)
+        }
+
+        pub fn get_mut(&mut self, index: usize) -> Option<&mut Line> {
+            let (chunk, offset) = self.locate(index)?;
+            self.chunks.get_mut(chunk)?.get_mut(offset)
+        }
+
+        pub fn iter(&self) -> Iter<'_> {
+            Iter {
+                inner: self.chunks.iter().flatten(),
+                remaining: self.len,
+            }
+        }
+
+        pub fn iter_mut(&mut self) -> Flatten<slice::IterMut<'_, Vec<Line>>> {
+            self.chunks.iter_mut().flatten()
+        }
+
+        pub fn push(&mut self, line: Line) {
+            self.insert(self.len, line);
+        }
+
+        /// 在 `index` 处插入一行。
+        ///
+        /// # Panics
+        /// `index` 大于总行数时 panic，与 `Vec::insert` 相同。
+        pub fn insert(&mut self, index: usize, line: Line) {
+            assert!(index <= self.len, "insertion index {index} is out of bounds");
+            // 插入到末尾时追加到最后一个块
+            let (chunk, offset) = self.locate(index).unwrap_or_else(|| {
+                let last = self.chunks.len().saturating_sub(1);
+                (last, self.chunks.last().map_or(0, Vec::len))
+            });
+            if self.chunks.is_empty() {
+                self.chunks.push(Vec::new());
+                self.starts.push(0);
+            }
+            let Some(lines) = self.chunks.get_mut(chunk) else {
+                return;
+            };
+            lines.insert(offset, line);
+            if lines.len() > MAX_CHUNK_LEN {
+                let second_half = lines.split_off(lines.len().saturating_div(2));
+                self.chunks.insert(chunk.saturating_add(1), second_half);
+                self.starts.insert(chunk.saturating_add(1), 0);
+            }
+            self.len = self.len.saturating_add(1);
+            self.update_starts(chunk);
+        }
+
+        /// 删除并返回 `index` 处的行。
+        ///
+        /// # Panics
+        /// `index` 超出范围时 panic，与 `Vec::remove` 相同。
+        pub fn remove(&mut self, index: usize) -> Line {
+            let Some((chunk, offset)) = self.locate(index) else {
+                panic!("removal index {index} is out of bounds");
+            };
+            let lines = &mut self.chunks[chunk];
+            let line = lines.remove(offset);
+            if lines.is_empty() {
+                self.chunks.remove(chunk);
+                self.starts.remove(chunk);
+            }
+            self.len = self.len.saturating_sub(1);
+            self.update_starts(chunk);
+            line
+        }
+
+        /// 交换两行。
+        ///
+        /// # Panics
+        /// 行号超出范围时 panic，与 `Vec::swap` 相同。
+        pub fn swap(&mut self, first: usize, second: usize) {
+            if first == second {
+                return;
+            }
+            let (low, high) = (first.min(second), first.max(second));
+            let high_line = self.remove(high);
+            let low_line = self.remove(low);
+            self.insert(low, high_line);
+            self.insert(high, low_line);
+        }
+
+        /// 用 `replace_with` 替换 `range` 范围内的行。
+        ///
+        /// 只合并受影响的块(第一行和最后一行所在的块以及它们之间的块)，
+        /// 在合并后的块中一次完成替换，再重新均匀分块，不会逐行插入和删除。
+        pub fn splice<I>(&mut self, range: impl RangeBounds<usize>, replace_with: I)
+        where
+            I: IntoIterator<Item = Line>,
+        {
+            let start = match range.start_bound() {
+                Bound::Included(start) => *start,
+                Bound::Excluded(start) => start.saturating_add(1),
+                Bound::Unbounded => 0,
+            };
+            let end = match range.end_bound() {
+                Bound::Included(end) => end.saturating_add(1),
+                Bound::Excluded(end) => *end,
+                Bound::Unbounded => self.len,
+            };
+            let end = end.min(self.len);
+            let start = start.min(end);
+            // 插入到末尾时使用最后一个块
+            let first_chunk = self
+                .locate(start)
+                .map_or(self.chunks.len().saturating_sub(1), |(chunk, _)| chunk);
+            let last_chunk = end
+                .checked_sub(1)
+                .filter(|last| *last >= start)
+                .and_then(|last| self.locate(last))
+                .map_or(first_chunk, |(chunk, _)| chunk);
+            let affected = first_chunk..last_chunk.saturating_add(1).min(self.chunks.len());
+            let chunk_start = self.starts.get(first_chunk).copied().unwrap_or(0);
+
+            let mut merged: Vec<Line> = self.chunks.drain(affected.clone()).flatten().collect();
+            self.starts.drain(affected);
+            let merged_len = merged.len();
+            merged.splice(
+                start.saturating_sub(chunk_start)..end.saturating_sub(chunk_start),
+                replace_with,
+            );
+            self.len = self.len.saturating_sub(merged_len).saturating_add(merged.len());
+
+            // 重新均匀分块，每块不超过 `MAX_CHUNK_LEN` 行
+            let chunk_count = merged.len().div_ceil(MAX_CHUNK_LEN);
+            let chunk_len = merged.len().div_ceil(chunk_count.max(1));
+            let mut merged = merged.into_iter();
+            let new_chunks: Vec<Vec<Line>> = (0..chunk_count)
+                .map(|_| merged.by_ref().take(chunk_len).collect::<Vec<Line>>())
+                .filter(|chunk| !chunk.is_empty())
+                .collect();
+            self.starts.splice(first_chunk..first_chunk, new_chunks.iter().map(|_| 0));
+            self.chunks.splice(first_chunk..first_chunk, new_chunks);
+            self.update_starts(first_chunk);
+        }
+
+        /// 查找行所在的块及其在块中的位置
+        fn locate(&self, index: usize) -> Option<(usize, usize)> {
+            if index >= self.len {
+                return None;
+            }
+            let chunk = self
+                .starts
+                .partition_point(|start| *start <= index)
+                .saturating_sub(1);
+            let offset = index.saturating_sub(*self.starts.get(chunk)?);
+            Some((chunk, offset))
+        }
+
+        /// 从 `from` 开始重新计算每个块的起始行号
+        fn update_starts(&mut self, from: usize) {
+            let mut start = from
+                .checked_sub(1)
+                .and_then(|previous| {
+                    Some(self.starts.get(previous)?.saturating_add(self.chunks.get(previous)?.len()))
+                })
+                .unwrap_or(0);
+            for (chunk_start, lines) in self.starts.iter_mut().zip(&self.chunks).skip(from) {
+                *chunk_start = start;
+                start = start.saturating_add(lines.len());
+            }
+        }
+    }
+
+    impl Index<usize> for Lines {
+        type Output = Line;
+
+        fn index(&self, index: usize) -> &Line {
+            self.get(index).expect("line index is out of bounds")
+        }
+    }
+
+    impl IndexMut<usize> for Lines {
+        fn index_mut(&mut self, index: usize) -> &mut Line {
+            self.get_mut(index).expect("line index is out of bounds")
+        }
+    }
+
+    impl FromIterator<Line> for Lines {
+        fn from_iter<I: IntoIterator<Item = Line>>(iter: I) -> Self {
+            let mut lines = Self::new();
+            let mut iter = iter.into_iter().peekable();
+            while iter.peek().is_some() {
+                let chunk: Vec<Line> = iter.by_ref().take(MAX_CHUNK_LEN).collect();
+                lines.starts.push(lines.len);
+                lines.len = lines.len.saturating_add(chunk.len());
+                lines.chunks.push(chunk);
+            }
+            lines
+        }
+    }
+
+    impl<'a> IntoIterator for &'a mut Lines {
+        type Item = &'a mut Line;
+        type IntoIter = Flatten<slice::IterMut<'a, Vec<Line>>>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.iter_mut()
+        }
+    }
+
+    /// 按顺序遍历所有行的迭代器。
+    ///
+    /// 记录剩余的行数，以便像 `Vec` 的迭代器一样支持 `enumerate().rev()`。
+    #[derive(Clone)]
+    pub struct Iter<'a> {
+        inner: Flatten<slice::Iter<'a, Vec<Line>>>,
+        remaining: usize,
+    }
+
+    impl<'a> Iterator for Iter<'a> {
+        type Item = &'a Line;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let line = self.inner.next()?;
+            self.remaining = self.remaining.saturating_sub(1);
+            Some(line)
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.remaining, Some(self.remaining))
+        }
+    }
+
+    impl DoubleEndedIterator for Iter<'_> {
+        fn next_back(&mut self) -> Option<Self::Item> {
+            let line = self.inner.next_back()?;
+            self.remaining = self.remaining.saturating_sub(1);
+            Some(line)
+        }
+    }
+
+    impl ExactSizeIterator for Iter<'_> {}
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// 简单的伪随机数生成器(xorshift)，保证每次运行的操作序列相同
+        struct Rng(u64);
+
+        impl Rng {
+            fn below(&mut self, bound: usize) -> usize {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                usize::try_from(self.0).unwrap().checked_rem(bound).unwrap_or(0)
+            }
+        }
+
+        /// 内容为行号的行
+        fn numbered(range: std::ops::Range<usize>) -> Vec<Line> {
+            range.map(|number| Line::from(&number.to_string())).collect()
+        }
+
+        /// 检查内容与参照的 `Vec` 一致，并检查块的不变量
+        fn assert_matches(lines: &Lines, expected: &[Line]) {
+            assert_eq!(lines.len(), expected.len());
+            assert!(lines.iter().map(|line| &**line).eq(expected.iter().map(|line| &**line)));
+            for (index, line) in expected.iter().enumerate().step_by(97) {
+                assert_eq!(&*lines[index], &**line);
+            }
+            assert!(lines.get(expected.len()).is_none());
+            let mut start = 0;
+            for (chunk, chunk_start) in lines.chunks.iter().zip(&lines.starts) {
+                assert!(!chunk.is_empty() && chunk.len() <= MAX_CHUNK_LEN);
+                assert_eq!(*chunk_start, start);
+                start = start.saturating_add(chunk.len());
+            }
+            assert_eq!(lines.chunks.len(), lines.starts.len());
+            assert_eq!(start, lines.len());
+        }
+
+        #[test]
+        fn random_edits_match_vec_across_chunk_boundaries() {
+            let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+            let mut expected = numbered(0..MAX_CHUNK_LEN * 2 + 1);
+            let mut lines: Lines = expected.iter().cloned().collect();
+            assert_eq!(lines.chunks.len(), 3);
+            assert_matches(&lines, &expected);
+            let mut next = expected.len();
+            for _ in 0..400 {
+                match rng.below(5) {
+                    0 => {
+                        let index = rng.below(expected.len() + 1);
+                        lines.insert(index, Line::from(&next.to_string()));
+                        expected.insert(index, Line::from(&next.to_string()));
+                        next += 1;
+                    }
+                    1 if !expected.is_empty() => {
+                        let index = rng.below(expected.len());
+                        assert_eq!(&*lines.remove(index), &*expected.remove(index));
+                    }
+                    2 if !expected.is_empty() => {
+                        let (first, second) = (rng.below(expected.len()), rng.below(expected.len()));
+                        lines.swap(first, second);
+                        expected.swap(first, second);
+                    }
+                    _ => {
+                        let start = rng.below(expected.len() + 1);
+                        // 偶尔替换或插入超过一个块的行
+                        let large = rng.below(4) == 0;
+                        let removed = rng.below(if large { MAX_CHUNK_LEN * 2 } else { 8 });
+                        let end = (start + removed).min(expected.len());
+                        let inserted = rng.below(if large { MAX_CHUNK_LEN * 3 } else { 8 });
+                        let new_lines = numbered(next..next + inserted);
+                        next += inserted;
+                        lines.splice(start..end, new_lines.clone());
+                        expected.splice(start..end, new_lines);
+                    }
+                }
+                assert_matches(&lines, &expected);
+            }
+        }
+
+        #[test]
+        fn splice_handles_empty_and_whole_ranges() {
+            let mut lines = Lines::new();
+            lines.splice(0..0, numbered(0..MAX_CHUNK_LEN * 2 + 1));
+            assert_matches(&lines, &numbered(0..MAX_CHUNK_LEN * 2 + 1));
+            // 替换全部行
+            lines.splice(.., numbered(5..8));
+            assert_matches(&lines, &numbered(5..8));
+            // 追加到末尾，超出范围的结束位置按末尾处理
+            lines.splice(3..10, numbered(8..10));
+            assert_matches(&lines, &numbered(5..10));
+            lines.splice(.., []);
+            assert!(lines.is_empty() && lines.chunks.is_empty());
+        }
+    }
+}
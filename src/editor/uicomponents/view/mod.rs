@@ -1,43 +1,181 @@
-use std::{cmp::min, io::Error};
+use std::{cell::{Ref, RefCell}, cmp::{max, min}, io::{Error, ErrorKind}, mem, ops::Range};
 use crate::prelude::*;
 
-use super::super::{command::{Edit, Move}, DocumentStatus, Line, Terminal};
+use super::super::{
+    command::{Edit, Move},
+    syntax::{self, Highlighter, Language},
+    AnnotatedString, Annotation, AnnotationType, DocumentStatus, Line, Terminal, DEFAULT_TAB_WIDTH,
+};
 use super::UIComponent;
 use buffer::Buffer;
+use encoding::TextEncoding;
+use encoding_rs::Encoding;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 use fileinfo::FileInfo;
+pub use fileinfo::DiskChange;
+pub use textbuffer::TextBuffer;
+use lineending::LineEnding;
+use lineedit::LineEdit;
+use lines::Lines;
 use searchinfo::SearchInfo;
-use searchdirection::SearchDirection;
+pub use searchdirection::SearchDirection;
+
+/// 默认的注释符号
+const DEFAULT_COMMENT_TOKEN: &str = "//";
+/// 高亮配对括号时，在视图的行数之外最多再扫描这么多行
+const BRACKET_SCAN_MARGIN: usize = 100;
+/// 自动配对的开括号(引号)和对应的闭括号(引号)
+const AUTO_PAIRS: [(char, char); 6] = [('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\''), ('`', '`')];
 
 mod buffer;
+mod encoding;
 mod fileinfo;
+mod lineending;
+mod lineedit;
+mod lines;
+mod modeline;
 mod searchinfo;
 mod searchdirection;
+mod textbuffer;
 
+// clippy::struct_excessive_bools: 这些标志分别控制互相独立的显示和编辑选项
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Default)]
 pub struct View {
     // 存储文本内容的缓冲区
     buffer: Buffer,
     // 标记是否需要重新渲染
     needs_redraw: bool,
-    // View总是从 (0, 0) 开始。size 属性决定了可见区域。
+    // View总是从第 0 行开始，从 `origin_col` 列开始。size 属性决定了可见区域。
     size: Size,
+    // 视图左边缘在终端上的列，左右分屏时右侧窗格的视图不从第 0 列开始
+    origin_col: ColIdx,
     // 文档中位置
     text_location: Location,
     // view的偏移
     scroll_offset: Position,
     // 搜索内容
     search_info: Option<SearchInfo>,
+    // 是否在左侧显示行号
+    show_line_numbers: bool,
+    // 无法识别文件编码时使用的编码，为 None 时使用默认编码
+    fallback_encoding: Option<&'static Encoding>,
+    // 选区的锚点。选区是锚点与光标之间的文本，为 None 时没有选区
+    selection_anchor: Option<Location>,
+    // 在文档末尾按 Enter 时是否不插入新行
+    skip_newline_at_eof: bool,
+    // 换行时是否让新行沿用当前行的缩进
+    auto_indent: bool,
+    // 加载文件时是否读取其中的 modeline
+    read_modelines: bool,
+    // 制表符宽度，为 None 时使用默认宽度。文件的 modeline 中指定的宽度优先
+    tab_width: Option<ColIdx>,
+    // 按 Tab 键时是否插入空格而不是制表符
+    expand_tabs: bool,
+    // 加载的文件混用了制表符和空格缩进时，是否按 `expand_tabs` 统一缩进
+    normalize_indentation: bool,
+    // 加载文件时统一了缩进的行数
+    normalized_lines: usize,
+    // 是否正在以折行的方式只读预览当前行
+    line_preview: bool,
+    // 注释符号，为 None 时使用默认的注释符号
+    comment_token: Option<String>,
+    // 根据文件扩展名选择的语法高亮器
+    highlighter: Box<dyn Highlighter>,
+    // 保存时是否删除行尾的空白
+    trim_trailing_whitespace: bool,
+    // 是否只读。只读时忽略所有编辑命令。每次加载文件时根据 `force_read_only` 和文件权限重新计算
+    read_only: bool,
+    // 是否强制只读(命令行中的 `-R`)，不随加载的文件改变
+    force_read_only: bool,
+    // 第一次保存已有文件前是否备份原文件
+    backup: bool,
+    // 是否将超出视图宽度的行折行显示。折行时不进行水平滚动
+    wrap: bool,
+    // 折行显示时，视图顶部的行(`scroll_offset.row`)中第一个显示的折行
+    scroll_wrap_row: usize,
+    // 是否在状态栏中显示单词数和字符数
+    show_word_count: bool,
+    // 保存时是否删除文档末尾的空白行
+    trim_trailing_blank_lines: bool,
+    // 是否处于改写模式：输入的字符替换光标处的字素，而不是插入到它之前
+    overwrite: bool,
+    // 是否在最右侧一列显示滚动条
+    show_scrollbar: bool,
+    // 是否将空格显示为 `·`、制表符显示为 `→`
+    render_whitespace: bool,
+    // 是否高亮光标所在单词在文档中的其他出现位置
+    highlight_occurrences: bool,
+    // 行的列数限制，超出限制的第一列(从 0 开始计数的这一列)显示为标尺。为 None 时不显示
+    ruler_column: Option<ColIdx>,
+    // 主光标(`text_location`)之外的其他光标，按文档顺序排列，不包含主光标的位置。为空时只有一个光标
+    extra_cursors: Vec<Location>,
+    // 在下一个匹配项处添加光标时查找的内容，收起多个光标时清除
+    cursor_query: Option<String>,
+    // 上下移动时希望回到的列(粘性列)，以及上次上下移动后光标所在的位置。
+    // 光标离开这个位置(编辑、点击、搜索等)后不再使用，左右移动时清除
+    desired_col: Option<(Location, ColIdx)>,
+    // 折叠隐藏的行的范围，按行号排列且互不重叠。每个折叠在视图中显示为一行摘要
+    folds: Vec<Range<LineIdx>>,
+    // 输入开括号或引号时是否自动插入对应的闭括号或引号
+    auto_pair: bool,
+    // 光标处的配对括号和单词，移动光标和绘制时共用
+    caret_cache: RefCell<CaretCache>,
+}
+
+/// 光标处的配对括号和单词，光标位置、缓冲区内容或视图的行数变化后重新计算
+#[derive(Default)]
+struct CaretCache {
+    // 计算时的光标位置、缓冲区的修订号和视图的行数
+    key: Option<(Location, usize, usize)>,
+    brackets: Option<(Location, Location)>,
+    // 光标下的单词，不考虑是否开启了单词高亮
+    word: Option<String>,
 }
 
 impl View {
+    /// 用已有的缓冲区创建视图，光标位于文档开头
+    pub fn from_buffer(buffer: Buffer) -> Self {
+        Self {
+            highlighter: syntax::for_extension(buffer.file_info.extension()),
+            buffer,
+            needs_redraw: true,
+            ..Self::default()
+        }
+    }
+
+    /// 用内存中的文本创建视图，视图不关联任何文件
+    // clippy::should_implement_trait: 创建不会失败，不需要 `FromStr` 返回的 `Result`
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(text: &str) -> Self {
+        Self::from_buffer(Buffer::from(text))
+    }
 
     // 获取状态
     pub fn get_status(&self) -> DocumentStatus {
         DocumentStatus {
             total_lines: self.buffer.height(),
             current_line_index: self.text_location.line_index,
+            current_grapheme_index: self.text_location.grapheme_index,
+            current_column: self
+                .buffer
+                .lines
+                .get(self.text_location.line_index)
+                .map_or(0, |line| line.width_until(self.text_location.grapheme_index)),
             file_name: format!("{}", self.buffer.file_info),
             is_modified: self.buffer.dirty,
+            is_read_only: self.read_only,
+            horizontal_offset: self.scroll_offset.col,
+            word_and_char_count: self.show_word_count.then(|| self.word_and_char_count()),
+            buffer_position: None,
+            mode: None,
+            recording: false,
+            overwrite: self.overwrite,
+            render_whitespace: self.render_whitespace,
+            line_ending: self.buffer.line_ending.to_string(),
+            encoding: self.buffer.file_info.encoding().to_string(),
+            language: Language::from_extension(self.buffer.file_info.extension()).name().to_string(),
         }
     }
 
@@ -46,11 +184,69 @@ impl View {
     /// # 参数
     /// - `command`: 编辑命令枚举。
     pub fn handle_edit_command(&mut self, command: Edit) {
+        if self.read_only {
+            return;
+        }
+        let line_index = self.text_location.line_index;
+        let height = self.buffer.height();
+        self.dispatch_edit(command);
+        self.update_folds_after_edit(line_index, height);
+    }
+
+    /// 按光标数和选区将编辑命令分派给对应的处理方法
+    fn dispatch_edit(&mut self, command: Edit) {
+        // 有多个光标时，只在行内进行的编辑作用于所有光标，其他编辑先收起到主光标
+        if !self.extra_cursors.is_empty() {
+            if Self::applies_to_all_cursors(&command) {
+                self.edit_at_all_cursors(&command);
+                return;
+            }
+            self.collapse_cursors();
+        }
+        // 作用于选中的行的命令需要保留选区
+        match command {
+            Edit::ToggleBlockComment => {
+                self.toggle_block_comment();
+                return;
+            }
+            Edit::ToggleComment => {
+                self.toggle_line_comments();
+                return;
+            }
+            Edit::Indent if self.selection_anchor.is_some() => {
+                self.indent_lines();
+                return;
+            }
+            Edit::Dedent => {
+                self.dedent_lines();
+                return;
+            }
+            _ => {}
+        }
+        self.clear_selection();
+        self.close_line_preview();
+        self.apply_edit(command);
+    }
+
+    /// 在主光标处执行不需要选区的编辑命令
+    fn apply_edit(&mut self, command: Edit) {
         match command {
             Edit::Insert(character) => self.insert_char(character),
             Edit::Delete => self.delete(),
             Edit::DeleteBackward => self.delete_backward(),
             Edit::InsertNewline => self.insert_newline(),
+            Edit::DeleteWordBackward => self.delete_word_backward(),
+            Edit::DeleteToLineEnd => self.delete_to_line_end(),
+            Edit::Paste(text) => self.paste(&text),
+            Edit::DuplicateLine => self.duplicate_line(),
+            Edit::JoinLine => self.join_line(),
+            Edit::DeleteLine => self.delete_line(),
+            Edit::MoveLineUp => self.move_line(true),
+            Edit::MoveLineDown => self.move_line(false),
+            // 没有选区时插入制表符
+            Edit::Indent => self.insert_char('\t'),
+            // 已在上面处理
+            Edit::ToggleBlockComment | Edit::ToggleComment | Edit::Dedent => {}
         }
     }
 
@@ -59,20 +255,127 @@ impl View {
     /// # 参数
     /// - `command`: 移动命令枚举。
     pub fn handle_move_command(&mut self, command: Move) {
+        let old_line_index = self.text_location.line_index;
+        let old_brackets = self.matching_brackets();
+        let old_word = self.word_under_caret();
+        self.clear_selection();
+        self.close_line_preview();
+        // 有多个光标时所有光标一起移动
+        if self.extra_cursors.is_empty() {
+            self.apply_move(command);
+        } else {
+            let primary = self.text_location;
+            let cursors = mem::take(&mut self.extra_cursors);
+            for cursor in cursors {
+                self.text_location = cursor;
+                self.apply_move(command);
+                self.extra_cursors.push(self.text_location);
+            }
+            self.text_location = primary;
+            self.apply_move(command);
+            self.normalize_cursors();
+            self.set_needs_redraw(true);
+        }
+
+        // 行号栏中当前行的对齐方式与其他行不同，光标换行后需要重绘
+        if self.show_line_numbers && old_line_index != self.text_location.line_index {
+            self.set_needs_redraw(true);
+        }
+        // 光标移到括号上或离开括号时，需要更新配对括号的高亮
+        if old_brackets != self.matching_brackets() {
+            self.set_needs_redraw(true);
+        }
+        // 光标所在的单词变化时，需要更新单词出现位置的高亮
+        if old_word != self.word_under_caret() {
+            self.set_needs_redraw(true);
+        }
+
+        // 处理滚动显示位置
+        self.scroll_text_location_into_view();
+    }
+
+    /// 按移动命令移动主光标，不处理选区和滚动
+    fn apply_move(&mut self, command: Move) {
         let Size { height, .. } = self.size;
+        if !matches!(command, Move::Up | Move::Down | Move::PageUp | Move::PageDown) {
+            self.desired_col = None;
+        }
         match command {
-            Move::Up => self.move_up(1),
-            Move::Down => self.move_down(1),
+            Move::Up => self.move_vertically(1, true),
+            Move::Down => self.move_vertically(1, false),
             Move::Left => self.move_left(),
             Move::Right => self.move_right(),
-            Move::PageUp => self.move_up(height.saturating_sub(1)),
-            Move::PageDown => self.move_down(height.saturating_sub(1)),
-            Move::StartOfLine => self.move_to_start_of_line(),
+            Move::PageUp => self.move_vertically(height.saturating_sub(1), true),
+            Move::PageDown => self.move_vertically(height.saturating_sub(1), false),
+            Move::PageLeft => self.move_horizontal_page(false),
+            Move::PageRight => self.move_horizontal_page(true),
+            Move::StartOfLine => self.move_to_smart_home(),
             Move::EndOfLine => self.move_to_end_of_line(),
+            Move::WordForward => self.move_word_forward(),
+            Move::WordBackward => self.move_word_backward(),
+            Move::MatchBracket => self.move_to_matching_bracket(),
+            Move::DocumentStart => self.move_to_document_start(),
+            Move::DocumentEnd => self.move_to_document_end(),
         }
+    }
 
-        // 处理滚动显示位置
+    /// 处理选择命令：移动光标并扩展选区。
+    ///
+    /// # 参数
+    /// - `command`: 移动命令枚举。
+    pub fn handle_select_command(&mut self, command: Move) {
+        // 选区只属于主光标
+        self.collapse_cursors();
+        // 没有选区时，以当前光标位置作为锚点
+        let anchor = self.selection_anchor.unwrap_or(self.text_location);
+        self.handle_move_command(command);
+        self.selection_anchor = Some(anchor);
+        self.set_needs_redraw(true);
+    }
+
+    /// 处理鼠标点击，将光标移动到被点击的字素上。
+    ///
+    /// # 参数
+    /// - `position`: 点击的位置，相对于视图左上角。超出视图高度的点击(状态栏、消息栏)会被忽略。
+    pub fn handle_click(&mut self, position: Position) {
+        if position.row >= self.size.height {
+            return;
+        }
+        self.collapse_cursors();
+        self.clear_selection();
+        self.close_line_preview();
+        // 点击行号栏时定位到行首
+        let col = position
+            .col
+            .saturating_sub(self.origin_col)
+            .saturating_sub(self.gutter_width());
+        let (line_index, col) = if self.wrap {
+            // 点击折行末尾之后的位置时，定位到该折行的最后一个字素上
+            self.visual_rows()
+                .nth(position.row)
+                .map_or((self.buffer.height(), 0), |(line_idx, row, range)| {
+                    let is_last_row = row.saturating_add(1) == self.line_wrap(line_idx).len();
+                    let row_end = if is_last_row { range.end } else { range.end.saturating_sub(1) };
+                    (line_idx, range.start.saturating_add(col).min(row_end))
+                })
+        } else {
+            // 折叠只占一行，点击摘要行时光标进入折叠，折叠随之展开
+            let line_index = (self.scroll_offset.row..self.buffer.height())
+                .filter(|line_idx| !self.is_line_hidden(*line_idx))
+                .nth(position.row)
+                .unwrap_or_else(|| self.buffer.height());
+            (line_index, col.saturating_add(self.scroll_offset.col))
+        };
+        self.text_location.line_index = line_index;
+        self.snap_to_valid_line();
+        self.text_location.grapheme_index = self
+            .buffer
+            .lines
+            .get(self.text_location.line_index)
+            .map_or(0, |line| line.grapheme_idx_at_column(col));
         self.scroll_text_location_into_view();
+        // 当前行变化后行号的对齐方式也会变化，直接重绘整个视图
+        self.set_needs_redraw(true);
     }
 
     /// 是否已加载文件
@@ -80,218 +383,1806 @@ impl View {
         self.buffer.is_file_loaded()
     }
 
-    // region: search
-    // 搜索代码区域
+    /// 跳转到指定位置，并居中显示。
+    ///
+    /// 超出文档范围的位置会被调整到最近的有效位置。
+    pub fn go_to(&mut self, location: Location) {
+        self.text_location = location;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.center_text_location();
+    }
 
-    /// 输入搜索
-    pub fn enter_search(&mut self) {
-        // 输入搜索后,存储之前光标所在的位置
-        self.search_info = Some(SearchInfo {
-            prev_location: self.text_location,
-            prev_scroll_offset: self.scroll_offset,
-            query: None,
+    /// 跳转到当前行之后第一个包含 `query` 的行的行首。
+    ///
+    /// 到达文档末尾后从开头继续查找，当前行最后检查。
+    ///
+    /// # 返回值
+    /// 找到时返回是否从文档开头继续查找过；没有匹配的行时返回 `None`，光标保持不动。
+    pub fn go_to_next_line_containing(&mut self, query: &str) -> Option<bool> {
+        let from = Location {
+            grapheme_index: 0,
+            line_index: min(self.text_location.line_index.saturating_add(1), self.buffer.height()),
+        };
+        let (location, wrapped) = self.buffer.search_forward(query, from)?;
+        self.go_to(Location {
+            grapheme_index: 0,
+            line_index: location.line_index,
         });
+        Some(wrapped)
     }
 
-    /// 退出搜索
-    pub fn exit_search(&mut self) {
-        self.search_info = None;
+    /// 设置在文档末尾按 Enter 时是否插入新行
+    pub fn set_newline_at_eof(&mut self, enabled: bool) {
+        self.skip_newline_at_eof = !enabled;
+    }
+
+    /// 设置换行时是否自动缩进
+    pub fn set_auto_indent(&mut self, enabled: bool) {
+        self.auto_indent = enabled;
+    }
+
+    /// 设置是否自动配对括号和引号
+    pub fn set_auto_pair(&mut self, enabled: bool) {
+        self.auto_pair = enabled;
+    }
+
+    /// 设置注释符号
+    pub fn set_comment_token(&mut self, token: &str) {
+        self.comment_token = Some(token.to_string());
+    }
+
+    /// 切换当前行的折行预览。
+    ///
+    /// 预览时只显示光标所在的行，并按视图宽度折行，方便阅读很长的行。
+    /// 预览不会修改缓冲区，任何移动或编辑操作都会结束预览。
+    pub fn toggle_line_preview(&mut self) {
+        self.line_preview = !self.line_preview;
         self.set_needs_redraw(true);
     }
-    
-    /// 关闭搜索
-    pub fn dismiss_search(&mut self) {
-        // search_info存有旧位置的信息就回到旧位置那
-        if let Some(search_info) = &self.search_info {
-            // 重置文本位置和关闭时的滚动偏移量
-            self.text_location = search_info.prev_location;
-            self.scroll_offset = search_info.prev_scroll_offset;
-            // 确保搜索时调整大小了,也能将view显示到对应位置
-            self.scroll_text_location_into_view();
+
+    /// 结束当前行的折行预览
+    fn close_line_preview(&mut self) {
+        if self.line_preview {
+            self.line_preview = false;
+            self.set_needs_redraw(true);
         }
-        self.exit_search();
     }
 
-    /// 搜索操作
-    pub fn search(&mut self, query: &str) {
-        // 设置搜索内容
-        if let Some(search_info) = &mut self.search_info {
-            search_info.query = Some(Line::from(query));
-        }
-        // 使用当前位置调用 search_in_direction,默认向下搜索
-        self.search_in_direction(self.text_location, SearchDirection::default());
+    /// 切换插入模式和改写模式
+    pub fn toggle_overwrite(&mut self) {
+        self.overwrite = !self.overwrite;
     }
 
-    // 尝试获取当前的搜索查询——适用于必须存在搜索查询的场景。
-    // 如果在debug模式下不存在搜索查询或搜索信息，则会触发 panic。
-    // 在生产模式下返回 None。
-    fn get_search_query(&self) -> Option<&Line> {
-        let query = self
-            .search_info
-            .as_ref()
-            .and_then(|search_info| search_info.query.as_ref());
-        debug_assert!(
-            query.is_some(),
-            "Attempting to search with malformed searchinfo present"
-        );
-        query
+    /// 切换是否显示行号
+    pub fn toggle_line_numbers(&mut self) {
+        self.set_show_line_numbers(!self.show_line_numbers);
     }
 
-    /// 按某个方向开始进行搜索(向上/向下)
-    fn search_in_direction(&mut self, from: Location, direction: SearchDirection) {
-        if let Some(location) = self.get_search_query().and_then(|query| {
-            // 从search_info取出要搜索的内容,判断是向上/向下搜索
-            if query.is_empty() {
-                None
-            } else if direction == SearchDirection::Forward {
-                self.buffer.search_forward(query, from)
-            } else {
-                self.buffer.search_backward(query, from)
-            }
-        })
-        // 查找到就移动到对应位置居中显示
-        {
-            self.text_location = location;
-            self.center_text_location();
-        };
+    /// 设置是否显示行号
+    pub fn set_show_line_numbers(&mut self, enabled: bool) {
+        if self.show_line_numbers == enabled {
+            return;
+        }
+        self.show_line_numbers = enabled;
+        // 行号栏宽度变化后，文本区域的宽度也会变化，需要重新确保光标可见
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// 切换是否显示滚动条
+    pub fn toggle_scrollbar(&mut self) {
+        self.set_show_scrollbar(!self.show_scrollbar);
+    }
 
+    /// 设置是否在最右侧一列显示滚动条
+    pub fn set_show_scrollbar(&mut self, enabled: bool) {
+        if self.show_scrollbar == enabled {
+            return;
+        }
+        self.show_scrollbar = enabled;
+        // 滚动条占用一列，文本区域的宽度随之变化
+        self.scroll_text_location_into_view();
         self.set_needs_redraw(true);
     }
 
-    /// 搜索下一个关键词
-    pub fn search_next(&mut self) {
-        // 计算字素的宽度,最少都移动1步,避免一直搜索到当前的关键词
-        let step_right = self
-            .get_search_query()
-            .map_or(1, |query| min(query.grapheme_count(), 1));
-        // 从当前搜索出来的关键词的字素结尾开始,搜索下一个关键词
-        let location = Location {
-            line_index: self.text_location.line_index,
-            grapheme_index: self.text_location.grapheme_index.saturating_add(step_right),
-        };
-        self.search_in_direction(location, SearchDirection::Forward);
+    /// 设置标尺所在的列，为 `None` 时不显示标尺
+    pub fn set_ruler_column(&mut self, column: Option<ColIdx>) {
+        if self.ruler_column != column {
+            self.ruler_column = column;
+            self.set_needs_redraw(true);
+        }
     }
 
-    // 搜索上一个关键词
-    pub fn search_prev(&mut self) {
-        self.search_in_direction(self.text_location, SearchDirection::Backward);
+    /// 切换是否显示空白字符
+    pub fn toggle_render_whitespace(&mut self) {
+        self.set_render_whitespace(!self.render_whitespace);
     }
-    // endregion
-    // 搜索代码区域结束
 
-    // region: file i/o
-    // 文件io处理代码区域
+    /// 设置是否将空格和制表符显示为可见的符号。符号与原字符的宽度相同，不影响布局
+    pub fn set_render_whitespace(&mut self, enabled: bool) {
+        if self.render_whitespace != enabled {
+            self.render_whitespace = enabled;
+            self.set_needs_redraw(true);
+        }
+    }
 
-    /// 读取文件内容并加载到缓冲区。
-    ///
-    /// # 参数
-    /// - `file_name`: 要加载的文件名。
-    ///
-    /// 如果文件加载成功，则将其内容保存到缓冲区，并标记视图需要重新渲染。
-    pub fn load(&mut self, file_name: &str) -> Result<(), Error> {
-        let buffer = Buffer::load(file_name)?;
-        self.buffer = buffer;
-        self.set_needs_redraw(true);
-        Ok(())
+    /// 切换是否高亮光标所在单词的出现位置
+    pub fn toggle_highlight_occurrences(&mut self) {
+        self.set_highlight_occurrences(!self.highlight_occurrences);
     }
 
-    /// 保存缓冲区内容到文件
-    pub fn save(&mut self) -> Result<(), Error> {
-        self.buffer.save()
+    /// 设置是否高亮光标所在单词在文档中的所有出现位置。只影响显示，不移动光标
+    pub fn set_highlight_occurrences(&mut self, enabled: bool) {
+        if self.highlight_occurrences != enabled {
+            self.highlight_occurrences = enabled;
+            self.set_needs_redraw(true);
+        }
     }
 
-    /// 另存为缓冲区内容到新文件
-    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        self.buffer.save_as(file_name)
+    /// 获取光标所在行的纯文本内容，光标位于文档末尾之后时返回空字符串
+    pub fn current_line_text(&self) -> &str {
+        self.buffer.line_text(self.text_location.line_index)
     }
 
-    // 文件io处理代码区域结束
+    // region: selection
+    // 选区代码区域
 
-    // region: Text editing
-    // 文本编辑代码区域
+    /// 取消选区
+    fn clear_selection(&mut self) {
+        if self.selection_anchor.take().is_some() {
+            self.set_needs_redraw(true);
+        }
+    }
 
-    fn insert_newline(&mut self) {
-        self.buffer.insert_newline(self.text_location);
-        self.handle_move_command(Move::Right);
-        self.set_needs_redraw(true);
+    /// 获取按先后顺序排列的选区起止位置，没有选区时返回 None
+    fn selection_range(&self) -> Option<(Location, Location)> {
+        let anchor = self.selection_anchor?;
+        let caret = self.text_location;
+        if (anchor.line_index, anchor.grapheme_index) <= (caret.line_index, caret.grapheme_index) {
+            Some((anchor, caret))
+        } else {
+            Some((caret, anchor))
+        }
     }
 
-    fn delete_backward(&mut self) {
-        // 确保我们只在文档贯标不位于左上角时向左移动。
-        if self.text_location.line_index != 0 || self.text_location.grapheme_index != 0 {
-            self.handle_move_command(Move::Left);
-            self.delete();
+    /// 获取选中的文本，多行之间用 `\n` 连接。没有选区或选区为空时返回 None
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection_range()?;
+        if start == end {
+            return None;
         }
+        let lines: Vec<String> = (start.line_index..=end.line_index)
+            .map(|line_idx| {
+                let range = self.selection_in_line(line_idx).unwrap_or(0..0);
+                self.buffer.lines.get(line_idx).map_or_else(String::new, |line| {
+                    line.graphemes(true)
+                        .skip(range.start)
+                        .take(range.end.saturating_sub(range.start))
+                        .collect()
+                })
+            })
+            .collect();
+        Some(lines.join("\n"))
     }
 
-    fn delete(&mut self) {
-        self.buffer.delete(self.text_location);
-        self.set_needs_redraw(true);
+    /// 获取选区涉及的行的范围(包含首尾两行)，没有选区时返回 None
+    fn selected_lines(&self) -> Option<(LineIdx, LineIdx)> {
+        let (start, end) = self.selection_range()?;
+        // 光标可以位于最后一行之后，此时不计入
+        let last_line = self.buffer.height().saturating_sub(1);
+        Some((min(start.line_index, last_line), min(end.line_index, last_line)))
     }
 
-    // 插入字符
-    fn insert_char(&mut self, character: char) {
-        // 获取当前所在行的内容长度
-        let old_len = self.buffer
-            .lines
-            .get(self.text_location.line_index)
-            .map_or(0, Line::grapheme_count);
+    /// 获取指定行中被选中的字素范围，该行不在选区内时返回 None
+    fn selection_in_line(&self, line_idx: LineIdx) -> Option<Range<GraphemeIdx>> {
+        let (start, end) = self.selection_range()?;
+        if line_idx < start.line_index || line_idx > end.line_index {
+            return None;
+        }
+        let from = if line_idx == start.line_index {
+            start.grapheme_index
+        } else {
+            0
+        };
+        let to = if line_idx == end.line_index {
+            end.grapheme_index
+        } else {
+            self.line_grapheme_count(line_idx)
+        };
+        Some(from..to)
+    }
 
-        // 在位置上插入字符
-        self.buffer.insert_char(character, self.text_location);
+    /// 用分隔符将选区涉及的所有行合并为一行，并将选区收缩到第一个合并点。
+    ///
+    /// # 返回值
+    /// 返回被合并的行数。选区少于两行时不做任何操作并返回 0。
+    pub fn join_selected_lines(&mut self, separator: &str) -> usize {
+        let Some((start, end)) = self.selected_lines() else {
+            return 0;
+        };
+        if start == end {
+            return 0;
+        }
+        let join_point = self.line_grapheme_count(start);
+        let height = self.buffer.height();
+        self.buffer.join_lines(start, end, separator);
+        self.selection_anchor = None;
+        self.text_location = Location {
+            grapheme_index: join_point,
+            line_index: start,
+        };
+        self.update_folds_after_edit(end, height);
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        end.saturating_sub(start).saturating_add(1)
+    }
 
-        // 获取插入后的长度
-        let new_len = self.buffer
-            .lines
-            .get(self.text_location.line_index)
-            .map_or(0, Line::grapheme_count);
+    /// 按内容排序选区涉及的行，`ignore_case` 为 `true` 时不区分大小写。
+    ///
+    /// # 返回值
+    /// 返回选区涉及的行数。选区少于两行时不做任何操作并返回 0。
+    pub fn sort_selected_lines(&mut self, ignore_case: bool) -> usize {
+        self.reorder_selected_lines(|buffer, start, end| buffer.sort_lines(start, end, ignore_case))
+    }
 
-        // 正常来说，插入字符后光标要右移一下。这里通过插入前后得长度查来判断
-        let grapheme = new_len.saturating_sub(old_len);
-        if grapheme > 0 {
-            self.handle_move_command(Move::Right);
+    /// 将选区涉及的行倒序排列。
+    ///
+    /// # 返回值
+    /// 返回选区涉及的行数。选区少于两行时不做任何操作并返回 0。
+    pub fn reverse_selected_lines(&mut self) -> usize {
+        self.reorder_selected_lines(Buffer::reverse_lines)
+    }
+
+    /// 重新排列选区涉及的行。之后选区从第一行的开头到最后一行的末尾，覆盖同样的行，方向保持不变
+    fn reorder_selected_lines(&mut self, reorder: impl FnOnce(&mut Buffer, LineIdx, LineIdx) -> bool) -> usize {
+        let Some((start, end)) = self.selected_lines() else {
+            return 0;
+        };
+        if start == end {
+            return 0;
+        }
+        if reorder(&mut self.buffer, start, end) {
+            // 行的内容变了，其中的折叠不再有效
+            self.folds.retain(|fold| fold.end <= start || fold.start > end);
         }
+        let first = Location {
+            grapheme_index: 0,
+            line_index: start,
+        };
+        let last = Location {
+            grapheme_index: self.line_grapheme_count(end),
+            line_index: end,
+        };
+        let caret = self.text_location;
+        let forward = self.selection_anchor.is_none_or(|anchor| {
+            (anchor.line_index, anchor.grapheme_index) <= (caret.line_index, caret.grapheme_index)
+        });
+        let (anchor, caret) = if forward { (first, last) } else { (last, first) };
+        self.selection_anchor = Some(anchor);
+        self.text_location = caret;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        end.saturating_sub(start).saturating_add(1)
+    }
+
+    /// 对选区涉及的行(没有选区时为当前行)整体添加或取消注释，并保持光标和选区的位置
+    fn toggle_block_comment(&mut self) {
+        let Some((start, end)) = self.block_edit_lines() else {
+            return;
+        };
+        let token = self.comment_token.as_deref().unwrap_or(DEFAULT_COMMENT_TOKEN);
+        let edits = self.buffer.toggle_block_comment(start, end, token);
+        self.apply_line_edits(&edits);
+    }
+
+    /// 逐行切换选中的行(没有选区时为当前行)的注释。
+    ///
+    /// 注释符号根据文件扩展名确定，无法识别时使用配置的注释符号。
+    fn toggle_line_comments(&mut self) {
+        let Some((start, end)) = self.block_edit_lines() else {
+            return;
+        };
+        let token = syntax::comment_token(self.buffer.file_info.extension())
+            .or(self.comment_token.as_deref())
+            .unwrap_or(DEFAULT_COMMENT_TOKEN);
+        let edits = self.buffer.toggle_line_comments(start, end, token);
+        self.apply_line_edits(&edits);
+    }
+
+    /// 增加选中的行的缩进：开启 `expand_tabs` 时插入制表符宽度个空格，否则插入一个制表符
+    fn indent_lines(&mut self) {
+        let Some((start, end)) = self.block_edit_lines() else {
+            return;
+        };
+        let unit = if self.expand_tabs {
+            " ".repeat(self.tab_width())
+        } else {
+            String::from("\t")
+        };
+        let edits = self.buffer.indent_lines(start, end, &unit);
+        self.apply_line_edits(&edits);
+    }
+
+    /// 减少选中的行(没有选区时为当前行)的缩进，每行最多删除制表符宽度列的空白
+    fn dedent_lines(&mut self) {
+        let Some((start, end)) = self.block_edit_lines() else {
+            return;
+        };
+        let edits = self.buffer.dedent_lines(start, end);
+        self.apply_line_edits(&edits);
+    }
+
+    /// 获取按行操作的命令作用的行：有选区时为选中的行，否则为当前行
+    fn block_edit_lines(&self) -> Option<(LineIdx, LineIdx)> {
+        let current_line = self.text_location.line_index;
+        self.selected_lines()
+            .or_else(|| (current_line < self.buffer.height()).then_some((current_line, current_line)))
+    }
 
+    /// 修改了多行之后，调整光标和选区锚点，使它们仍然指向原来的文本
+    fn apply_line_edits(&mut self, edits: &[LineEdit]) {
+        if edits.is_empty() {
+            return;
+        }
+        self.text_location = LineEdit::adjust_all(edits, self.text_location);
+        self.selection_anchor = self
+            .selection_anchor
+            .map(|anchor| LineEdit::adjust_all(edits, anchor));
+        self.scroll_text_location_into_view();
         self.set_needs_redraw(true);
     }
-    // 文本编辑代码区域结束
 
+    // endregion
+    // 选区代码区域结束
 
-    // region: Rendering
-    // 渲染方法代码
+    // region: multiple cursors
+    // 多光标代码区域
 
+    /// 光标总数(包括主光标)
+    pub fn cursor_count(&self) -> usize {
+        self.extra_cursors.len().saturating_add(1)
+    }
 
-    /// 渲染指定行的内容。
+    /// 收起多个光标，只保留主光标。
     ///
-    /// # 参数
+    /// # 返回值
+    /// 原来有多个光标时返回 `true`。
+    pub fn collapse_cursors(&mut self) -> bool {
+        self.cursor_query = None;
+        if self.extra_cursors.is_empty() {
+            return false;
+        }
+        self.extra_cursors.clear();
+        self.set_needs_redraw(true);
+        true
+    }
+
+    /// 在主光标的下一行添加光标，尽量保持相同的显示列。新的光标成为主光标。
+    ///
+    /// # 返回值
+    /// 主光标已经位于最后一行时返回 `false`。
+    pub fn add_cursor_below(&mut self) -> bool {
+        let Location { line_index, grapheme_index } = self.text_location;
+        let next_line_index = line_index.saturating_add(1);
+        let (Some(line), Some(next_line)) = (self.buffer.lines.get(line_index), self.buffer.lines.get(next_line_index))
+        else {
+            return false;
+        };
+        let column = line.width_until(grapheme_index);
+        let next = Location {
+            line_index: next_line_index,
+            grapheme_index: next_line.grapheme_at_width(column),
+        };
+        self.add_cursor(next);
+        true
+    }
+
+    /// 在下一个匹配项的开头添加光标，新的光标成为主光标。
+    ///
+    /// 查找的内容是一行之内的选中文本，没有选区时是光标处(或光标前)的单词。
+    /// 第一次添加时主光标先移到它所在的匹配项的开头。从主光标之后开始查找，到达文档末尾后从开头继续。
+    ///
+    /// # 返回值
+    /// 没有可以查找的内容，或者所有匹配项都已经有光标时返回 `false`。
+    pub fn add_cursor_at_next_match(&mut self) -> bool {
+        // 光标已经收起时重新确定查找的内容
+        if self.extra_cursors.is_empty() {
+            self.cursor_query = None;
+        }
+        if self.cursor_query.is_none() {
+            let Some((start, query)) = self.cursor_query_at_caret() else {
+                return false;
+            };
+            self.clear_selection();
+            self.text_location = start;
+            self.cursor_query = Some(query);
+            self.set_needs_redraw(true);
+        }
+        let Some(query) = self.cursor_query.clone() else {
+            return false;
+        };
+        let Location { line_index, grapheme_index } = self.text_location;
+        let from = Location {
+            line_index,
+            grapheme_index: min(grapheme_index.saturating_add(1), self.line_grapheme_count(line_index)),
+        };
+        let Some((next, _)) = self.buffer.search_forward(&query, from) else {
+            return false;
+        };
+        if next == self.text_location || self.extra_cursors.contains(&next) {
+            return false;
+        }
+        self.add_cursor(next);
+        true
+    }
+
+    /// 在指定位置添加光标并让它成为主光标，原来的主光标成为其他光标之一
+    fn add_cursor(&mut self, location: Location) {
+        self.clear_selection();
+        self.close_line_preview();
+        self.extra_cursors.push(self.text_location);
+        self.text_location = location;
+        self.normalize_cursors();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// 添加光标时查找的内容及其开始位置：一行之内的选中文本，或者光标处(或光标前)的单词
+    fn cursor_query_at_caret(&self) -> Option<(Location, String)> {
+        if let Some((start, end)) = self.selection_range() {
+            if start.line_index != end.line_index {
+                return None;
+            }
+            return Some((start, self.selected_text()?));
+        }
+        let Location { line_index, grapheme_index } = self.text_location;
+        let at = |grapheme_index| Location { grapheme_index, line_index };
+        let mut start = if self.is_word_grapheme(self.text_location) {
+            grapheme_index
+        } else if grapheme_index > 0 && self.is_word_grapheme(at(grapheme_index.saturating_sub(1))) {
+            grapheme_index.saturating_sub(1)
+        } else {
+            return None;
+        };
+        while start > 0 && self.is_word_grapheme(at(start.saturating_sub(1))) {
+            start = start.saturating_sub(1);
+        }
+        let mut end = start.saturating_add(1);
+        while self.is_word_grapheme(at(end)) {
+            end = end.saturating_add(1);
+        }
+        let word = self
+            .current_line_text()
+            .graphemes(true)
+            .skip(start)
+            .take(end.saturating_sub(start))
+            .collect();
+        Some((at(start), word))
+    }
+
+    /// 编辑命令在有多个光标时是否作用于所有光标：只有不会增减行的编辑才可以
+    fn applies_to_all_cursors(command: &Edit) -> bool {
+        match command {
+            Edit::Insert(_)
+            | Edit::Delete
+            | Edit::DeleteBackward
+            | Edit::DeleteWordBackward
+            | Edit::DeleteToLineEnd
+            | Edit::Indent => true,
+            Edit::Paste(text) => !text.contains(['\n', '\r']),
+            _ => false,
+        }
+    }
+
+    /// 在每个光标处执行编辑命令。
+    ///
+    /// 按文档顺序依次编辑，每次编辑后按该行字素数的变化调整同一行中后面的光标。
+    /// 会合并行的编辑(行首的退格、行尾的删除)在该光标处被跳过，使每个光标所在的行保持不变。
+    fn edit_at_all_cursors(&mut self, command: &Edit) {
+        self.clear_selection();
+        self.close_line_preview();
+        self.normalize_cursors();
+        let primary = self.text_location;
+        let scroll_offset = self.scroll_offset;
+        let scroll_wrap_row = self.scroll_wrap_row;
+        let mut cursors = mem::take(&mut self.extra_cursors);
+        cursors.push(primary);
+        cursors.sort_by_key(|cursor| (cursor.line_index, cursor.grapheme_index));
+        let primary_index = cursors.iter().position(|cursor| *cursor == primary).unwrap_or(0);
+        for index in 0..cursors.len() {
+            let Some(cursor) = cursors.get(index).copied() else {
+                break;
+            };
+            let line_end = self.line_grapheme_count(cursor.line_index);
+            let joins_lines = match command {
+                Edit::Delete => cursor.grapheme_index >= line_end,
+                Edit::DeleteBackward | Edit::DeleteWordBackward => cursor.grapheme_index == 0,
+                _ => false,
+            };
+            // 文档末尾之后的光标处输入会增加新行，同样跳过
+            if joins_lines || cursor.line_index >= self.buffer.height() {
+                continue;
+            }
+            self.text_location = cursor;
+            self.apply_edit(command.clone());
+            let edited = self.text_location;
+            let new_end = self.line_grapheme_count(cursor.line_index);
+            if let Some(slot) = cursors.get_mut(index) {
+                *slot = edited;
+            }
+            for later in cursors
+                .iter_mut()
+                .skip(index.saturating_add(1))
+                .take_while(|later| later.line_index == cursor.line_index)
+            {
+                let shifted = if new_end >= line_end {
+                    later.grapheme_index.saturating_add(new_end.saturating_sub(line_end))
+                } else {
+                    later.grapheme_index.saturating_sub(line_end.saturating_sub(new_end))
+                };
+                // 删除到行尾等编辑会删掉后面的光标所在的内容，这些光标停在编辑后的光标处
+                later.grapheme_index = shifted.clamp(edited.grapheme_index, new_end);
+            }
+        }
+        self.text_location = cursors.get(primary_index).copied().unwrap_or(primary);
+        self.extra_cursors = cursors;
+        self.normalize_cursors();
+        // 编辑各个光标时可能滚动了视图，恢复后只确保主光标可见
+        self.scroll_offset = scroll_offset;
+        self.scroll_wrap_row = scroll_wrap_row;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// 将其他光标调整到有效位置并按文档顺序排列，去掉重复的光标和与主光标重合的光标
+    fn normalize_cursors(&mut self) {
+        let height = self.buffer.height();
+        let primary = self.text_location;
+        let mut cursors = mem::take(&mut self.extra_cursors);
+        for cursor in &mut cursors {
+            cursor.line_index = min(cursor.line_index, height);
+            cursor.grapheme_index = min(cursor.grapheme_index, self.line_grapheme_count(cursor.line_index));
+        }
+        cursors.retain(|cursor| *cursor != primary);
+        cursors.sort_by_key(|cursor| (cursor.line_index, cursor.grapheme_index));
+        cursors.dedup();
+        self.extra_cursors = cursors;
+    }
+
+    // endregion
+
+    // region: folding
+    // 折叠代码区域
+
+    /// 折叠或展开光标所在行之后缩进更深的代码块。
+    ///
+    /// 光标所在行之后已经有折叠时展开它，否则折叠紧随其后、缩进比它更深的连续行，
+    /// 其中的空行一并折叠，末尾的空行除外。
+    ///
+    /// # 返回值
+    /// 既没有可以展开的折叠，也没有可以折叠的行时返回 `false`。
+    pub fn toggle_fold(&mut self) -> bool {
+        let line_index = self.text_location.line_index;
+        let body_start = line_index.saturating_add(1);
+        if let Some(index) = self.folds.iter().position(|fold| fold.start == body_start) {
+            self.folds.remove(index);
+            self.set_needs_redraw(true);
+            return true;
+        }
+        let Some(block) = self.indented_block_after(line_index) else {
+            return false;
+        };
+        self.collapse_cursors();
+        self.clear_selection();
+        self.close_line_preview();
+        // 新的折叠包含了其中已有的折叠
+        self.folds.retain(|fold| fold.end <= block.start || fold.start >= block.end);
+        let index = self.folds.partition_point(|fold| fold.start < block.start);
+        self.folds.insert(index, block);
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        true
+    }
+
+    /// 紧随指定行之后、缩进比它更深的连续行的范围。指定行是空行或者之后没有缩进更深的行时返回 `None`
+    fn indented_block_after(&self, line_index: LineIdx) -> Option<Range<LineIdx>> {
+        let line = self.buffer.lines.get(line_index)?;
+        if line.trim().is_empty() {
+            return None;
+        }
+        // 缩进只包含空格和制表符，每个字符都是一个字素
+        let indentation_width = |line: &Line| line.width_until(line.indentation().len());
+        let width = indentation_width(line);
+        let start = line_index.saturating_add(1);
+        let mut end = start;
+        for index in start..self.buffer.height() {
+            let Some(next) = self.buffer.lines.get(index) else {
+                break;
+            };
+            if next.trim().is_empty() {
+                continue;
+            }
+            if indentation_width(next) <= width {
+                break;
+            }
+            end = index.saturating_add(1);
+        }
+        (end > start).then_some(start..end)
+    }
+
+    /// 包含指定行的折叠
+    fn fold_at(&self, line_idx: LineIdx) -> Option<&Range<LineIdx>> {
+        self.folds.iter().find(|fold| fold.contains(&line_idx))
+    }
+
+    /// 指定行是否被折叠隐藏。折叠的第一行显示为摘要行，不算作隐藏
+    fn is_line_hidden(&self, line_idx: LineIdx) -> bool {
+        self.fold_at(line_idx).is_some_and(|fold| fold.start != line_idx)
+    }
+
+    /// 从 `from` 行到 `to` 行(不含)在视图中占用的行数，每个折叠只占一行
+    fn visible_lines_between(&self, from: LineIdx, to: LineIdx) -> usize {
+        let hidden = self.folds.iter().fold(0, |hidden: usize, fold| {
+            let start = max(fold.start.saturating_add(1), from);
+            hidden.saturating_add(min(fold.end, to).saturating_sub(start))
+        });
+        to.saturating_sub(from).saturating_sub(hidden)
+    }
+
+    /// 从指定行向上数 `count` 个视图中的行后到达的行，每个折叠只算一行，最多到文档开头
+    fn visible_lines_up(&self, mut line_idx: LineIdx, count: usize) -> LineIdx {
+        for _ in 0..count {
+            if line_idx == 0 {
+                break;
+            }
+            line_idx = line_idx.saturating_sub(1);
+            if let Some(fold) = self.fold_at(line_idx) {
+                line_idx = fold.start;
+            }
+        }
+        line_idx
+    }
+
+    /// 展开包含任何一个光标的折叠：光标进入折叠的行时折叠自动展开
+    fn unfold_at_cursors(&mut self) {
+        let count = self.folds.len();
+        let primary = self.text_location.line_index;
+        let extra_cursors = &self.extra_cursors;
+        self.folds.retain(|fold| {
+            !fold.contains(&primary) && !extra_cursors.iter().any(|cursor| fold.contains(&cursor.line_index))
+        });
+        if self.folds.len() != count {
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// 编辑后更新折叠。`line_index` 和 `height` 是编辑前光标所在的行和文档的行数。
+    ///
+    /// 编辑前后光标所在的行之前的折叠保持不变，之后的折叠随行数的变化移动。
+    /// 被编辑涉及的折叠直接展开，只修改折叠前一行的内容而不增减行时，折叠保持不变。
+    fn update_folds_after_edit(&mut self, line_index: LineIdx, height: usize) {
+        if self.folds.is_empty() {
+            return;
+        }
+        let new_height = self.buffer.height();
+        let added = new_height.saturating_sub(height);
+        let removed = height.saturating_sub(new_height);
+        // 被编辑涉及的行在编辑前的范围。插入行后光标下移，减去增加的行数才是编辑前的行
+        let first = min(line_index, self.text_location.line_index);
+        let last = max(line_index, self.text_location.line_index.saturating_sub(added));
+        let folds = mem::take(&mut self.folds);
+        self.folds = folds
+            .into_iter()
+            .filter_map(|fold| {
+                if fold.end <= first {
+                    return Some(fold);
+                }
+                let header = fold.start.saturating_sub(1);
+                if header > last || (header == last && added == 0 && removed == 0) {
+                    let shift = |line_idx: LineIdx| line_idx.saturating_add(added).saturating_sub(removed);
+                    return Some(shift(fold.start)..shift(fold.end));
+                }
+                None
+            })
+            .collect();
+        self.unfold_at_cursors();
+        self.set_needs_redraw(true);
+    }
+
+    // endregion
+
+    // region: search
+    // 搜索代码区域
+
+    /// 输入搜索
+    pub fn enter_search(&mut self) {
+        self.collapse_cursors();
+        // 输入搜索后,存储之前光标所在的位置
+        self.search_info = Some(SearchInfo {
+            prev_location: self.text_location,
+            prev_scroll_offset: self.scroll_offset,
+            query: None,
+            matches: Vec::new(),
+            wrapped: None,
+        });
+    }
+
+    /// 退出搜索
+    pub fn exit_search(&mut self) {
+        self.search_info = None;
+        self.set_needs_redraw(true);
+    }
+    
+    /// 关闭搜索
+    pub fn dismiss_search(&mut self) {
+        // search_info存有旧位置的信息就回到旧位置那
+        if let Some(search_info) = &self.search_info {
+            // 重置文本位置和关闭时的滚动偏移量
+            self.text_location = search_info.prev_location;
+            self.scroll_offset = search_info.prev_scroll_offset;
+            // 确保搜索时调整大小了,也能将view显示到对应位置
+            self.scroll_text_location_into_view();
+        }
+        self.exit_search();
+    }
+
+    /// 搜索操作
+    pub fn search(&mut self, query: &str) {
+        // 设置搜索内容，内容改变时重新统计所有匹配项
+        if let Some(search_info) = &mut self.search_info {
+            if search_info.query.as_deref() != Some(query) {
+                search_info.query = Some(Line::from(query));
+                search_info.matches = self.buffer.search_all(query);
+            }
+        }
+        // 使用当前位置调用 search_in_direction,默认向下搜索。输入搜索内容时不提示越过了边界
+        self.search_in_direction(self.text_location, SearchDirection::default());
+        self.set_search_wrapped(None);
+    }
+
+    // 尝试获取当前的搜索查询——适用于必须存在搜索查询的场景。
+    // 如果在debug模式下不存在搜索查询或搜索信息，则会触发 panic。
+    // 在生产模式下返回 None。
+    fn get_search_query(&self) -> Option<&Line> {
+        let query = self
+            .search_info
+            .as_ref()
+            .and_then(|search_info| search_info.query.as_ref());
+        debug_assert!(
+            query.is_some(),
+            "Attempting to search with malformed searchinfo present"
+        );
+        query
+    }
+
+    /// 按某个方向开始进行搜索(向上/向下)，并记录是否越过了文档的边界
+    fn search_in_direction(&mut self, from: Location, direction: SearchDirection) {
+        let mut wrapped = false;
+        if let Some((location, crossed)) = self.get_search_query().and_then(|query| {
+            // 从search_info取出要搜索的内容,判断是向上/向下搜索
+            if query.is_empty() {
+                None
+            } else if direction == SearchDirection::Forward {
+                self.buffer.search_forward(query, from)
+            } else {
+                self.buffer.search_backward(query, from)
+            }
+        })
+        // 查找到就移动到对应位置居中显示
+        {
+            self.text_location = location;
+            self.center_text_location();
+            wrapped = crossed;
+        }
+        self.set_search_wrapped(wrapped.then_some(direction));
+        self.set_needs_redraw(true);
+    }
+
+    fn set_search_wrapped(&mut self, wrapped: Option<SearchDirection>) {
+        if let Some(search_info) = &mut self.search_info {
+            search_info.wrapped = wrapped;
+        }
+    }
+
+    /// 最近一次查找下一个/上一个匹配项时越过文档边界的方向：
+    /// 向下查找时越过末尾回到顶部，向上查找时越过顶部回到末尾。没有越过或没有在搜索时返回 `None`
+    pub fn search_wrapped(&self) -> Option<SearchDirection> {
+        self.search_info.as_ref().and_then(|search_info| search_info.wrapped)
+    }
+
+    /// 搜索下一个关键词
+    pub fn search_next(&mut self) {
+        // 光标位于匹配项上时跳过整个匹配项，否则只移动1步，避免一直搜索到当前的关键词
+        let step_right = if self.is_on_search_match() {
+            self.get_search_query()
+                .map_or(1, |query| max(query.grapheme_count(), 1))
+        } else {
+            1
+        };
+        // 从当前搜索出来的关键词的字素结尾开始,搜索下一个关键词，不超过行尾
+        let line_index = self.text_location.line_index;
+        let line_end = self
+            .buffer
+            .lines
+            .get(line_index)
+            .map_or(0, Line::grapheme_count);
+        let location = Location {
+            line_index,
+            grapheme_index: min(
+                self.text_location.grapheme_index.saturating_add(step_right),
+                line_end,
+            ),
+        };
+        self.search_in_direction(location, SearchDirection::Forward);
+    }
+
+    // 搜索上一个关键词
+    pub fn search_prev(&mut self) {
+        self.search_in_direction(self.text_location, SearchDirection::Backward);
+    }
+
+    /// 获取光标所在匹配项的序号(从 1 开始，不在匹配项上时为 0)和匹配项总数。
+    ///
+    /// 没有在搜索或搜索内容为空时返回 `None`。
+    pub fn search_match_position(&self) -> Option<(usize, usize)> {
+        let search_info = self.search_info.as_ref()?;
+        if search_info.query.as_ref().is_none_or(|query| query.is_empty()) {
+            return None;
+        }
+        let current = search_info
+            .matches
+            .iter()
+            .position(|location| *location == self.text_location)
+            .map_or(0, |index| index.saturating_add(1));
+        Some((current, search_info.matches.len()))
+    }
+
+    /// 光标当前是否正位于搜索内容的一个匹配项上
+    pub fn is_on_search_match(&self) -> bool {
+        let Some(query) = self
+            .search_info
+            .as_ref()
+            .and_then(|search_info| search_info.query.as_ref())
+        else {
+            return false;
+        };
+        let Location { line_index, grapheme_index } = self.text_location;
+        !query.is_empty()
+            && self.buffer.lines.get(line_index).is_some_and(|line| {
+                line.search_forward(query, grapheme_index) == Some(grapheme_index)
+            })
+    }
+
+    /// 替换光标处的匹配项，然后跳转到下一个匹配项。
+    ///
+    /// # 参数
+    /// - `replacement`: 用于替换匹配项的字符串。
+    ///
+    /// # 返回值
+    /// 如果光标处存在匹配项并完成了替换，则返回 `true`。
+    pub fn replace_next(&mut self, replacement: &str) -> bool {
+        if !self.is_on_search_match() {
+            return false;
+        }
+        let query_len = self.get_search_query().map_or(0, Line::grapheme_count);
+        let end = self.replace_at(self.text_location, query_len, replacement);
+        // 文档内容改变了，重新统计匹配项
+        if let Some(search_info) = &mut self.search_info {
+            search_info.matches = search_info
+                .query
+                .as_deref()
+                .map_or_else(Vec::new, |query| self.buffer.search_all(query));
+        }
+        // 从替换内容的末尾开始搜索，避免匹配到刚插入的内容
+        self.text_location = end;
+        self.search_in_direction(end, SearchDirection::Forward);
+        true
+    }
+
+    /// 从文档开头开始替换所有匹配项。
+    ///
+    /// # 参数
+    /// - `replacement`: 用于替换匹配项的字符串。
+    ///
+    /// # 返回值
+    /// 返回替换的次数。
+    pub fn replace_all(&mut self, replacement: &str) -> usize {
+        let Some(query) = self.get_search_query().map(ToString::to_string) else {
+            return 0;
+        };
+        if query.is_empty() {
+            return 0;
+        }
+        let query_len = Line::from(&query).grapheme_count();
+        let mut count: usize = 0;
+        for line_index in 0..self.buffer.height() {
+            let mut from = Location {
+                grapheme_index: 0,
+                line_index,
+            };
+            // 每次替换后从替换内容的末尾继续搜索，保证不会在新插入的内容中再次匹配
+            while let Some(grapheme_index) = self
+                .buffer
+                .lines
+                .get(line_index)
+                .and_then(|line| line.search_forward(&query, from.grapheme_index))
+            {
+                from = self.replace_at(
+                    Location {
+                        grapheme_index,
+                        line_index,
+                    },
+                    query_len,
+                    replacement,
+                );
+                count = count.saturating_add(1);
+            }
+        }
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        count
+    }
+
+    /// 删除指定位置起的 `len` 个字素，并在该位置插入替换内容。
+    ///
+    /// # 返回值
+    /// 返回替换内容末尾的位置。
+    fn replace_at(&mut self, at: Location, len: GraphemeIdx, replacement: &str) -> Location {
+        for _ in 0..len {
+            self.buffer.delete(at);
+        }
+        let mut end = at;
+        for character in replacement.chars() {
+            let old_len = self.line_grapheme_count(end.line_index);
+            self.buffer.insert_char(character, end);
+            // 组合字符不会增加字素数量，所以根据插入前后的长度差来移动位置
+            let new_len = self.line_grapheme_count(end.line_index);
+            end.grapheme_index = end
+                .grapheme_index
+                .saturating_add(new_len.saturating_sub(old_len));
+        }
+        self.set_needs_redraw(true);
+        end
+    }
+
+    /// 获取指定行的字素数量
+    fn line_grapheme_count(&self, line_index: LineIdx) -> GraphemeIdx {
+        self.buffer
+            .lines
+            .get(line_index)
+            .map_or(0, Line::grapheme_count)
+    }
+    // endregion
+    // 搜索代码区域结束
+
+    // region: file i/o
+    // 文件io处理代码区域
+
+    /// 读取文件内容并加载到缓冲区。
+    ///
+    /// # 参数
+    /// - `file_name`: 要加载的文件名。
+    ///
+    /// 如果文件加载成功，则将其内容保存到缓冲区，并标记视图需要重新渲染。
+    pub fn load(&mut self, file_name: &str) -> Result<(), Error> {
+        let fallback = self.fallback_encoding.unwrap_or(encoding::DEFAULT_FALLBACK);
+        let buffer = Buffer::load(file_name, fallback)?;
+        self.set_buffer(buffer);
+        Ok(())
+    }
+
+    /// 使用从文件中读取的缓冲区，应用 modeline、制表符宽度和语法高亮等设置
+    fn set_buffer(&mut self, mut buffer: Buffer) {
+        if self.read_modelines {
+            buffer.modeline = buffer.find_modeline();
+        }
+        // 没有写权限的文件以只读方式打开，重新加载时按文件当前的权限重新判断
+        self.read_only = self.force_read_only || buffer.file_info.is_read_only();
+        self.buffer = buffer;
+        self.buffer.set_tab_width(self.tab_width());
+        self.select_highlighter();
+        self.selection_anchor = None;
+        self.collapse_cursors();
+        self.folds.clear();
+        // 只读的文件不能保存，不做修改
+        self.normalized_lines = 0;
+        if self.normalize_indentation && !self.read_only && self.buffer.has_mixed_indentation() {
+            self.normalized_lines = self.buffer.normalize_indentation(self.expand_tabs).len();
+        }
+        self.set_needs_redraw(true);
+    }
+
+    /// 从磁盘重新读取当前文件，丢弃未保存的修改，光标尽量留在原来的行上
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let file_name = self.file_name_to_reload()?;
+        let location = self.text_location;
+        self.load(&file_name)?;
+        self.restore_location(location);
+        Ok(())
+    }
+
+    /// 按指定的编码重新读取当前文件，光标尽量保持在原来的位置。
+    ///
+    /// # 返回值
+    /// 编码名称无法识别时返回 `ErrorKind::InvalidInput` 错误，文件不能按该编码无损解码时返回
+    /// `ErrorKind::InvalidData` 错误。出错时缓冲区保持不变。
+    pub fn reload_with_encoding(&mut self, label: &str) -> Result<(), Error> {
+        let encoding = TextEncoding::for_label(label)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Unknown encoding {label}")))?;
+        let file_name = self.file_name_to_reload()?;
+        let location = self.text_location;
+        let buffer = Buffer::load_as(&file_name, encoding)?;
+        self.set_buffer(buffer);
+        self.restore_location(location);
+        Ok(())
+    }
+
+    /// 当前缓冲区关联的文件名，没有关联文件时返回错误
+    fn file_name_to_reload(&self) -> Result<String, Error> {
+        self.buffer
+            .file_info
+            .get_path()
+            .and_then(|path| path.to_str())
+            .map(String::from)
+            .ok_or_else(|| Error::other("No file to reload"))
+    }
+
+    /// 重新读取文件后将光标移回原来的位置，超出新内容范围时移到最近的有效位置
+    fn restore_location(&mut self, location: Location) {
+        self.text_location = location;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.scroll_text_location_into_view();
+    }
+
+    /// 检查当前文件在磁盘上是否被其他程序修改或删除
+    pub fn check_file_on_disk(&mut self) -> DiskChange {
+        self.buffer.file_info.check_on_disk()
+    }
+
+    /// 设置是否强制只读。不强制只读时，没有写权限的文件仍然以只读方式打开
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.force_read_only = read_only;
+        self.read_only = read_only || self.buffer.file_info.is_read_only();
+    }
+
+    /// 获取是否只读
+    pub const fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// 设置加载文件时是否读取其中的 modeline
+    pub fn set_read_modelines(&mut self, enabled: bool) {
+        self.read_modelines = enabled;
+    }
+
+    /// 获取当前文件 modeline 指定的制表符宽度，没有指定时返回 `None`
+    pub fn modeline_tab_width(&self) -> Option<ColIdx> {
+        self.buffer.modeline.and_then(|modeline| modeline.tab_width)
+    }
+
+    /// 设置制表符宽度。文件的 modeline 中指定了宽度时，仍以 modeline 为准
+    pub fn set_tab_width(&mut self, tab_width: ColIdx) {
+        self.tab_width = Some(tab_width.max(1));
+        if self.buffer.set_tab_width(self.tab_width()) {
+            // 制表符宽度变化后光标所在的列也会变化
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// 获取当前生效的制表符宽度
+    pub fn tab_width(&self) -> ColIdx {
+        self.modeline_tab_width()
+            .or(self.tab_width)
+            .unwrap_or(DEFAULT_TAB_WIDTH)
+    }
+
+    /// 设置按 Tab 键时是否插入空格
+    pub fn set_expand_tabs(&mut self, enabled: bool) {
+        self.expand_tabs = enabled;
+    }
+
+    /// 设置加载混用了制表符和空格缩进的文件时是否统一缩进
+    pub fn set_normalize_indentation(&mut self, enabled: bool) {
+        self.normalize_indentation = enabled;
+    }
+
+    /// 设置保存时是否删除行尾的空白
+    pub fn set_trim_trailing_whitespace(&mut self, enabled: bool) {
+        self.trim_trailing_whitespace = enabled;
+    }
+
+    /// 设置是否折行显示超出视图宽度的行。
+    ///
+    /// 折行时在单词之间断行，水平滚动被禁用；上下移动光标仍然按文档中的行移动。
+    pub fn set_wrap(&mut self, enabled: bool) {
+        if self.wrap == enabled {
+            return;
+        }
+        self.wrap = enabled;
+        self.scroll_offset.col = 0;
+        self.scroll_wrap_row = 0;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// 设置视图左边缘在终端上的列
+    pub fn set_origin_col(&mut self, origin_col: ColIdx) {
+        if self.origin_col != origin_col {
+            self.origin_col = origin_col;
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// 设置是否在状态栏中显示单词数和字符数
+    pub fn set_show_word_count(&mut self, enabled: bool) {
+        self.show_word_count = enabled;
+    }
+
+    /// 获取文档的单词数和字符数
+    pub fn word_and_char_count(&self) -> (usize, usize) {
+        (self.buffer.word_count(), self.buffer.char_count())
+    }
+
+    /// 设置保存时是否删除文档末尾的空白行
+    pub fn set_trim_trailing_blank_lines(&mut self, enabled: bool) {
+        self.trim_trailing_blank_lines = enabled;
+    }
+
+    /// 设置第一次保存已有文件前是否备份原文件
+    pub fn set_backup(&mut self, enabled: bool) {
+        self.backup = enabled;
+    }
+
+    /// 设置无法识别文件编码时使用的编码。
+    ///
+    /// # 返回值
+    /// 如果无法识别编码标签，则返回 `false`，并继续使用原来的编码。
+    pub fn set_fallback_encoding(&mut self, label: &str) -> bool {
+        TextEncoding::for_label(label).is_some_and(|encoding| {
+            self.fallback_encoding = Some(encoding);
+            true
+        })
+    }
+
+    /// 文件解码是否有损
+    pub const fn is_decoded_lossily(&self) -> bool {
+        self.buffer.lossy_decoded
+    }
+
+    /// 加载文件时统一了缩进的行数，没有统一缩进时为 0
+    pub const fn normalized_indentation_lines(&self) -> usize {
+        self.normalized_lines
+    }
+
+    /// 加载的文件是否混用了多种换行符
+    pub const fn has_mixed_line_endings(&self) -> bool {
+        self.buffer.mixed_line_endings
+    }
+
+    /// 保存缓冲区内容到文件
+    pub fn save(&mut self) -> Result<(), Error> {
+        self.trim_before_save();
+        self.buffer.save(self.backup)?;
+        // 清除行号栏中修改过的行的标记
+        self.set_needs_redraw(true);
+        Ok(())
+    }
+
+    /// 获取另存为的目标文件是否已经存在，存在时保存会覆盖它
+    pub fn file_exists(file_name: &str) -> bool {
+        FileInfo::from(file_name).exists()
+    }
+
+    /// 另存为缓冲区内容到新文件
+    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
+        self.trim_before_save();
+        self.buffer.save_as(file_name)?;
+        // 文件扩展名可能发生了变化
+        self.select_highlighter();
+        Ok(())
+    }
+
+    /// 启用了相应选项时，保存前删除行尾的空白和文档末尾的空白行，
+    /// 并让光标和选区锚点留在有效的位置上
+    fn trim_before_save(&mut self) {
+        if self.trim_trailing_whitespace {
+            let edits = self.buffer.trim_trailing_whitespace();
+            self.apply_line_edits(&edits);
+        }
+        if self.trim_trailing_blank_lines && self.buffer.trim_trailing_blank_lines() {
+            // 光标和锚点不能留在被删除的行上
+            let last_line = self.buffer.height().saturating_sub(1);
+            self.text_location.line_index = self.text_location.line_index.min(last_line);
+            self.snap_to_valid_grapheme();
+            if let Some(anchor) = self
+                .selection_anchor
+                .as_mut()
+                .filter(|anchor| anchor.line_index > last_line)
+            {
+                *anchor = Location {
+                    line_index: last_line,
+                    grapheme_index: self.buffer.lines.get(last_line).map_or(0, Line::grapheme_count),
+                };
+            }
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// 根据当前文件的扩展名重新选择语法高亮器
+    fn select_highlighter(&mut self) {
+        self.highlighter = syntax::for_extension(self.buffer.file_info.extension());
+        // 配对括号的查找依赖高亮器，新的缓冲区的修订号也可能与之前的相同
+        self.caret_cache.take();
+        self.set_needs_redraw(true);
+    }
+
+    // 文件io处理代码区域结束
+
+    // region: Text editing
+    // 文本编辑代码区域
+
+    fn insert_newline(&mut self) {
+        let indentation = if self.auto_indent {
+            self.indentation_before_caret()
+        } else {
+            String::new()
+        };
+        if self
+            .buffer
+            .insert_newline(self.text_location, self.skip_newline_at_eof)
+        {
+            self.handle_move_command(Move::Right);
+            if !indentation.is_empty() {
+                self.text_location = self.replace_at(self.text_location, 0, &indentation);
+                self.scroll_text_location_into_view();
+            }
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// 获取当前行光标之前的缩进。
+    ///
+    /// 光标位于缩进中间时只取光标之前的部分，剩余的缩进会随拆分移到新行，
+    /// 这样新行的缩进总量与原来一致。
+    fn indentation_before_caret(&self) -> String {
+        let Location { line_index, grapheme_index } = self.text_location;
+        self.buffer.lines.get(line_index).map_or_else(String::new, |line| {
+            // 缩进只包含空格和制表符，字素数量与字节数相同
+            let indentation = line.indentation();
+            let end = grapheme_index.min(indentation.len());
+            indentation.get(..end).unwrap_or_default().to_string()
+        })
+    }
+
+    /// 在光标处插入粘贴的文本，光标移动到粘贴内容的末尾
+    fn paste(&mut self, text: &str) {
+        // 统一换行符为 \n，保存时再按文件的换行符类型写回
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.text_location = self.buffer.insert_text(self.text_location, &text);
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    fn delete_backward(&mut self) {
+        // 光标位于一对空的括号或引号之间时，同时删除两者
+        let Location { line_index, grapheme_index } = self.text_location;
+        if self.auto_pair && grapheme_index > 0 {
+            let before = Location { line_index, grapheme_index: grapheme_index.saturating_sub(1) };
+            if AUTO_PAIRS
+                .iter()
+                .any(|(open, close)| self.grapheme_is(before, *open) && self.grapheme_is(self.text_location, *close))
+            {
+                self.buffer
+                    .delete_range(line_index, before.grapheme_index..grapheme_index.saturating_add(1));
+                self.text_location = before;
+                self.scroll_text_location_into_view();
+                self.set_needs_redraw(true);
+                return;
+            }
+        }
+        // 确保我们只在文档贯标不位于左上角时向左移动。
+        if self.text_location.line_index != 0 || self.text_location.grapheme_index != 0 {
+            self.handle_move_command(Move::Left);
+            self.delete();
+        }
+    }
+
+    /// 删除光标处的字素，位于行尾时将下一行合并到当前行。
+    ///
+    /// 合并后光标仍停在原来的行尾，即两行的连接处。下一行开头的宽字符会移到光标处，
+    /// 所以需要重新确保光标处的字素完整地显示在视图中。
+    fn delete(&mut self) {
+        self.buffer.delete(self.text_location);
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// 删除光标左侧到上一个单词开头之间的内容。位于行首时与退格相同，会合并到上一行。
+    fn delete_word_backward(&mut self) {
+        let Location { line_index, grapheme_index } = self.text_location;
+        if grapheme_index == 0 {
+            self.delete_backward();
+            return;
+        }
+        let word_start = self
+            .buffer
+            .lines
+            .get(line_index)
+            .map_or(0, |line| line.prev_word_start(grapheme_index));
+        self.buffer.delete_range(line_index, word_start..grapheme_index);
+        self.text_location.grapheme_index = word_start;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// 删除从光标到行尾的内容，不会合并下一行
+    fn delete_to_line_end(&mut self) {
+        let Location { line_index, grapheme_index } = self.text_location;
+        let line_end = self.line_grapheme_count(line_index);
+        self.buffer.delete_range(line_index, grapheme_index..line_end);
+        self.set_needs_redraw(true);
+    }
+
+    /// 复制当前行并插入到下一行，光标移动到复制出的行上，保持所在的列
+    fn duplicate_line(&mut self) {
+        if self.buffer.duplicate_line(self.text_location.line_index) {
+            self.move_down(1);
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// 删除当前行，光标移动到下一行(删除的是最后一行时为上一行)的开头
+    fn delete_line(&mut self) {
+        if !self.buffer.delete_line(self.text_location.line_index) {
+            return;
+        }
+        self.text_location = Location {
+            grapheme_index: 0,
+            line_index: min(self.text_location.line_index, self.buffer.height().saturating_sub(1)),
+        };
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// 将下一行合并到当前行，光标移动到合并点。位于最后一行时不做任何操作
+    fn join_line(&mut self) {
+        let line_index = self.text_location.line_index;
+        if let Some(join_point) = self.buffer.join_next_line(line_index) {
+            self.text_location = Location {
+                grapheme_index: join_point,
+                line_index,
+            };
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+        }
+    }
+
+    /// 将当前行与上一行(`up` 为 `true` 时)或下一行交换，光标跟随当前行移动
+    fn move_line(&mut self, up: bool) {
+        if !self.buffer.move_line(self.text_location.line_index, up) {
+            return;
+        }
+        if up {
+            self.move_up(1);
+        } else {
+            self.move_down(1);
+        }
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    /// 在分隔符出现的每个位置拆分当前行，光标移动到拆分后第一行的开头。
+    ///
+    /// # 返回值
+    /// 返回拆分后得到的行数。
+    pub fn split_current_line(&mut self, delimiter: &str) -> usize {
+        let line_index = self.text_location.line_index;
+        let height = self.buffer.height();
+        let count = self.buffer.split_line_at(line_index, delimiter);
+        if count > 1 {
+            self.update_folds_after_edit(line_index, height);
+            self.move_to_start_of_line();
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+        }
+        count
+    }
+
+    /// 指定位置的字素是否是给定的字符
+    fn grapheme_is(&self, at: Location, character: char) -> bool {
+        let mut buffer = [0; 4];
+        let expected: &str = character.encode_utf8(&mut buffer);
+        self.buffer
+            .lines
+            .get(at.line_index)
+            .and_then(|line| line.graphemes(true).nth(at.grapheme_index))
+            .is_some_and(|grapheme| grapheme == expected)
+    }
+
+    /// 指定位置的字素是否以字母或数字开头，位置超出行尾时返回 `false`
+    fn is_word_grapheme(&self, at: Location) -> bool {
+        self.buffer
+            .lines
+            .get(at.line_index)
+            .and_then(|line| line.graphemes(true).nth(at.grapheme_index))
+            .and_then(|grapheme| grapheme.chars().next())
+            .is_some_and(char::is_alphanumeric)
+    }
+
+    /// 自动配对括号和引号。
+    ///
+    /// 光标处已经是要输入的闭括号(引号)时直接越过它；输入开括号(引号)时同时插入对应的闭括号(引号)，光标留在两者之间。
+    /// 光标处是字母或数字时不配对，引号前面是字母或数字时(如 `don't`)也不配对。
+    ///
+    /// # 返回值
+    /// 已经处理了输入的字符时返回 `true`，否则需要按普通字符插入。
+    fn insert_pair(&mut self, character: char) -> bool {
+        let Location { line_index, grapheme_index } = self.text_location;
+        if AUTO_PAIRS.iter().any(|(_, close)| *close == character) && self.grapheme_is(self.text_location, character) {
+            self.handle_move_command(Move::Right);
+            return true;
+        }
+        let Some((_, close)) = AUTO_PAIRS.iter().find(|(open, _)| *open == character) else {
+            return false;
+        };
+        let is_quote = character == *close;
+        let after_word = grapheme_index > 0
+            && self.is_word_grapheme(Location { line_index, grapheme_index: grapheme_index.saturating_sub(1) });
+        if self.is_word_grapheme(self.text_location) || (is_quote && after_word) {
+            return false;
+        }
+        self.buffer.insert_char(*close, self.text_location);
+        self.buffer.insert_char(character, self.text_location);
+        self.handle_move_command(Move::Right);
+        self.set_needs_redraw(true);
+        true
+    }
+
+    // 插入字符
+    fn insert_char(&mut self, character: char) {
+        // 开启 expand_tabs 时用制表符宽度个空格代替制表符
+        if character == '\t' && self.expand_tabs {
+            for _ in 0..self.tab_width() {
+                self.insert_char(' ');
+            }
+            return;
+        }
+        if self.auto_pair && !self.overwrite && self.insert_pair(character) {
+            return;
+        }
+
+        // 改写模式下先删除光标处的字素，位于行尾时仍然追加。
+        // 宽度为 0 的组合字符会与前一个字素组合，不替换任何字素
+        let Location { line_index, grapheme_index } = self.text_location;
+        if self.overwrite
+            && grapheme_index < self.line_grapheme_count(line_index)
+            && UnicodeWidthChar::width(character) != Some(0)
+        {
+            self.buffer
+                .delete_range(line_index, grapheme_index..grapheme_index.saturating_add(1));
+        }
+
+        // 获取当前所在行的内容长度
+        let old_len = self.buffer
+            .lines
+            .get(self.text_location.line_index)
+            .map_or(0, Line::grapheme_count);
+
+        // 在位置上插入字符
+        self.buffer.insert_char(character, self.text_location);
+
+        // 获取插入后的长度
+        let new_len = self.buffer
+            .lines
+            .get(self.text_location.line_index)
+            .map_or(0, Line::grapheme_count);
+
+        // 正常来说，插入字符后光标要右移一下。这里通过插入前后得长度查来判断
+        let grapheme = new_len.saturating_sub(old_len);
+        if grapheme > 0 {
+            self.handle_move_command(Move::Right);
+        }
+
+        self.set_needs_redraw(true);
+    }
+    // 文本编辑代码区域结束
+
+
+    // region: Rendering
+    // 渲染方法代码
+
+
+    /// 渲染指定行的内容。
+    ///
+    /// # 参数
     /// - `at`: 行号，表示要渲染到的目标行。
     /// - `line_text`: 要渲染的文本内容。
     ///
-    /// 清除指定行的内容，将文本渲染到该终端行。
-    fn render_line(at: RowIdx, line_text: &str) -> Result<(), Error> {
-        Terminal::print_row(at, line_text)
+    /// 清除视图在指定行中的内容，将文本渲染到该终端行。
+    fn render_line(&self, at: RowIdx, line_text: &str) -> Result<(), Error> {
+        Terminal::print_at(self.row_origin(at), self.size.width, line_text)
+    }
+
+    /// 清除视图在指定行中的内容，将带标注的文本渲染到该终端行。
+    fn render_annotated_line(&self, at: RowIdx, annotated_string: &AnnotatedString) -> Result<(), Error> {
+        Terminal::print_annotated_at(self.row_origin(at), self.size.width, annotated_string)
+    }
+
+    /// 视图中指定终端行的起始位置
+    fn row_origin(&self, row: RowIdx) -> Position {
+        Position {
+            row,
+            col: self.origin_col,
+        }
+    }
+
+    /// 构建欢迎信息字符串，欢迎信息内容会居中显示在终端宽度范围内。
+    ///
+    /// # 参数
+    /// - `width`: 终端的宽度，用于决定欢迎信息的显示位置。
+    ///
+    /// # 返回值
+    /// - 返回一个格式化后的欢迎信息，若终端宽度小于欢迎信息长度，则返回波浪符 "~"。
+    fn build_welcome_message(width: usize) -> String {
+        if width == 0 {
+            return String::new()
+        }
+        let welcome_message = format!("{NAME} editor -- version {VERSION}");
+        let len = welcome_message.len();
+        let remaining_width = width.saturating_sub(1);
+        // 宽度不够就隐藏隐藏欢迎消息
+        if remaining_width < len {
+            return "~".to_string();
+        }
+
+        format!("{:<1}{:^remaining_width$}", "~", welcome_message)
+    }
+
+    /// 计算行号栏的宽度：行号的位数加上一个分隔空格。不显示行号时为 0。
+    fn gutter_width(&self) -> ColIdx {
+        if !self.show_line_numbers {
+            return 0;
+        }
+        // 空缓冲区也至少显示 "1"
+        let digits = self.buffer.height().max(1).ilog10().saturating_add(1);
+        usize::try_from(digits).unwrap_or(1).saturating_add(1)
+    }
+
+    /// 滚动条占用的宽度。显示滚动条时即使整个文档都能显示在视图中也保留这一列，避免文本区域的宽度随行数变化
+    fn scrollbar_width(&self) -> ColIdx {
+        usize::from(self.show_scrollbar)
+    }
+
+    /// 计算可用于显示文本的宽度(view宽度减去行号栏和滚动条的宽度)
+    fn text_width(&self) -> ColIdx {
+        self.size
+            .width
+            .saturating_sub(self.gutter_width())
+            .saturating_sub(self.scrollbar_width())
+    }
+
+    /// 计算滚动条滑块在视图中占据的行。
+    ///
+    /// 滑块的长度与视图高度占文档行数的比例一致(至少一行)，位置与视图顶部的行在可滚动范围中的位置一致：
+    /// 视图位于文档开头时滑块在最上方，显示到文档最后一行时滑块在最下方。
+    /// 整个文档都能显示在视图中时返回 `None`。
+    fn scrollbar_thumb(height: usize, total: usize, top: LineIdx) -> Option<Range<RowIdx>> {
+        if height == 0 || total <= height {
+            return None;
+        }
+        let size = height
+            .saturating_mul(height)
+            .checked_div(total)
+            .unwrap_or(0)
+            .clamp(1, height);
+        let max_top = total.saturating_sub(height);
+        let start = top
+            .min(max_top)
+            .saturating_mul(height.saturating_sub(size))
+            .checked_div(max_top)
+            .unwrap_or(0);
+        Some(start..start.saturating_add(size))
+    }
+
+    /// 在视图最右侧一列绘制滚动条。折行显示时同样按文档的行计算滑块的位置
+    fn draw_scrollbar(&self, origin_row: RowIdx) -> Result<(), Error> {
+        let Some(thumb) = Self::scrollbar_thumb(self.size.height, self.buffer.height(), self.scroll_offset.row) else {
+            return Ok(());
+        };
+        let col = self
+            .origin_col
+            .saturating_add(self.size.width.saturating_sub(1));
+        for row in 0..self.size.height {
+            let symbol = if thumb.contains(&row) { "█" } else { "│" };
+            Terminal::print_at(Position { row: origin_row.saturating_add(row), col }, 1, symbol)?;
+        }
+        Ok(())
+    }
+
+    /// 构建指定行的行号字符串。
+    ///
+    /// 当前行的行号右对齐，其他行左对齐，以便区分光标所在的行。
+    fn build_line_number(&self, line_idx: LineIdx) -> String {
+        let digits = self.gutter_width().saturating_sub(1);
+        let number = line_idx.saturating_add(1);
+        if line_idx == self.text_location.line_index {
+            format!("{number:>digits$} ")
+        } else {
+            format!("{number:<digits$} ")
+        }
+    }
+
+    /// 光标处的配对括号和单词。光标、内容和视图的行数都没有变化时直接使用上次的结果。
+    ///
+    /// 为了在很长的代码块中移动光标时保持流畅，只在视图的行数加上 `BRACKET_SCAN_MARGIN`
+    /// 行的范围内查找配对括号
+    fn caret_context(&self) -> Ref<'_, CaretCache> {
+        let key = (self.text_location, self.buffer.revision(), self.size.height);
+        if self.caret_cache.borrow().key != Some(key) {
+            let max_lines = self.size.height.saturating_add(BRACKET_SCAN_MARGIN);
+            let brackets = self
+                .buffer
+                .find_matching_bracket(self.text_location, self.highlighter.as_ref(), max_lines)
+                .map(|matching| (self.text_location, matching));
+            *self.caret_cache.borrow_mut() = CaretCache {
+                key: Some(key),
+                brackets,
+                word: self.find_word_under_caret(),
+            };
+        }
+        self.caret_cache.borrow()
+    }
+
+    /// 光标位于括号上且括号有配对时，返回光标处的括号和与之配对的括号的位置
+    fn matching_brackets(&self) -> Option<(Location, Location)> {
+        self.caret_context().brackets
+    }
+
+    /// 开启了单词高亮时，返回主光标下的单词。
+    ///
+    /// 光标位于空白、标点或行尾，或者有选区时返回 `None`，此时不高亮任何内容
+    fn word_under_caret(&self) -> Option<String> {
+        if !self.highlight_occurrences || self.selection_anchor.is_some() {
+            return None;
+        }
+        self.caret_context().word.clone()
+    }
+
+    /// 提取主光标下的单词
+    fn find_word_under_caret(&self) -> Option<String> {
+        let Location { line_index, grapheme_index } = self.text_location;
+        let word = self.buffer.lines.get(line_index)?.word_at(grapheme_index)?;
+        Some(self.current_line_text().graphemes(true).skip(word.start).take(word.len()).collect())
+    }
+
+    /// 获取一行的语法高亮，并加上位于该行的配对括号和光标所在单词(`word`)的出现位置的高亮
+    fn line_highlights(
+        &self,
+        line: &Line,
+        line_idx: LineIdx,
+        brackets: Option<(Location, Location)>,
+        word: Option<&str>,
+    ) -> Vec<Annotation> {
+        let mut highlights = self.highlighter.highlight(line);
+        // 单词的出现位置显示在语法高亮之上，选区和搜索匹配之下
+        for range in word.map(|word| line.find_words(word)).unwrap_or_default() {
+            highlights.push(Annotation {
+                annotation_type: AnnotationType::WordOccurrence,
+                start: range.start,
+                end: range.end,
+            });
+        }
+        for location in brackets.into_iter().flat_map(|(bracket, matching)| [bracket, matching]) {
+            if location.line_index != line_idx {
+                continue;
+            }
+            if let Some((start, grapheme)) = line.grapheme_indices(true).nth(location.grapheme_index) {
+                highlights.push(Annotation {
+                    annotation_type: AnnotationType::MatchingBracket,
+                    start,
+                    end: start.saturating_add(grapheme.len()),
+                });
+            }
+        }
+        // 标尺列上的字素显示为标尺的背景色，位于语法高亮之上
+        if let Some(ruler) = self.ruler_column {
+            if let Some((start, grapheme)) = line.grapheme_indices(true).nth(line.grapheme_idx_at_column(ruler)) {
+                highlights.push(Annotation {
+                    annotation_type: AnnotationType::Ruler,
+                    start,
+                    end: start.saturating_add(grapheme.len()),
+                });
+            }
+        }
+        // 其他光标下的字素反色显示，行尾的光标由 `append_line_end_marks` 显示
+        for cursor in self.extra_cursors.iter().filter(|cursor| cursor.line_index == line_idx) {
+            if let Some((start, grapheme)) = line.grapheme_indices(true).nth(cursor.grapheme_index) {
+                highlights.push(Annotation {
+                    annotation_type: AnnotationType::Cursor,
+                    start,
+                    end: start.saturating_add(grapheme.len()),
+                });
+            }
+        }
+        highlights
     }
 
-    /// 构建欢迎信息字符串，欢迎信息内容会居中显示在终端宽度范围内。
-    ///
-    /// # 参数
-    /// - `width`: 终端的宽度，用于决定欢迎信息的显示位置。
+    /// 在可见内容之后显示位于行尾之后的标记：行尾的其他光标，以及行没有延伸到的标尺列。
     ///
-    /// # 返回值
-    /// - 返回一个格式化后的欢迎信息，若终端宽度小于欢迎信息长度，则返回波浪符 "~"。
-    fn build_welcome_message(width: usize) -> String {
-        if width == 0 {
-            return String::new()
+    /// 标记之前用空格补齐。`range` 是这一行显示的列范围，不在其中的标记(如水平滚动后位于视图之外)不显示。
+    fn append_line_end_marks(
+        &self,
+        annotated_string: &mut AnnotatedString,
+        line: &Line,
+        line_idx: LineIdx,
+        range: &Range<ColIdx>,
+    ) {
+        let width = line.width();
+        let cursor_at_end = self
+            .extra_cursors
+            .iter()
+            .any(|cursor| cursor.line_index == line_idx && cursor.grapheme_index >= line.grapheme_count());
+        let mut marks = Vec::new();
+        if cursor_at_end {
+            marks.push((width, AnnotationType::Cursor));
         }
-        let welcome_message = format!("{NAME} editor -- version {VERSION}");
-        let len = welcome_message.len();
-        let remaining_width = width.saturating_sub(1);
-        // 宽度不够就隐藏隐藏欢迎消息
-        if remaining_width < len {
-            return "~".to_string();
+        // 标尺与行尾的光标在同一列时只显示光标
+        if let Some(ruler) = self.ruler_column.filter(|ruler| *ruler > width || (*ruler == width && !cursor_at_end)) {
+            marks.push((ruler, AnnotationType::Ruler));
         }
+        let mut column = width.clamp(range.start, range.end);
+        for (mark, annotation_type) in marks {
+            if !range.contains(&mark) {
+                continue;
+            }
+            annotated_string.append(&" ".repeat(mark.saturating_sub(column)));
+            annotated_string.append_annotated(" ", annotation_type);
+            column = mark.saturating_add(1);
+        }
+    }
 
-        format!("{:<1}{:^remaining_width$}", "~", welcome_message)
+    /// 渲染代替折叠的行显示的摘要行，摘要沿用折叠中第一行的缩进
+    fn render_fold_summary(&self, at: RowIdx, fold: &Range<LineIdx>, range: &Range<ColIdx>) -> Result<(), Error> {
+        let indentation = self.buffer.lines.get(fold.start).map_or("", Line::indentation);
+        let count = fold.len();
+        let unit = if count == 1 { "line" } else { "lines" };
+        let summary = Line::new(&format!("{indentation}... {count} {unit}"), self.tab_width());
+        // 折行显示时摘要行只有一个空的折行范围，显示视图宽度内的部分
+        let range = if self.wrap { 0..self.text_width() } else { range.clone() };
+        let mut annotated_string = AnnotatedString::from(&summary.get_visible_graphemes(range));
+        annotated_string.add_annotation(AnnotationType::Fold, 0, annotated_string.to_string().len());
+        annotated_string.prepend(&" ".repeat(self.gutter_width()));
+        self.render_annotated_line(at, &annotated_string)
+    }
+
+    /// 渲染当前行的折行预览，多余的行显示波浪符 "~"
+    fn draw_line_preview(&self, origin_row: RowIdx) -> Result<(), Error> {
+        let rows = self.current_line_wrap();
+        let line = self.buffer.lines.get(self.text_location.line_index);
+        let end_y = origin_row.saturating_add(self.size.height);
+        for (row, current_row) in (origin_row..end_y).enumerate() {
+            match (line, rows.get(row)) {
+                (Some(line), Some(range)) => {
+                    let annotated_string = line.get_annotated_visible_substr(
+                        range.clone(),
+                        &self.highlighter.highlight(line),
+                        None,
+                        None,
+                        None,
+                        self.render_whitespace,
+                    );
+                    self.render_annotated_line(current_row, &annotated_string)?;
+                }
+                _ => self.render_line(current_row, "~")?,
+            }
+        }
+        Ok(())
     }
 
     // endregion
@@ -307,9 +2198,9 @@ impl View {
             // 如果目标行小于当前滚动偏移行，更新滚动偏移行
             self.scroll_offset.row = to;
             true
-        } else if to >= self.scroll_offset.row.saturating_add(height) {
-            // 如果目标行大于等于当前滚动偏移行加上窗口高度，更新滚动偏移行
-            self.scroll_offset.row = to.saturating_sub(height).saturating_add(1);
+        } else if self.visible_lines_between(self.scroll_offset.row, to) >= height {
+            // 如果目标行位于视图底部之下(折叠只占一行)，更新滚动偏移行
+            self.scroll_offset.row = self.visible_lines_up(to, height.saturating_sub(1));
             true
         } else {
             // 如果目标行在当前滚动偏移行和窗口高度之间，滚动偏移行不变
@@ -318,33 +2209,81 @@ impl View {
 
         // 如果滚动偏移行发生变化，需要重新渲染
         if offset_changed {
-            self.needs_redraw = true
+            self.needs_redraw = true;
         }
     }
 
     // 水平滚动
     fn scroll_horizontally(&mut self, to: ColIdx) {
-        let Size { width, .. } = self.size;
+        let width = self.text_width();
+        // 光标所在字素的最后一列，宽字符需要完整地显示在视图中
+        let to_end = to.saturating_add(self.caret_grapheme_width()).saturating_sub(1);
         let offset_changed = if to < self.scroll_offset.col {
             // 如果目标列小于当前滚动偏移列，更新滚动偏移列
             self.scroll_offset.col = to;
             true
-        } else if to >= self.scroll_offset.col.saturating_add(width) {
+        } else if to_end >= self.scroll_offset.col.saturating_add(width) {
             // 如果目标列大于等于当前滚动偏移列加上窗口宽度，更新滚动偏移列
-            self.scroll_offset.col = to.saturating_sub(width).saturating_add(1);
+            self.scroll_offset.col = to_end.saturating_sub(width).saturating_add(1);
             true
         } else {
             // 如果目标列在当前滚动偏移列和窗口宽度之间，滚动偏移列不变
             false
         };
         
-        if offset_changed {
-            self.needs_redraw = true
+        if offset_changed | self.snap_scroll_col_to_grapheme_boundary() {
+            self.needs_redraw = true;
         }
     }
 
+    /// 光标所在字素的显示宽度，位于行尾时为 1
+    fn caret_grapheme_width(&self) -> ColIdx {
+        let Location { line_index, grapheme_index } = self.text_location;
+        self.buffer.lines.get(line_index).map_or(1, |line| {
+            line.width_until(grapheme_index.saturating_add(1))
+                .saturating_sub(line.width_until(grapheme_index))
+                .max(1)
+        })
+    }
+
+    /// 视图左边缘落在当前行的宽字符中间时，将水平偏移移到该字素之后。
+    ///
+    /// 光标总是位于字素边界上，所以向右调整不会让光标移出视图。返回偏移是否改变。
+    fn snap_scroll_col_to_grapheme_boundary(&mut self) -> bool {
+        let Some(line) = self.buffer.lines.get(self.text_location.line_index) else {
+            return false;
+        };
+        let col = line.grapheme_boundary_at_or_after(self.scroll_offset.col);
+        let changed = col != self.scroll_offset.col;
+        self.scroll_offset.col = col;
+        changed
+    }
+
+    /// 折行显示时的垂直滚动，让光标所在的折行出现在视图中
+    fn scroll_wrapped_into_view(&mut self) {
+        let line_idx = self.text_location.line_index;
+        let (row, _) = self.wrapped_caret();
+        // 编辑后视图顶部的行可能变短，先让顶部的折行保持有效
+        let top_rows = self.line_wrap(self.scroll_offset.row).len();
+        self.scroll_wrap_row = self.scroll_wrap_row.min(top_rows.saturating_sub(1));
+        let new_top = if (line_idx, row) < (self.scroll_offset.row, self.scroll_wrap_row) {
+            (line_idx, row)
+        } else if self.wrapped_rows_from_top(line_idx, row) >= self.size.height {
+            self.wrapped_rows_up(line_idx, row, self.size.height.saturating_sub(1))
+        } else {
+            return;
+        };
+        (self.scroll_offset.row, self.scroll_wrap_row) = new_top;
+        self.needs_redraw = true;
+    }
+
     // 滚动至文本内容位置
     fn scroll_text_location_into_view(&mut self) {
+        self.unfold_at_cursors();
+        if self.wrap {
+            self.scroll_wrapped_into_view();
+            return;
+        }
         let Position { row, col } = self.text_location_to_position();
         self.scroll_vertically(row);
         self.scroll_horizontally(col);
@@ -352,13 +2291,23 @@ impl View {
 
     /// 居中文本位置
     fn center_text_location(&mut self) {
-        let Size { height, width } = self.size;
+        self.unfold_at_cursors();
+        let Size { height, .. } = self.size;
+        if self.wrap {
+            let (row, _) = self.wrapped_caret();
+            (self.scroll_offset.row, self.scroll_wrap_row) =
+                self.wrapped_rows_up(self.text_location.line_index, row, height.div_ceil(2));
+            self.set_needs_redraw(true);
+            return;
+        }
+        let width = self.text_width();
         let Position { row, col } = self.text_location_to_position();
         // 除法四舍五入
         let vertical_mid = height.div_ceil(2);
         let horizontal_mid = width.div_ceil(2);
-        self.scroll_offset.row = row.saturating_sub(vertical_mid);
+        self.scroll_offset.row = self.visible_lines_up(row, vertical_mid);
         self.scroll_offset.col = col.saturating_sub(horizontal_mid);
+        self.snap_scroll_col_to_grapheme_boundary();
         self.set_needs_redraw(true);
     }
     // endregion
@@ -367,16 +2316,140 @@ impl View {
     // region: Location and Position Handling
     // 处理位置代码
 
-    // 指针位置
+    // 指针位置，总是位于视图的范围内
     pub fn caret_position(&self) -> Position {
-        self.text_location_to_position()
-            .saturating_sub(self.scroll_offset)
+        let Position { row, col } = self.caret_position_in_view();
+        // 视图比行号栏还窄(或大小为 0)时，光标不能越过视图的边缘
+        Position {
+            row: row.min(self.size.height.saturating_sub(1)),
+            col: col
+                .min(self.size.width.saturating_sub(1))
+                .saturating_add(self.origin_col),
+        }
+    }
+
+    /// 光标相对于视图左上角的位置
+    fn caret_position_in_view(&self) -> Position {
+        if self.line_preview {
+            return self.line_preview_caret_position();
+        }
+        if self.wrap {
+            let (row, col) = self.wrapped_caret();
+            return Position {
+                row: self.wrapped_rows_from_top(self.text_location.line_index, row),
+                col: col.saturating_add(self.gutter_width()),
+            };
+        }
+        let position = self.text_location_to_position();
+        let Position { col, .. } = position.saturating_sub(self.scroll_offset);
+        // 光标需要越过左侧的行号栏。折叠只占一行
+        Position {
+            row: self.visible_lines_between(self.scroll_offset.row, position.row),
+            col: col.saturating_add(self.gutter_width()),
+        }
+    }
+
+    /// 折行预览时光标的位置：找到光标所在列属于哪一个折行
+    fn line_preview_caret_position(&self) -> Position {
+        let Position { col, .. } = self.text_location_to_position();
+        let rows = self.current_line_wrap();
+        let row = rows
+            .iter()
+            .position(|range| col < range.end)
+            .unwrap_or(rows.len().saturating_sub(1));
+        let row_start = rows.get(row).map_or(0, |range| range.start);
+        Position {
+            row,
+            col: col.saturating_sub(row_start),
+        }
+    }
+
+    /// 将光标所在的行按视图宽度折行
+    fn current_line_wrap(&self) -> Vec<Range<ColIdx>> {
+        self.buffer
+            .lines
+            .get(self.text_location.line_index)
+            .map_or_else(Vec::new, |line| line.wrap(self.size.width.max(1)))
+    }
+
+    /// 折行显示时一行的各个折行。文档末尾之后的空行也算作一个空的折行
+    // clippy::single_range_in_vec_init: 这里需要的就是只有一个空范围的折行列表
+    #[allow(clippy::single_range_in_vec_init)]
+    fn line_wrap(&self, line_idx: LineIdx) -> Vec<Range<ColIdx>> {
+        // 被折叠的行不占用视图中的行，折叠的第一行显示为一行摘要
+        if let Some(fold) = self.fold_at(line_idx) {
+            return if fold.start == line_idx { vec![0..0] } else { Vec::new() };
+        }
+        self.buffer
+            .lines
+            .get(line_idx)
+            .map_or_else(|| vec![0..0], |line| line.word_wrap(self.text_width().max(1)))
+    }
+
+    /// 折行显示时，光标位于所在行的第几个折行，以及在该折行中的列
+    fn wrapped_caret(&self) -> (usize, ColIdx) {
+        let Position { col, .. } = self.text_location_to_position();
+        let rows = self.line_wrap(self.text_location.line_index);
+        let row = rows
+            .iter()
+            .position(|range| col < range.end)
+            .unwrap_or(rows.len().saturating_sub(1));
+        let row_start = rows.get(row).map_or(0, |range| range.start);
+        (row, col.saturating_sub(row_start))
+    }
+
+    /// 折行显示时，从视图顶部到指定行的第 `row` 个折行之间的折行数。
+    ///
+    /// 指定的折行需要位于视图顶部或之后。超过视图高度后不再继续统计。
+    fn wrapped_rows_from_top(&self, line_idx: LineIdx, row: usize) -> usize {
+        let limit = self.size.height.saturating_add(self.scroll_wrap_row);
+        let mut count: usize = 0;
+        for idx in self.scroll_offset.row..line_idx {
+            count = count.saturating_add(self.line_wrap(idx).len());
+            if count > limit {
+                break;
+            }
+        }
+        count.saturating_add(row).saturating_sub(self.scroll_wrap_row)
+    }
+
+    /// 折行显示时，从指定行的第 `row` 个折行向上数 `count` 个折行后的位置，最多到文档开头
+    fn wrapped_rows_up(&self, mut line_idx: LineIdx, mut row: usize, count: usize) -> (LineIdx, usize) {
+        for _ in 0..count {
+            if row > 0 {
+                row = row.saturating_sub(1);
+            } else if let Some(previous) = (0..line_idx).rev().find(|idx| !self.is_line_hidden(*idx)) {
+                // 跳过不占用视图中的行的折叠行
+                line_idx = previous;
+                row = self.line_wrap(line_idx).len().saturating_sub(1);
+            } else {
+                break;
+            }
+        }
+        (line_idx, row)
+    }
+
+    /// 折行显示时，从视图顶部开始依次返回每个折行所在的行、它是该行的第几个折行以及列范围
+    fn visual_rows(&self) -> impl Iterator<Item = (LineIdx, usize, Range<ColIdx>)> + '_ {
+        let top = self.scroll_offset.row;
+        (top..self.buffer.height()).flat_map(move |line_idx| {
+            let rows = self.line_wrap(line_idx);
+            let skip = if line_idx == top {
+                self.scroll_wrap_row.min(rows.len().saturating_sub(1))
+            } else {
+                0
+            };
+            rows.into_iter()
+                .enumerate()
+                .skip(skip)
+                .map(move |(row, range)| (line_idx, row, range))
+        })
     }
 
     // 文本内容位置
     fn text_location_to_position(&self) -> Position {
         let row = self.text_location.line_index;
-        debug_assert!(row.saturating_sub(1) <= self.buffer.lines.len());
+        debug_assert!(row <= self.buffer.lines.len());
         let col = self
             .buffer
             .lines
@@ -384,7 +2457,7 @@ impl View {
             // 获取当前行的图形单元宽度，直到文本位置的图形单元索引
             .map_or(0, |line| line.width_until(self.text_location.grapheme_index));
 
-        Position { col, row }
+        Position { row, col }
     }
     // endregion
     // 处理位置代码结束
@@ -392,6 +2465,30 @@ impl View {
     // region: text location movement
     // 文本位置移动代码
 
+    // 上下移动指定行数。落在折叠中时跳过整个折叠，光标尽量保持原来的字素索引
+    fn move_vertically(&mut self, step: usize, up: bool) {
+        // 连续上下移动时沿用第一次移动前的列，经过较短的行后仍能回到原来的列
+        let col = self
+            .desired_col
+            .filter(|(at, _)| *at == self.text_location)
+            .map_or_else(|| self.text_location_to_position().col, |(_, col)| col);
+        if up {
+            self.move_up(step);
+        } else {
+            self.move_down(step);
+        }
+        if let Some(fold) = self.fold_at(self.text_location.line_index).cloned() {
+            self.text_location.line_index = if up { fold.start.saturating_sub(1) } else { fold.end };
+            self.snap_to_valid_line();
+        }
+        self.text_location.grapheme_index = self
+            .buffer
+            .lines
+            .get(self.text_location.line_index)
+            .map_or(0, |line| line.grapheme_at_width(col));
+        self.desired_col = Some((self.text_location, col));
+    }
+
     // 向上移动指定行数
     fn move_up(&mut self, step: usize) {
         self.text_location.line_index = self.text_location.line_index.saturating_sub(step);
@@ -409,6 +2506,21 @@ impl View {
     }
 
 
+    // 在当前行中向左或向右移动一个视图宽度(减去一列)，光标停在离目标列最近的字素边界上
+    fn move_horizontal_page(&mut self, forward: bool) {
+        let step = self.text_width().saturating_sub(1).max(1);
+        let Location { line_index, grapheme_index } = self.text_location;
+        if let Some(line) = self.buffer.lines.get(line_index) {
+            let col = line.width_until(grapheme_index);
+            let target = if forward {
+                col.saturating_add(step)
+            } else {
+                col.saturating_sub(step)
+            };
+            self.text_location.grapheme_index = line.grapheme_at_width(target);
+        }
+    }
+
     // 向右移动
     // clippy::arithmetic_side_effects: 这个函数执行算术计算，并且已经显式检查了目标值将在范围内。
     #[allow(clippy::arithmetic_side_effects)]
@@ -442,11 +2554,63 @@ impl View {
         }
     }
 
+    // 向右移动到下一个单词的开头，位于行尾时移动到下一行的开头
+    fn move_word_forward(&mut self) {
+        let Location { line_index, grapheme_index } = self.text_location;
+        match self.buffer.lines.get(line_index) {
+            Some(line) if grapheme_index < line.grapheme_count() => {
+                self.text_location.grapheme_index = line.next_word_start(grapheme_index);
+            }
+            _ => self.move_right(),
+        }
+    }
+
+    // 光标位于括号上时，移动到与之配对的括号。跳转不限制扫描的行数
+    fn move_to_matching_bracket(&mut self) {
+        if let Some(matching) = self.buffer.find_matching_bracket(
+            self.text_location,
+            self.highlighter.as_ref(),
+            usize::MAX,
+        ) {
+            self.text_location = matching;
+        }
+    }
+
+    // 向左移动到上一个单词的开头，位于行首时移动到上一行的结尾
+    fn move_word_backward(&mut self) {
+        let Location { line_index, grapheme_index } = self.text_location;
+        match self.buffer.lines.get(line_index) {
+            Some(line) if grapheme_index > 0 => {
+                self.text_location.grapheme_index = line.prev_word_start(grapheme_index);
+            }
+            _ => self.move_left(),
+        }
+    }
+
     // 移动到当前行的开头
     fn move_to_start_of_line(&mut self) {
         self.text_location.grapheme_index = 0;
     }
 
+    // 移动到当前行第一个非空白字符；已经在那里或整行都是空白时移动到行首(Home 键)
+    fn move_to_smart_home(&mut self) {
+        let Location { line_index, grapheme_index } = self.text_location;
+        // 缩进只包含空格和制表符，每个字符都是一个字素
+        let first_non_whitespace = self.buffer.lines.get(line_index).map_or(0, |line| {
+            let indentation = line.indentation().len();
+            if indentation < line.grapheme_count() {
+                indentation
+            } else {
+                0
+            }
+        });
+        self.text_location.grapheme_index = if grapheme_index == first_non_whitespace {
+            0
+        } else {
+            first_non_whitespace
+        };
+    }
+
     // 移动到当前行的结尾
     fn move_to_end_of_line(&mut self) {
         self.text_location.grapheme_index = self
@@ -456,6 +2620,17 @@ impl View {
             .map_or(0, Line::grapheme_count);
     }
 
+    // 移动到文档开头
+    fn move_to_document_start(&mut self) {
+        self.text_location = Location::default();
+    }
+
+    // 移动到文档最后一行的结尾
+    fn move_to_document_end(&mut self) {
+        self.text_location.line_index = self.buffer.height().saturating_sub(1);
+        self.move_to_end_of_line();
+    }
+
     // 确保图形单元(列)索引有效，如果需要，将其调整到最左边的图形单元。
     // 不触发滚动。
     fn snap_to_valid_grapheme(&mut self) {
@@ -495,26 +2670,55 @@ impl UIComponent for View {
     }
 
     fn draw(&mut self, origin_row: RowIdx) -> Result<(), Error> {
+        // 终端复用器调整大小的过程中可能短暂地报告 0x0 的大小，此时没有可以绘制的区域
+        if self.size.is_empty() {
+            return Ok(());
+        }
+        if self.line_preview {
+            return self.draw_line_preview(origin_row);
+        }
         let Size { height, width } = self.size;
-        let end_y = origin_row.saturating_add(height);
+        // 文本区域的宽度(扣除左侧行号栏)
+        let gutter_width = self.gutter_width();
+        let text_width = self.text_width();
 
         // 计算垂直居中的位置，用于显示欢迎信息
         // 它可以稍微偏上一点或偏下一点，因为我们不在乎欢迎信息是否恰好位于正中间。
         let top_third = height.div_ceil(3);
         // 获取滚动偏移量
         let scroll_top = self.scroll_offset.row;
-        for current_row in origin_row..end_y {
-            // 从终端上的当前行、原点和滚动偏移量计算缓冲区中的正确行。
-            // 为了获得正确的行索引，我们必须取 current_row（屏幕上绝对的行位置）,
-            // 减去 origin_row 以得到相对于视图的当前行（范围从 0 到 self.size.height）,
-            // 然后加上滚动偏移量。
-            let line_idx = current_row
-                .saturating_sub(origin_row)
-                .saturating_add(scroll_top);
+        // 光标所在的括号及其配对括号
+        let brackets = self.matching_brackets();
+        // 光标所在的单词，每次绘制只提取一次
+        let word = self.word_under_caret();
+        // 视图中每一行显示的文档行、它是该行的第几个折行以及要显示的列范围。
+        // 不折行时每个文档行只占一行，显示水平滚动后可见的部分
+        let rows: Vec<(LineIdx, usize, Range<ColIdx>)> = if self.wrap {
+            self.visual_rows().take(height).collect()
+        } else {
+            let left = self.scroll_offset.col;
+            let right = left.saturating_add(text_width);
+            (scroll_top..self.buffer.height())
+                .filter(|line_idx| !self.is_line_hidden(*line_idx))
+                .take(height)
+                .map(|line_idx| (line_idx, 0, left..right))
+                .collect()
+        };
+        // 文档结束后，视图中剩下的行对应的行索引
+        let mut line_idx_after_end = rows
+            .last()
+            .map_or(scroll_top, |(line_idx, ..)| line_idx.saturating_add(1));
+        for (view_row, current_row) in (origin_row..origin_row.saturating_add(height)).enumerate() {
             // 判断输出
-            if let Some(line) = self.buffer.lines.get(line_idx) {
-                let left = self.scroll_offset.col;
-                let right = self.scroll_offset.col.saturating_add(width);
+            if let Some((line_idx, row, range)) = rows.get(view_row) {
+                let line_idx = *line_idx;
+                let Some(line) = self.buffer.lines.get(line_idx) else {
+                    continue;
+                };
+                if let Some(fold) = self.fold_at(line_idx).filter(|fold| fold.start == line_idx) {
+                    self.render_fold_summary(current_row, fold, range)?;
+                    continue;
+                }
                 // 获取想要查询的内容
                 let query = self.search_info
                     .as_ref()
@@ -523,20 +2727,906 @@ impl UIComponent for View {
                 // 有就返回Some(字素索引), 否则返回None
                 let selected_match = (self.text_location.line_index == line_idx && query.is_some())
                     .then_some(self.text_location.grapheme_index);
+                // 根据参数获取带注释的字符串
+                let mut annotated_string = line.get_annotated_visible_substr(
+                    range.clone(),
+                    &self.line_highlights(line, line_idx, brackets, word.as_deref()),
+                    query,
+                    selected_match,
+                    self.selection_in_line(line_idx),
+                    self.render_whitespace,
+                );
+                self.append_line_end_marks(&mut annotated_string, line, line_idx, range);
+                // 在行首加上行号栏，折行的后续部分不显示行号。
+                // 上次保存后修改过的行，用行号后的 `+` 标记
+                if gutter_width > 0 {
+                    let mut line_number = if *row == 0 {
+                        self.build_line_number(line_idx)
+                    } else {
+                        " ".repeat(gutter_width)
+                    };
+                    let is_modified = *row == 0 && self.buffer.is_line_modified(line_idx);
+                    if is_modified {
+                        line_number.pop();
+                        line_number.push('+');
+                    }
+                    annotated_string.prepend(&line_number);
+                    let number_end = line_number.len().saturating_sub(usize::from(is_modified));
+                    annotated_string.add_annotation(AnnotationType::LineNumber, 0, number_end);
+                    if is_modified {
+                        annotated_string.add_annotation(
+                            AnnotationType::ModifiedLine,
+                            number_end,
+                            line_number.len(),
+                        );
+                    }
+                }
                 // 渲染行
-                Terminal::print_annotated_row(
-                    current_row,
-                    // 根据参数获取带注释的字符串
-                    &line.get_annotated_visible_substr(left..right, query, selected_match),
-                )?;
+                self.render_annotated_line(current_row, &annotated_string)?;
+                continue;
+            }
+            let line_idx = line_idx_after_end;
+            line_idx_after_end = line_idx_after_end.saturating_add(1);
+            if gutter_width > 0 && line_idx == self.text_location.line_index {
+                // 光标位于文档末尾之后的空行上(例如空缓冲区)，也为其显示行号
+                self.render_line(current_row, &self.build_line_number(line_idx))?;
             } else if current_row == top_third && self.buffer.is_empty() {
                 // 如果当前行是垂直居中的位置且缓冲区为空，显示欢迎信息
-                Self::render_line(current_row, &Self::build_welcome_message(width))?;
+                self.render_line(current_row, &Self::build_welcome_message(width.saturating_sub(self.scrollbar_width())))?;
             } else {
                 // 否则，渲染波浪符 "~" 表示空白行
-                Self::render_line(current_row, "~")?;
+                self.render_line(current_row, "~")?;
             }
         }
+        if self.show_scrollbar {
+            self.draw_scrollbar(origin_row)?;
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+impl View {
+    /// 测试用：用文本创建指定大小的视图，光标位于文档开头
+    fn with_size(text: &str, size: Size) -> Self {
+        let mut view = Self::from_str(text);
+        view.set_size(size);
+        view
+    }
+
+    /// 测试用：光标在文档中的位置
+    const fn text_location(&self) -> Location {
+        self.text_location
+    }
+
+    /// 测试用：视图的滚动偏移
+    const fn scroll_offset(&self) -> Position {
+        self.scroll_offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Size = Size { height: 5, width: 10 };
+
+    fn location(line_index: LineIdx, grapheme_index: GraphemeIdx) -> Location {
+        Location {
+            grapheme_index,
+            line_index,
+        }
+    }
+
+    fn position(row: RowIdx, col: ColIdx) -> Position {
+        Position { row, col }
+    }
+
+    fn line(view: &View, line_idx: LineIdx) -> &Line {
+        view.buffer.lines.get(line_idx).unwrap()
+    }
+
+    #[test]
+    fn move_right_at_end_of_line_wraps_to_next_line() {
+        let mut view = View::with_size("ab\ncd", SIZE);
+        view.handle_move_command(Move::EndOfLine);
+        assert_eq!(view.text_location(), location(0, 2));
+        view.handle_move_command(Move::Right);
+        assert_eq!(view.text_location(), location(1, 0));
+    }
+
+    #[test]
+    fn move_left_at_start_of_line_wraps_to_previous_line_end() {
+        let mut view = View::with_size("ab\ncd", SIZE);
+        view.handle_move_command(Move::Down);
+        view.handle_move_command(Move::Left);
+        assert_eq!(view.text_location(), location(0, 2));
+        // 文档开头不再移动
+        view.handle_move_command(Move::DocumentStart);
+        view.handle_move_command(Move::Left);
+        assert_eq!(view.text_location(), location(0, 0));
+    }
+
+    #[test]
+    fn move_right_steps_over_whole_graphemes() {
+        let mut view = View::with_size("你e\u{301}x", SIZE);
+        view.handle_move_command(Move::Right);
+        assert_eq!(view.caret_position(), position(0, 2));
+        view.handle_move_command(Move::Right);
+        assert_eq!(view.text_location(), location(0, 2));
+        assert_eq!(view.caret_position(), position(0, 3));
+    }
+
+    #[test]
+    fn page_down_and_up_scroll_by_view_height() {
+        let text = (1..=20).map(|number| number.to_string()).collect::<Vec<_>>().join("\n");
+        let mut view = View::with_size(&text, SIZE);
+
+        view.handle_move_command(Move::PageDown);
+        assert_eq!(view.text_location(), location(4, 0));
+        assert_eq!(view.scroll_offset(), position(0, 0));
+
+        view.handle_move_command(Move::PageDown);
+        assert_eq!(view.text_location(), location(8, 0));
+        assert_eq!(view.scroll_offset(), position(4, 0));
+        assert_eq!(view.caret_position(), position(4, 0));
+
+        view.handle_move_command(Move::PageUp);
+        assert_eq!(view.text_location(), location(4, 0));
+        assert_eq!(view.scroll_offset(), position(4, 0));
+
+        view.handle_move_command(Move::PageUp);
+        view.handle_move_command(Move::PageUp);
+        assert_eq!(view.text_location(), location(0, 0));
+        assert_eq!(view.scroll_offset(), position(0, 0));
+    }
+
+    #[test]
+    fn page_down_stops_after_last_line() {
+        let mut view = View::with_size("a\nb\nc", SIZE);
+        view.handle_move_command(Move::PageDown);
+        assert_eq!(view.text_location(), location(3, 0));
+    }
+
+    #[test]
+    fn horizontal_scroll_does_not_bisect_wide_graphemes() {
+        // 各字素的起始列：a 0, b 1, 你 2, 好 4, 世 6, 界 8, c 10, d 11，行尾 12
+        let mut view = View::with_size("ab你好世界cd", Size { height: 5, width: 6 });
+        view.handle_move_command(Move::EndOfLine);
+        // 让行尾出现在视图中需要滚动到第 7 列，它落在“世”的中间，因此移到“界”的开头
+        assert_eq!(view.scroll_offset(), position(0, 8));
+        assert_eq!(view.caret_position(), position(0, 4));
+
+        for _ in 0..4 {
+            view.handle_move_command(Move::Left);
+        }
+        assert_eq!(view.text_location(), location(0, 4));
+        assert_eq!(view.scroll_offset(), position(0, 6));
+        assert_eq!(view.caret_position(), position(0, 0));
+    }
+
+    #[test]
+    fn wide_grapheme_under_caret_is_fully_visible() {
+        let mut view = View::with_size("abcde你", Size { height: 5, width: 6 });
+        for _ in 0..5 {
+            view.handle_move_command(Move::Right);
+        }
+        // “你”占第 5、6 列，视图只有 6 列宽，需要滚动一列才能完整显示
+        assert_eq!(view.scroll_offset(), position(0, 1));
+        assert_eq!(view.caret_position(), position(0, 4));
+    }
+
+    #[test]
+    fn delete_at_line_end_merges_short_line_into_long_one() {
+        let mut view = View::with_size("a long first line\nab", SIZE);
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_edit_command(Edit::Delete);
+        assert_eq!(view.current_line_text(), "a long first lineab");
+        assert_eq!(view.text_location(), location(0, 17));
+        assert_eq!(view.scroll_offset(), position(0, 8));
+        assert_eq!(view.caret_position(), position(0, 9));
+    }
+
+    #[test]
+    fn delete_at_line_end_merges_long_line_into_short_one() {
+        let mut view = View::with_size("ab\na long second line", SIZE);
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_edit_command(Edit::Delete);
+        assert_eq!(view.current_line_text(), "aba long second line");
+        assert_eq!(view.text_location(), location(0, 2));
+        assert_eq!(view.scroll_offset(), position(0, 0));
+        assert_eq!(view.caret_position(), position(0, 2));
+    }
+
+    #[test]
+    fn delete_at_line_end_keeps_merged_wide_grapheme_visible() {
+        let mut view = View::with_size("abcde\n你好", Size { height: 5, width: 6 });
+        view.handle_move_command(Move::EndOfLine);
+        assert_eq!(view.scroll_offset(), position(0, 0));
+        view.handle_edit_command(Edit::Delete);
+        assert_eq!(view.current_line_text(), "abcde你好");
+        assert_eq!(view.text_location(), location(0, 5));
+        // “你”占第 5、6 列，需要滚动一列才能完整显示
+        assert_eq!(view.scroll_offset(), position(0, 1));
+        assert_eq!(view.caret_position(), position(0, 4));
+    }
+
+    #[test]
+    fn backspace_at_line_start_keeps_caret_at_join_point() {
+        let mut view = View::with_size("abcde\n你好", Size { height: 5, width: 6 });
+        view.handle_move_command(Move::Down);
+        view.handle_edit_command(Edit::DeleteBackward);
+        assert_eq!(view.text_location(), location(0, 5));
+        assert_eq!(view.scroll_offset(), position(0, 1));
+    }
+
+    #[test]
+    fn home_toggles_between_indentation_and_line_start() {
+        let mut view = View::with_size("    indented", SIZE);
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_move_command(Move::StartOfLine);
+        assert_eq!(view.text_location(), location(0, 4));
+        view.handle_move_command(Move::StartOfLine);
+        assert_eq!(view.text_location(), location(0, 0));
+        view.handle_move_command(Move::StartOfLine);
+        assert_eq!(view.text_location(), location(0, 4));
+    }
+
+    #[test]
+    fn home_on_whitespace_only_or_empty_line_goes_to_line_start() {
+        let mut view = View::with_size("   \n", SIZE);
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_move_command(Move::StartOfLine);
+        assert_eq!(view.text_location(), location(0, 0));
+        view.handle_move_command(Move::StartOfLine);
+        assert_eq!(view.text_location(), location(0, 0));
+
+        view.handle_move_command(Move::Down);
+        view.handle_move_command(Move::StartOfLine);
+        assert_eq!(view.text_location(), location(1, 0));
+    }
+
+    #[test]
+    fn zero_size_view_draws_nothing() {
+        let mut view = View::with_size("abc\ndef", Size::default());
+        view.handle_move_command(Move::Down);
+        view.handle_move_command(Move::EndOfLine);
+        assert!(view.draw(0).is_ok());
+        assert_eq!(view.caret_position(), position(0, 0));
+    }
+
+    #[test]
+    fn caret_stays_inside_view_narrower_than_gutter() {
+        let text = (1..=200).map(|number| number.to_string()).collect::<Vec<_>>().join("\n");
+        let mut view = View::with_size(&text, Size { height: 3, width: 2 });
+        view.set_show_line_numbers(true);
+        view.handle_move_command(Move::DocumentEnd);
+        let Position { row, col } = view.caret_position();
+        assert!(row < 3 && col < 2);
+    }
+
+    #[test]
+    fn overwrite_replaces_graphemes_and_appends_at_line_end() {
+        let mut view = View::with_size("a你b", SIZE);
+        view.toggle_overwrite();
+        view.handle_move_command(Move::Right);
+        // 用单宽字符替换双宽字符
+        view.handle_edit_command(Edit::Insert('x'));
+        assert_eq!(view.current_line_text(), "axb");
+        assert_eq!(view.caret_position(), position(0, 2));
+        view.handle_edit_command(Edit::Insert('好'));
+        view.handle_edit_command(Edit::Insert('!'));
+        assert_eq!(view.current_line_text(), "ax好!");
+        assert_eq!(view.text_location(), location(0, 4));
+    }
+
+    #[test]
+    fn overwrite_keeps_combining_characters_with_previous_grapheme() {
+        let mut view = View::with_size("ab", SIZE);
+        view.toggle_overwrite();
+        view.handle_edit_command(Edit::Insert('e'));
+        view.handle_edit_command(Edit::Insert('\u{301}'));
+        assert_eq!(view.current_line_text(), "e\u{301}b");
+        assert_eq!(view.text_location(), location(0, 1));
+    }
+
+    #[test]
+    fn ruler_marks_its_column_in_buffer_coordinates() {
+        let mut view = View::with_size("abc\nabcdef", SIZE);
+        view.set_ruler_column(Some(4));
+        let ruler = |view: &View, line_idx: LineIdx| {
+            let line = line(view, line_idx);
+            view.line_highlights(line, line_idx, None, None)
+                .iter()
+                .find(|annotation| annotation.annotation_type == AnnotationType::Ruler)
+                .map(|annotation| annotation.start..annotation.end)
+        };
+        // 较长的行标出标尺列上的字素，较短的行用空格补齐到标尺列
+        assert_eq!(ruler(&view, 1), Some(4..5));
+        assert_eq!(ruler(&view, 0), None);
+        let line = line(&view, 0);
+        let mut annotated_string = AnnotatedString::from("abc");
+        view.append_line_end_marks(&mut annotated_string, line, 0, &(0..10));
+        assert_eq!(annotated_string.to_string(), "abc  ");
+        // 水平滚动后，标尺随文档的列移动
+        let mut annotated_string = AnnotatedString::from("c");
+        view.append_line_end_marks(&mut annotated_string, line, 0, &(2..12));
+        assert_eq!(annotated_string.to_string(), "c  ");
+        let mut annotated_string = AnnotatedString::from("");
+        view.append_line_end_marks(&mut annotated_string, line, 0, &(5..15));
+        assert_eq!(annotated_string.to_string(), "");
+    }
+
+    #[test]
+    fn from_str_builds_untitled_view_at_document_start() {
+        let view = View::from_str("one\ntwo\nthree");
+        let status = view.get_status();
+        assert_eq!(status.total_lines, 3);
+        assert_eq!((status.current_line_index, status.current_grapheme_index), (0, 0));
+        assert!(!status.is_modified);
+        assert!(!view.is_file_loaded());
+        assert_eq!(status.file_name, "[No Name]");
+        assert_eq!(view.current_line_text(), "one");
+    }
+
+    #[test]
+    fn current_line_text_follows_cursor() {
+        let mut view = View::with_size("first\nsecond", SIZE);
+        assert_eq!(view.current_line_text(), "first");
+        view.handle_move_command(Move::Down);
+        assert_eq!(view.current_line_text(), "second");
+        // 光标位于文档末尾之后的空行上
+        view.go_to(location(2, 0));
+        assert_eq!(view.text_location(), location(2, 0));
+        assert_eq!(view.current_line_text(), "");
+    }
+
+    #[test]
+    fn read_only_follows_file_permissions_on_reload() {
+        let path = std::env::temp_dir().join(format!("hecto-test-{}-read-only", std::process::id()));
+        std::fs::write(&path, "text").unwrap();
+        let set_readonly = |readonly: bool| {
+            let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+            permissions.set_readonly(readonly);
+            std::fs::set_permissions(&path, permissions).unwrap();
+        };
+        set_readonly(true);
+        let mut view = View::default();
+        view.load(path.to_str().unwrap()).unwrap();
+        assert!(view.is_read_only());
+        // 文件变为可写后重新加载，不再只读
+        set_readonly(false);
+        view.reload().unwrap();
+        assert!(!view.is_read_only());
+        // 强制只读时，可写的文件也只读
+        view.set_read_only(true);
+        view.reload().unwrap();
+        assert!(view.is_read_only());
+        view.set_read_only(false);
+        assert!(!view.is_read_only());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn changing_tab_width_lays_out_live_buffer_again() {
+        let mut view = View::with_size("ab\tc\td", SIZE);
+        view.set_tab_width(4);
+        assert_eq!(view.tab_width(), 4);
+        assert_eq!((line(&view, 0).width_until(3), line(&view, 0).width_until(5)), (4, 8));
+        view.set_tab_width(8);
+        assert_eq!(view.tab_width(), 8);
+        assert_eq!((line(&view, 0).width_until(3), line(&view, 0).width_until(5)), (8, 16));
+        assert!(!view.get_status().is_modified);
+    }
+
+    #[test]
+    fn loaded_tabs_align_to_tab_stops() {
+        let path = std::env::temp_dir().join(format!("hecto-test-{}-tabs", std::process::id()));
+        std::fs::write(&path, "a\tb\n\t\tc\nabcd\te").unwrap();
+        let mut view = View::default();
+        view.set_tab_width(8);
+        view.load(path.to_str().unwrap()).unwrap();
+        let column_at = |view: &mut View, line_index, grapheme_index| {
+            view.go_to(location(line_index, grapheme_index));
+            view.get_status().current_column
+        };
+        assert_eq!(column_at(&mut view, 0, 2), 8);
+        assert_eq!(column_at(&mut view, 1, 2), 16);
+        assert_eq!(column_at(&mut view, 2, 5), 8);
+        assert_eq!(line(&view, 2).get_visible_graphemes(0..9), "abcd    e");
+        // 制表符只影响显示，缓冲区中仍然保存原来的制表符
+        assert_eq!(view.buffer.contents(), "a\tb\n\t\tc\nabcd\te");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn go_to_next_line_containing_wraps_around() {
+        let mut view = View::with_size("fn a() {}\nlet b;\n  fn c() {}\n", SIZE);
+        view.handle_move_command(Move::EndOfLine);
+        assert_eq!(view.go_to_next_line_containing("fn "), Some(false));
+        assert_eq!(view.text_location(), location(2, 0));
+        assert_eq!(view.go_to_next_line_containing("fn "), Some(true));
+        assert_eq!(view.text_location(), location(0, 0));
+        // 没有匹配的行时光标不动
+        assert_eq!(view.go_to_next_line_containing("struct"), None);
+        assert_eq!(view.go_to_next_line_containing(""), None);
+        assert_eq!(view.text_location(), location(0, 0));
+    }
+
+    #[test]
+    fn vertical_moves_return_to_desired_column() {
+        let mut view = View::with_size("abcdef\nab\n\nabcdefgh\n你好你好", SIZE);
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_move_command(Move::Left);
+        for expected in [location(1, 2), location(2, 0), location(3, 5)] {
+            view.handle_move_command(Move::Down);
+            assert_eq!(view.text_location(), expected);
+        }
+        // 第 5 列落在宽字符中间，取左侧的边界
+        view.handle_move_command(Move::Down);
+        assert_eq!(view.text_location(), location(4, 2));
+        view.handle_move_command(Move::Up);
+        assert_eq!(view.text_location(), location(3, 5));
+        // 左右移动和编辑之后，从新的列开始
+        view.handle_move_command(Move::Left);
+        view.handle_move_command(Move::Up);
+        view.handle_move_command(Move::Up);
+        view.handle_move_command(Move::Up);
+        assert_eq!(view.text_location(), location(0, 4));
+        view.handle_move_command(Move::Down);
+        view.handle_edit_command(Edit::Insert('x'));
+        view.handle_move_command(Move::Down);
+        view.handle_move_command(Move::Down);
+        assert_eq!(view.text_location(), location(3, 3));
+    }
+
+    #[test]
+    fn caret_context_is_recomputed_after_edits() {
+        let mut view = View::with_size("(a", SIZE);
+        assert_eq!(view.matching_brackets(), None);
+        // 光标没有移动，内容变化后重新查找
+        view.buffer.insert_char(')', location(0, 2));
+        assert_eq!(view.matching_brackets(), Some((location(0, 0), location(0, 2))));
+        view.handle_move_command(Move::Right);
+        assert_eq!(view.matching_brackets(), None);
+        assert_eq!(view.caret_cache.borrow().key, Some((location(0, 1), view.buffer.revision(), SIZE.height)));
+    }
+
+    #[test]
+    fn bracket_highlight_scan_is_bounded_but_jump_is_not() {
+        let far = SIZE.height.saturating_add(BRACKET_SCAN_MARGIN);
+        let mut view = View::with_size(&format!("{{{}}}", "\n".repeat(far)), SIZE);
+        assert_eq!(view.matching_brackets(), None);
+        view.handle_move_command(Move::MatchBracket);
+        assert_eq!(view.text_location(), location(far, 0));
+        // 在扫描范围内时可以找到配对的括号
+        let near = far.saturating_sub(1);
+        let view = View::with_size(&format!("{{{}}}", "\n".repeat(near)), SIZE);
+        assert_eq!(view.matching_brackets(), Some((location(0, 0), location(near, 0))));
+    }
+
+    #[test]
+    fn word_under_caret_follows_cursor_and_skips_whitespace() {
+        let mut view = View::with_size("let foo = 1;\nfoo(foobar)", SIZE);
+        assert_eq!(view.word_under_caret(), None);
+        view.toggle_highlight_occurrences();
+        assert_eq!(view.word_under_caret().as_deref(), Some("let"));
+        view.handle_move_command(Move::WordForward);
+        let word = view.word_under_caret();
+        assert_eq!(word.as_deref(), Some("foo"));
+        let occurrences = |view: &View, line_idx: LineIdx| -> Vec<Range<ByteIdx>> {
+            view.line_highlights(line(view, line_idx), line_idx, None, word.as_deref())
+                .iter()
+                .filter(|annotation| annotation.annotation_type == AnnotationType::WordOccurrence)
+                .map(|annotation| annotation.start..annotation.end)
+                .collect()
+        };
+        // 只高亮完整的单词，光标不移动
+        assert_eq!(occurrences(&view, 1), vec![0..3]);
+        assert_eq!(view.text_location(), location(0, 4));
+        view.handle_move_command(Move::EndOfLine);
+        assert_eq!(view.word_under_caret(), None);
+        view.handle_move_command(Move::Left);
+        assert_eq!(view.word_under_caret(), None);
+    }
+
+    #[test]
+    fn typing_and_deleting_at_all_cursors() {
+        let mut view = View::with_size("ab ab\nab", SIZE);
+        assert!(view.add_cursor_at_next_match());
+        assert!(view.add_cursor_at_next_match());
+        assert_eq!(view.cursor_count(), 3);
+        // 所有匹配项都有光标后不再添加
+        assert!(!view.add_cursor_at_next_match());
+        view.handle_edit_command(Edit::Insert('x'));
+        assert_eq!(line(&view, 0).to_string(), "xab xab");
+        assert_eq!(view.current_line_text(), "xab");
+        assert_eq!(view.text_location(), location(1, 1));
+        view.handle_edit_command(Edit::DeleteBackward);
+        assert_eq!(line(&view, 0).to_string(), "ab ab");
+        // 行首的退格会合并行，在该光标处跳过
+        view.handle_edit_command(Edit::DeleteBackward);
+        assert_eq!(view.buffer.height(), 2);
+        assert_eq!(line(&view, 0).to_string(), "abab");
+        assert_eq!(view.current_line_text(), "ab");
+        assert_eq!(view.extra_cursors, vec![location(0, 0), location(0, 2)]);
+    }
+
+    #[test]
+    fn cursor_below_keeps_its_column() {
+        let mut view = View::with_size("abcd\nab\nabcd", SIZE);
+        view.handle_move_command(Move::Right);
+        view.handle_move_command(Move::Right);
+        view.handle_move_command(Move::Right);
+        assert!(view.add_cursor_below());
+        assert!(view.add_cursor_below());
+        assert!(!view.add_cursor_below());
+        assert_eq!(view.extra_cursors, vec![location(0, 3), location(1, 2)]);
+        assert_eq!(view.text_location(), location(2, 2));
+        view.handle_edit_command(Edit::Insert('-'));
+        assert_eq!(line(&view, 1).to_string(), "ab-");
+        // 行尾的光标在行之后显示
+        let line = line(&view, 1);
+        let mut annotated_string = AnnotatedString::from("ab-");
+        view.append_line_end_marks(&mut annotated_string, line, 1, &(0..10));
+        assert_eq!(annotated_string.to_string(), "ab- ");
+        // 会增加行的编辑先收起光标
+        view.handle_edit_command(Edit::InsertNewline);
+        assert_eq!(view.cursor_count(), 1);
+        assert_eq!(view.buffer.height(), 4);
+    }
+
+    #[test]
+    fn wrapped_caret_and_scrolling_follow_visual_rows() {
+        let mut view = View::with_size("aaaa bbbb cccc\nx\ny\nz\nw\nv", SIZE);
+        view.set_wrap(true);
+        assert_eq!(view.line_wrap(0), vec![0..10, 10..14]);
+        view.go_to(location(0, 12));
+        assert_eq!(view.caret_position_in_view(), position(1, 2));
+        // 第一行占两个折行，移到第 5 行时只需滚动掉它的第一个折行
+        for _ in 0..4 {
+            view.handle_move_command(Move::Down);
+        }
+        assert_eq!(view.text_location(), location(4, 1));
+        assert_eq!(view.caret_position_in_view(), position(4, 1));
+        let rows: Vec<(LineIdx, usize)> = view.visual_rows().take(SIZE.height).map(|(line_idx, row, _)| (line_idx, row)).collect();
+        assert_eq!(rows, vec![(0, 1), (1, 0), (2, 0), (3, 0), (4, 0)]);
+        // 继续向下移动后，第一行完全滚出视图
+        view.handle_move_command(Move::Down);
+        assert_eq!(view.text_location(), location(5, 1));
+        assert_eq!(view.scroll_offset(), position(1, 0));
+        assert_eq!(view.caret_position_in_view(), position(4, 1));
+    }
+
+    #[test]
+    fn line_preview_wraps_at_view_width_without_editing() {
+        let text = "abcdefghijklmnopqrstuvwxy\nnext";
+        let mut view = View::with_size(text, SIZE);
+        view.go_to(location(0, 12));
+        view.toggle_line_preview();
+        assert_eq!(view.current_line_wrap(), vec![0..10, 10..20, 20..25]);
+        assert_eq!(view.caret_position_in_view(), position(1, 2));
+        assert_eq!(view.buffer.contents(), text);
+        assert!(!view.get_status().is_modified);
+        // 移动光标会结束预览
+        view.handle_move_command(Move::Right);
+        assert!(!view.line_preview);
+        assert_eq!(view.buffer.height(), 2);
+    }
+
+    #[test]
+    fn enter_at_end_of_file_follows_config() {
+        let mut view = View::with_size("one\ntwo", SIZE);
+        view.handle_move_command(Move::Down);
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_edit_command(Edit::InsertNewline);
+        assert_eq!(view.buffer.height(), 3);
+        assert_eq!(view.text_location(), location(2, 0));
+
+        let mut view = View::with_size("one\ntwo", SIZE);
+        view.set_newline_at_eof(false);
+        view.handle_move_command(Move::Down);
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_edit_command(Edit::InsertNewline);
+        assert_eq!(view.buffer.height(), 2);
+        assert_eq!(view.text_location(), location(1, 3));
+        assert!(!view.get_status().is_modified);
+        // 不在文档末尾时照常换行
+        view.handle_move_command(Move::Left);
+        view.handle_edit_command(Edit::InsertNewline);
+        assert_eq!(view.buffer.height(), 3);
+    }
+
+    #[test]
+    fn smart_home_toggles_but_line_wrapping_moves_to_column_zero() {
+        let mut view = View::with_size("    foo\n    bar", SIZE);
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_move_command(Move::StartOfLine);
+        assert_eq!(view.text_location(), location(0, 4));
+        view.handle_move_command(Move::StartOfLine);
+        assert_eq!(view.text_location(), location(0, 0));
+        // 在行尾向右移动到下一行的行首，而不是它的第一个非空白字符
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_move_command(Move::Right);
+        assert_eq!(view.text_location(), location(1, 0));
+    }
+
+    #[test]
+    fn enter_in_indented_line_moves_caret_to_new_line() {
+        let mut view = View::with_size("    foobar", SIZE);
+        view.go_to(location(0, 7));
+        view.handle_edit_command(Edit::InsertNewline);
+        assert_eq!(view.buffer.contents(), "    foo\nbar");
+        assert_eq!(view.text_location(), location(1, 0));
+
+        let mut view = View::with_size("    foobar", SIZE);
+        view.set_auto_indent(true);
+        view.go_to(location(0, 7));
+        view.handle_edit_command(Edit::InsertNewline);
+        assert_eq!(view.buffer.contents(), "    foo\n    bar");
+        assert_eq!(view.text_location(), location(1, 4));
+    }
+
+    #[test]
+    fn word_movement_over_camel_and_snake_case() {
+        let mut view = View::with_size("CamelCase snake_case.x", SIZE);
+        // 大小写变化和下划线都不是单词边界
+        view.handle_move_command(Move::WordForward);
+        assert_eq!(view.text_location(), location(0, 10));
+        view.handle_move_command(Move::WordForward);
+        assert_eq!(view.text_location(), location(0, 21));
+        view.handle_move_command(Move::WordBackward);
+        assert_eq!(view.text_location(), location(0, 10));
+        view.handle_move_command(Move::WordBackward);
+        assert_eq!(view.text_location(), location(0, 0));
+    }
+
+    #[test]
+    fn word_movement_skips_leading_whitespace_and_wraps() {
+        let mut view = View::with_size("end\n    foo", SIZE);
+        view.handle_move_command(Move::WordForward);
+        assert_eq!(view.text_location(), location(0, 3));
+        // 位于行尾时移动到下一行的开头，再跳过缩进
+        view.handle_move_command(Move::WordForward);
+        assert_eq!(view.text_location(), location(1, 0));
+        view.handle_move_command(Move::WordForward);
+        assert_eq!(view.text_location(), location(1, 4));
+        view.handle_move_command(Move::WordBackward);
+        assert_eq!(view.text_location(), location(1, 0));
+        view.handle_move_command(Move::WordBackward);
+        assert_eq!(view.text_location(), location(0, 3));
+    }
+
+    #[test]
+    fn block_comment_toggles_selection_back_to_original() {
+        let text = "fn a() {\n    one();\n\n        two();\n}";
+        let mut view = View::with_size(text, SIZE);
+        view.go_to(location(1, 0));
+        view.handle_select_command(Move::Down);
+        view.handle_select_command(Move::Down);
+        view.handle_edit_command(Edit::ToggleBlockComment);
+        // 注释符号对齐到最浅的缩进，空行保持不变
+        assert_eq!(view.buffer.contents(), "fn a() {\n    // one();\n\n    //     two();\n}");
+        view.handle_edit_command(Edit::ToggleBlockComment);
+        assert_eq!(view.buffer.contents(), text);
+    }
+
+    #[test]
+    fn join_selected_lines_with_separator() {
+        let mut view = View::with_size("a\nb\nc\nd", SIZE);
+        // 没有选区或选区只有一行时不做任何操作
+        assert_eq!(view.join_selected_lines(", "), 0);
+        view.handle_select_command(Move::Right);
+        assert_eq!(view.join_selected_lines(", "), 0);
+        view.handle_select_command(Move::Down);
+        view.handle_select_command(Move::Down);
+        assert_eq!(view.join_selected_lines(", "), 3);
+        assert_eq!(view.buffer.contents(), "a, b, c\nd");
+        assert_eq!(view.selection_anchor, None);
+        assert_eq!(view.text_location(), location(0, 1));
+    }
+
+    #[test]
+    fn reordered_lines_stay_selected() {
+        let mut view = View::with_size("c\nb\na\nz", SIZE);
+        assert_eq!(view.sort_selected_lines(false), 0);
+        view.handle_select_command(Move::Down);
+        view.handle_select_command(Move::Down);
+        view.handle_select_command(Move::Right);
+        assert_eq!(view.sort_selected_lines(false), 3);
+        assert_eq!(view.buffer.contents(), "a\nb\nc\nz");
+        assert_eq!(view.selection_anchor, Some(location(0, 0)));
+        assert_eq!(view.text_location(), location(2, 1));
+        assert_eq!(view.reverse_selected_lines(), 3);
+        assert_eq!(view.buffer.contents(), "c\nb\na\nz");
+    }
+
+    #[test]
+    fn fold_hides_indented_block_and_is_skipped() {
+        let mut view = View::with_size("fn a() {\n    one\n\n    two\n\n}\nend", SIZE);
+        assert!(view.toggle_fold());
+        assert_eq!(view.folds, vec![1..4]);
+        // 折叠只占一行，向下移动时跳过整个折叠
+        view.handle_move_command(Move::Down);
+        assert_eq!(view.text_location(), location(4, 0));
+        assert_eq!(view.caret_position().row, 2);
+        view.handle_move_command(Move::Up);
+        assert_eq!(view.text_location(), location(0, 0));
+        // 在折叠前一行再次执行时展开
+        assert!(view.toggle_fold());
+        assert!(view.folds.is_empty());
+        view.handle_move_command(Move::Down);
+        assert!(!view.toggle_fold());
+    }
+
+    #[test]
+    fn folds_follow_edits_and_unfold_when_entered() {
+        let mut view = View::with_size("a\nb\n  c\n  d\ne", SIZE);
+        view.go_to(location(1, 0));
+        assert!(view.toggle_fold());
+        assert_eq!(view.folds, vec![2..4]);
+        // 在折叠之前增加行，折叠随之移动；修改折叠前一行的内容时折叠保持不变
+        view.go_to(location(0, 1));
+        view.handle_edit_command(Edit::InsertNewline);
+        assert_eq!(view.folds, vec![3..5]);
+        view.go_to(location(2, 1));
+        view.handle_edit_command(Edit::Insert('!'));
+        assert_eq!(view.folds, vec![3..5]);
+        // 删除折叠前一行时折叠被展开
+        view.handle_edit_command(Edit::DeleteLine);
+        assert!(view.folds.is_empty());
+        // 折叠中间的空行也一并折叠
+        view.go_to(location(0, 0));
+        assert!(view.toggle_fold());
+        assert_eq!(view.folds, vec![1..4]);
+        // 光标进入折叠时展开
+        view.go_to(location(2, 0));
+        assert!(view.folds.is_empty());
+    }
+
+    #[test]
+    fn search_next_reports_wrapping_until_next_search() {
+        let mut view = View::with_size("ab\nab", SIZE);
+        view.enter_search();
+        view.search("a");
+        assert!(view.search_wrapped().is_none());
+        view.search_next();
+        assert!(view.search_wrapped().is_none());
+        view.search_next();
+        assert!(matches!(view.search_wrapped(), Some(SearchDirection::Forward)));
+        view.search_prev();
+        assert!(matches!(view.search_wrapped(), Some(SearchDirection::Backward)));
+        // 修改搜索内容后不再提示
+        view.search("b");
+        assert!(view.search_wrapped().is_none());
+    }
+
+    #[test]
+    fn search_next_advances_past_consecutive_matches() {
+        let mut view = View::with_size("aaaa\naa", SIZE);
+        view.enter_search();
+        view.search("aa");
+        assert_eq!(view.text_location(), location(0, 0));
+        view.search_next();
+        assert_eq!(view.text_location(), location(0, 2));
+        view.search_next();
+        assert_eq!(view.text_location(), location(1, 0));
+        view.search_next();
+        assert_eq!(view.text_location(), location(0, 0));
+    }
+
+    #[test]
+    fn search_next_with_wide_graphemes() {
+        let mut view = View::with_size("你好你好", SIZE);
+        view.enter_search();
+        view.search("你好");
+        view.search_next();
+        assert_eq!(view.text_location(), location(0, 2));
+        assert_eq!(view.caret_position(), position(0, 4));
+    }
+
+    #[test]
+    fn scrollbar_thumb_tracks_scroll_position() {
+        // 整个文档都能显示时不绘制滚动条
+        assert_eq!(View::scrollbar_thumb(10, 10, 0), None);
+        assert_eq!(View::scrollbar_thumb(0, 10, 0), None);
+        // 视图显示文档的一半时滑块占一半高度，从最上方移动到最下方
+        assert_eq!(View::scrollbar_thumb(10, 20, 0), Some(0..5));
+        assert_eq!(View::scrollbar_thumb(10, 20, 5), Some(2..7));
+        assert_eq!(View::scrollbar_thumb(10, 20, 10), Some(5..10));
+        assert_eq!(View::scrollbar_thumb(10, 20, 15), Some(5..10));
+        // 很长的文档中滑块至少占一行
+        assert_eq!(View::scrollbar_thumb(10, 10_000, 9_990), Some(9..10));
+    }
+
+    #[test]
+    fn scrollbar_column_is_excluded_from_text_width() {
+        let mut view = View::with_size("abcdefghij", Size { height: 3, width: 10 });
+        view.set_show_scrollbar(true);
+        view.handle_move_command(Move::EndOfLine);
+        assert_eq!(view.scroll_offset(), position(0, 2));
+        assert_eq!(view.caret_position(), position(0, 8));
+    }
+
+    /// 测试用：依次输入字符串中的每个字符
+    fn type_text(view: &mut View, text: &str) {
+        for character in text.chars() {
+            view.handle_edit_command(Edit::Insert(character));
+        }
+    }
+
+    #[test]
+    fn auto_pair_inserts_and_types_over_closing_characters() {
+        let mut view = View::with_size("", SIZE);
+        view.set_auto_pair(true);
+        type_text(&mut view, "f(");
+        assert_eq!(view.current_line_text(), "f()");
+        assert_eq!(view.text_location(), location(0, 2));
+        type_text(&mut view, "\"a\")");
+        assert_eq!(view.current_line_text(), "f(\"a\")");
+        assert_eq!(view.text_location(), location(0, 6));
+    }
+
+    #[test]
+    fn auto_pair_skips_quotes_after_words_and_brackets_before_words() {
+        let mut view = View::with_size("word", SIZE);
+        view.set_auto_pair(true);
+        view.handle_edit_command(Edit::Insert('('));
+        assert_eq!(view.current_line_text(), "(word");
+        view.handle_move_command(Move::EndOfLine);
+        view.handle_edit_command(Edit::Insert('\''));
+        assert_eq!(view.current_line_text(), "(word'");
+    }
+
+    #[test]
+    fn backspace_deletes_empty_pair() {
+        let mut view = View::with_size("", SIZE);
+        view.set_auto_pair(true);
+        type_text(&mut view, "[");
+        view.handle_edit_command(Edit::DeleteBackward);
+        assert_eq!(view.current_line_text(), "");
+        // 关闭自动配对时只删除一个字符
+        view.set_auto_pair(false);
+        type_text(&mut view, "[]");
+        view.handle_move_command(Move::Left);
+        view.handle_edit_command(Edit::DeleteBackward);
+        assert_eq!(view.current_line_text(), "]");
+    }
+
+    #[test]
+    fn selected_text_spans_lines() {
+        let mut view = View::with_size("ab你\ncd\nef", SIZE);
+        assert_eq!(view.selected_text(), None);
+        view.handle_move_command(Move::Right);
+        view.handle_select_command(Move::Down);
+        view.handle_select_command(Move::Down);
+        assert_eq!(view.selected_text().as_deref(), Some("b你\ncd\ne"));
+        view.handle_select_command(Move::Up);
+        view.handle_select_command(Move::Up);
+        assert_eq!(view.selected_text(), None);
+    }
+
+    #[test]
+    fn search_match_position_counts_matches() {
+        let mut view = View::with_size("ab ab\nab", SIZE);
+        view.enter_search();
+        view.search("ab");
+        assert_eq!(view.search_match_position(), Some((1, 3)));
+        view.search_next();
+        assert_eq!(view.search_match_position(), Some((2, 3)));
+        view.search_next();
+        assert_eq!(view.search_match_position(), Some((3, 3)));
+        view.search_prev();
+        assert_eq!(view.search_match_position(), Some((2, 3)));
+    }
+
+    #[test]
+    fn search_without_matches_keeps_caret() {
+        let mut view = View::with_size("ab\ncd", SIZE);
+        view.handle_move_command(Move::Down);
+        view.enter_search();
+        view.search("zz");
+        assert_eq!(view.search_match_position(), Some((0, 0)));
+        assert_eq!(view.text_location(), location(1, 0));
+    }
+}
@@ -0,0 +1,89 @@
+/// 只在文件开头和结尾的这么多行中查找 modeline(与 vim 的默认值相同)
+pub const SCANNED_LINES: usize = 5;
+
+// modeline 的起始标记，标记前必须是行首或空白
+const MARKERS: [&str; 4] = ["vim:", "Vim:", "vi:", "ex:"];
+
+/// 从文件中解析出的 modeline 设置，目前只支持制表符宽度
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Modeline {
+    // tabstop / ts
+    pub tab_width: Option<usize>,
+}
+
+impl Modeline {
+    /// 解析一行中的 modeline。
+    ///
+    /// 支持 vim 的两种格式：
+    /// - `vim: set ts=4 sw=4:`，`set` 之后到下一个冒号为止的选项用空白分隔；
+    /// - `vim:ts=4:sw=4`，选项用冒号或空白分隔。
+    ///
+    /// # 返回值
+    /// 没有 modeline，或其中没有受支持的选项时返回 `None`。无法识别的选项会被忽略。
+    pub fn parse(line: &str) -> Option<Self> {
+        let rest = Self::options_after_marker(line)?.trim_start();
+        let options = if let Some(set_options) = rest
+            .strip_prefix("set ")
+            .or_else(|| rest.strip_prefix("se "))
+        {
+            // 第二种格式以冒号结束，之后的内容不属于 modeline
+            set_options.split(':').next().unwrap_or_default()
+        } else {
+            rest
+        };
+
+        let mut modeline = Self::default();
+        for option in options.split(|character: char| character == ':' || character.is_whitespace()) {
+            if let Some(("ts" | "tabstop", value)) = option.split_once('=') {
+                modeline.tab_width = value.parse().ok().filter(|width| *width > 0);
+            }
+        }
+        (modeline != Self::default()).then_some(modeline)
+    }
+
+    /// 查找 modeline 标记，返回标记之后的内容
+    fn options_after_marker(line: &str) -> Option<&str> {
+        MARKERS.iter().find_map(|marker| {
+            line.match_indices(marker).find_map(|(index, _)| {
+                let after_whitespace = line
+                    .get(..index)
+                    .and_then(|before| before.chars().next_back())
+                    .is_none_or(char::is_whitespace);
+                if after_whitespace {
+                    line.get(index.saturating_add(marker.len())..)
+                } else {
+                    None
+                }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab_width(line: &str) -> Option<usize> {
+        Modeline::parse(line).and_then(|modeline| modeline.tab_width)
+    }
+
+    #[test]
+    fn parses_both_modeline_formats() {
+        assert_eq!(tab_width("vim: ts=4"), Some(4));
+        assert_eq!(tab_width("// vim:ts=2:sw=2"), Some(2));
+        assert_eq!(tab_width("# vim: set tabstop=8 :"), Some(8));
+        // `set` 格式在冒号处结束，之后的内容被忽略
+        assert_eq!(tab_width("/* vi: set ts=3: ts=9 */"), Some(3));
+    }
+
+    #[test]
+    fn rejects_invalid_widths_and_missing_markers() {
+        assert_eq!(Modeline::parse("vim: ts=0"), None);
+        assert_eq!(Modeline::parse("vim: ts=wide"), None);
+        assert_eq!(Modeline::parse("vim: ts=-4"), None);
+        assert_eq!(Modeline::parse("vim: sw=4"), None);
+        // 标记前必须是行首或空白
+        assert_eq!(Modeline::parse("novim: ts=4"), None);
+        assert_eq!(Modeline::parse("ts=4"), None);
+    }
+}
@@ -1,11 +1,17 @@
 use crate::editor::Line;
 use crate::prelude::*;
 
+use super::SearchDirection;
+
 pub struct SearchInfo {
     // 搜索前光标所在文本位置
     pub prev_location: Location,
     // 搜索前view的滚动偏移量
     pub prev_scroll_offset: Position,
     // 搜索内容
-    pub query: Option<Line>
+    pub query: Option<Line>,
+    // 搜索内容的所有匹配位置，只在搜索内容改变时重新计算
+    pub matches: Vec<Location>,
+    // 最近一次查找下一个/上一个匹配项时越过了文档边界的方向，没有越过时为 None
+    pub wrapped: Option<SearchDirection>,
 }
\ No newline at end of file
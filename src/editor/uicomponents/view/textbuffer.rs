@@ -0,0 +1,186 @@
+use std::fmt::{self, Display};
+
+use crate::prelude::*;
+
+use super::{Buffer, Line};
+
+/// 不依赖终端的文本缓冲区：在内存中的文本上按光标位置插入、删除和搜索。
+///
+/// 只包含缓冲区和光标，不会读写标准输出，也不依赖 crossterm，可以在测试和其他工具中单独使用。
+/// 光标位置与编辑器中的含义相同：行索引和行内的字素索引，都从 0 开始。
+#[derive(Default)]
+pub struct TextBuffer {
+    buffer: Buffer,
+    cursor: Location,
+}
+
+impl From<&str> for TextBuffer {
+    /// 从文本创建缓冲区，光标位于文档开头
+    fn from(text: &str) -> Self {
+        Self {
+            buffer: Buffer::from(text),
+            cursor: Location::default(),
+        }
+    }
+}
+
+impl Display for TextBuffer {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.buffer.contents())
+    }
+}
+
+impl TextBuffer {
+    /// 创建空的缓冲区
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 获取光标位置
+    #[must_use]
+    pub const fn cursor(&self) -> Location {
+        self.cursor
+    }
+
+    /// 移动光标，超出文档范围的位置会被限制到最近的有效位置
+    pub fn set_cursor(&mut self, location: Location) {
+        let line_index = location.line_index.min(self.buffer.height());
+        let grapheme_index = location.grapheme_index.min(self.line_grapheme_count(line_index));
+        self.cursor = Location {
+            grapheme_index,
+            line_index,
+        };
+    }
+
+    /// 获取行数
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.buffer.height()
+    }
+
+    /// 获取指定行的内容，行不存在时返回空字符串
+    #[must_use]
+    pub fn line(&self, line_index: LineIdx) -> &str {
+        self.buffer.line_text(line_index)
+    }
+
+    /// 创建之后内容是否被修改过
+    #[must_use]
+    pub const fn is_modified(&self) -> bool {
+        self.buffer.dirty
+    }
+
+    /// 在光标处插入字符，光标移动到插入的字符之后。
+    ///
+    /// 插入的字符与前面的字素组合(如组合用重音符号)时光标不移动；插入 `\n` 与 [`Self::newline`] 相同。
+    pub fn insert(&mut self, character: char) {
+        if character == '\n' {
+            self.newline();
+            return;
+        }
+        let line_index = self.cursor.line_index;
+        let old_len = self.line_grapheme_count(line_index);
+        self.buffer.insert_char(character, self.cursor);
+        let new_len = self.line_grapheme_count(line_index);
+        self.cursor.grapheme_index = self
+            .cursor
+            .grapheme_index
+            .saturating_add(new_len.saturating_sub(old_len));
+    }
+
+    /// 删除光标处的字素，光标位于行尾时将下一行合并到当前行
+    pub fn delete(&mut self) {
+        self.buffer.delete(self.cursor);
+    }
+
+    /// 在光标处换行，光标移动到新行的开头
+    pub fn newline(&mut self) {
+        if self.buffer.insert_newline(self.cursor, false) {
+            self.cursor = Location {
+                grapheme_index: 0,
+                line_index: self.cursor.line_index.saturating_add(1),
+            };
+        }
+    }
+
+    /// 从光标处(包括光标处)向下搜索，到达文档末尾后从开头继续，找到时光标移动到匹配项的开头。
+    ///
+    /// # 返回值
+    /// 返回匹配项的位置，没有匹配项时返回 `None`，光标保持不变。
+    pub fn search(&mut self, query: &str) -> Option<Location> {
+        let (location, _) = self.buffer.search_forward(query, self.cursor)?;
+        self.cursor = location;
+        Some(location)
+    }
+
+    /// 按文档顺序列出查询字符串的所有匹配位置，光标保持不变。
+    ///
+    /// 匹配项不会跨越行，同一行内的匹配项互不重叠；查询字符串为空时返回空列表。
+    #[must_use]
+    pub fn search_all(&self, query: &str) -> Vec<Location> {
+        self.buffer.search_all(query)
+    }
+
+    fn line_grapheme_count(&self, line_index: LineIdx) -> GraphemeIdx {
+        self.buffer
+            .lines
+            .get(line_index)
+            .map_or(0, Line::grapheme_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_newline_move_cursor() {
+        let mut text = TextBuffer::new();
+        for character in "ab\ncd".chars() {
+            text.insert(character);
+        }
+        assert_eq!(text.to_string(), "ab\ncd\n");
+        assert_eq!(text.cursor(), Location { grapheme_index: 2, line_index: 1 });
+        assert!(text.is_modified());
+    }
+
+    #[test]
+    fn combining_character_does_not_move_cursor() {
+        let mut text = TextBuffer::from("");
+        text.insert('e');
+        text.insert('\u{301}');
+        assert_eq!(text.cursor(), Location { grapheme_index: 1, line_index: 0 });
+        assert_eq!(text.line(0), "e\u{301}");
+    }
+
+    #[test]
+    fn delete_at_end_of_line_joins_next_line() {
+        let mut text = TextBuffer::from("你好\nworld");
+        text.set_cursor(Location { grapheme_index: 10, line_index: 0 });
+        assert_eq!(text.cursor(), Location { grapheme_index: 2, line_index: 0 });
+        text.delete();
+        assert_eq!(text.to_string(), "你好world");
+        assert_eq!(text.line_count(), 1);
+    }
+
+    #[test]
+    fn search_moves_cursor_to_match() {
+        let mut text = TextBuffer::from("one two\nthree two");
+        text.set_cursor(Location { grapheme_index: 5, line_index: 0 });
+        assert_eq!(text.search("two"), Some(Location { grapheme_index: 6, line_index: 1 }));
+        assert_eq!(text.cursor(), Location { grapheme_index: 6, line_index: 1 });
+        assert_eq!(text.search("four"), None);
+        assert_eq!(text.cursor(), Location { grapheme_index: 6, line_index: 1 });
+    }
+
+    #[test]
+    fn search_all_lists_matches_without_moving_cursor() {
+        let text = TextBuffer::from("one two\nthree two");
+        assert_eq!(
+            text.search_all("two"),
+            vec![Location { grapheme_index: 4, line_index: 0 }, Location { grapheme_index: 6, line_index: 1 }]
+        );
+        assert_eq!(text.cursor(), Location::default());
+    }
+}
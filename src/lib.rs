@@ -0,0 +1,10 @@
+#![warn(clippy::all, clippy::pedantic, clippy::print_stdout, clippy::arithmetic_side_effects, clippy::as_conversions, clippy::integer_division)]
+//! hecto 编辑器。
+//!
+//! 除了终端编辑器 [`Editor`] 之外，还提供不依赖终端的 [`TextBuffer`]，
+//! 可以在测试和其他工具中复用编辑文本的逻辑。
+pub use editor::{Editor, TextBuffer};
+pub use prelude::Location;
+
+mod editor;
+mod prelude;
@@ -1,9 +1,6 @@
 #![warn(clippy::all, clippy::pedantic, clippy::print_stdout, clippy::arithmetic_side_effects, clippy::as_conversions, clippy::integer_division)]
-use editor::Editor;
-
-mod editor;
-mod prelude;
+use hecto::Editor;
 
 fn main() {
     Editor::new().unwrap().run();
-}
\ No newline at end of file
+}
@@ -1,6 +1,6 @@
 use super::{GraphemeIdx, LineIdx};
 
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
 pub struct Location {
     pub grapheme_index: GraphemeIdx,
     pub line_index: LineIdx,
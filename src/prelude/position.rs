@@ -1,6 +1,6 @@
 use super::{ColIdx,RowIdx};
 
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
 pub struct Position {
     pub row: RowIdx,
     pub col: ColIdx,
@@ -1,5 +1,11 @@
-#[derive(Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Size {
     pub height: usize,
     pub width: usize,
-}
\ No newline at end of file
+}
+impl Size {
+    /// 宽度或高度为 0，没有可以绘制的区域
+    pub const fn is_empty(self) -> bool {
+        self.height == 0 || self.width == 0
+    }
+}